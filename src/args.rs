@@ -0,0 +1,176 @@
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use steamvr_lighthouse_control::power::PowerStateCommand;
+
+/// Exit code for invalid arguments, the same as for other errors of the commands.
+const EXIT_ERROR: i32 = 1;
+
+/// Environment variable that enables simulation mode, as an alternative to `--simulate`.
+const SIMULATE_ENV_VAR: &str = "LIGHTHOUSE_SIMULATE";
+
+/// Command line arguments.
+#[derive(Clone, Debug, Parser)]
+#[command(version, about = "Controls the power state of SteamVR base stations")]
+pub struct Args {
+    /// Print device state changes as JSON lines without a window
+    #[arg(long)]
+    pub watch: bool,
+    /// Show a terminal interface instead of a window
+    #[arg(long)]
+    pub tui: bool,
+    /// Execute commands read from stdin and write their responses and device events to stdout
+    #[arg(long, visible_alias = "stdin")]
+    pub stdio: bool,
+    /// Run without an interface and accept commands over IPC
+    #[arg(long)]
+    pub daemon: bool,
+    /// Open the window in compact mode
+    #[arg(long)]
+    pub minimized: bool,
+    /// Quit when SteamVR exits
+    #[arg(long)]
+    pub steamvr: bool,
+    /// Turn the devices on and start SteamVR once they are ready
+    #[arg(long)]
+    pub launch: bool,
+    /// Print the output of a command as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Wait until the devices report the state set by a power command
+    #[arg(long, global = true)]
+    pub wait_for_state: bool,
+    /// Use simulated devices instead of Bluetooth
+    #[arg(long, value_name = "count", env = SIMULATE_ENV_VAR)]
+    pub simulate: Option<u8>,
+    /// Window title to use instead of the configured one
+    #[arg(long, value_name = "title")]
+    pub title: Option<String>,
+    /// Register the app with SteamVR
+    #[arg(long)]
+    pub register_steamvr: bool,
+    /// Unregister the app from SteamVR
+    #[arg(long)]
+    pub unregister_steamvr: bool,
+    /// Install the background service
+    #[arg(long)]
+    pub install_service: bool,
+    /// Uninstall the background service
+    #[arg(long)]
+    pub uninstall_service: bool,
+    #[command(subcommand)]
+    subcommand: Option<Subcommands>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum Subcommands {
+    /// List the found devices
+    List,
+    /// List the found devices once their power states are known
+    Status,
+    /// Print device status changes as JSON lines until interrupted, also through a daemon
+    Watch,
+    /// Turn devices on
+    On(PowerArgs),
+    /// Put devices in standby
+    Standby(PowerArgs),
+    /// Put devices to sleep
+    Sleep(PowerArgs),
+    /// Print the completion script for a shell
+    Completions { shell: Shell },
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["all", "address"])))]
+struct PowerArgs {
+    /// Every device that is not manual only
+    #[arg(long)]
+    all: bool,
+    /// Address of the device
+    address: Option<String>,
+    /// URL or program to open once the command has succeeded
+    #[arg(long, value_name = "target")]
+    launch: Option<String>,
+}
+
+/// A command that is run without a window, after which the app exits.
+#[derive(Clone, Debug)]
+pub enum CliCommand {
+    /// Lists the found devices.
+    List,
+    /// Lists the found devices once their power states have been read.
+    Status,
+    /// Prints the status records of the devices as they change until interrupted.
+    Watch,
+    Power(PowerStateCommand, Target),
+}
+
+/// Devices that a power command applies to.
+#[derive(Clone, Debug)]
+pub enum Target {
+    /// Every device that is not manual only.
+    All,
+    Device(String),
+}
+
+impl Args {
+    /// Parses the command line. Unknown or incomplete arguments print the usage and exit, rather
+    /// than opening a window that the caller did not ask for.
+    pub fn parse() -> Self {
+        match <Self as Parser>::try_parse() {
+            Ok(args) => args,
+            // --help and --version end up here as well
+            Err(e) => {
+                e.print().ok();
+                std::process::exit(if e.use_stderr() { EXIT_ERROR } else { 0 });
+            }
+        }
+    }
+
+    /// The command to run without a window, if any.
+    pub fn cli_command(&self) -> Option<CliCommand> {
+        let (cmd, power) = match &self.subcommand {
+            Some(Subcommands::List) => return Some(CliCommand::List),
+            Some(Subcommands::Status) => return Some(CliCommand::Status),
+            Some(Subcommands::Watch) => return Some(CliCommand::Watch),
+            Some(Subcommands::On(power)) => (PowerStateCommand::On, power),
+            Some(Subcommands::Standby(power)) => (PowerStateCommand::Standby, power),
+            Some(Subcommands::Sleep(power)) => (PowerStateCommand::Sleep, power),
+            Some(Subcommands::Completions { .. }) | None => return None,
+        };
+
+        let target = match &power.address {
+            Some(addr) if !power.all => Target::Device(addr.clone()),
+            _ => Target::All,
+        };
+
+        Some(CliCommand::Power(cmd, target))
+    }
+
+    /// URL or program to open once a power command has succeeded.
+    pub fn launch_target(&self) -> Option<&str> {
+        match &self.subcommand {
+            Some(
+                Subcommands::On(power) | Subcommands::Standby(power) | Subcommands::Sleep(power),
+            ) => power.launch.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The shell to print the completion script for, if that is all there is to do.
+    pub fn completions(&self) -> Option<Shell> {
+        match self.subcommand {
+            Some(Subcommands::Completions { shell }) => Some(shell),
+            _ => None,
+        }
+    }
+}
+
+/// Prints the completion script for `shell` to stdout.
+pub fn print_completions(shell: Shell) {
+    clap_complete::generate(
+        shell,
+        &mut <Args as CommandFactory>::command(),
+        env!("CARGO_PKG_NAME"),
+        &mut std::io::stdout(),
+    );
+}
@@ -0,0 +1,161 @@
+//! Runs the backend that owns the devices, together with the tasks that automate them and the
+//! endpoints for controlling them.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::Mutex;
+
+use crate::ble::Bluetooth;
+use crate::remote::Remote;
+use crate::simulate::Simulated;
+use crate::state::{AppState, Command};
+use crate::{
+    hooks, ipc, launcher, metrics, osc, schedule, status, steamvr, suspend, update, webhook,
+};
+
+#[cfg(feature = "openvr")]
+use crate::vr;
+
+/// Something that discovers devices, keeps their entries in the state up to date and executes
+/// the commands sent to it.
+pub trait Backend: Send + Sync {
+    /// Executes the commands from `cmd_rx` until it receives [`Command::Shutdown`].
+    fn run(
+        &self,
+        app_state: Arc<Mutex<AppState>>,
+        cmd_rx: Receiver<Command>,
+    ) -> BoxFuture<'static, ()>;
+
+    /// Whether the automation tasks and endpoints run alongside the backend. A backend that
+    /// controls another instance leaves them to that instance.
+    fn automated(&self) -> bool {
+        true
+    }
+}
+
+/// Options for running the backend.
+#[derive(Clone, Default)]
+pub struct BackendOptions {
+    /// Number of simulated devices to use instead of real Bluetooth devices.
+    pub simulate: Option<u8>,
+    /// Print device status changes to stdout.
+    pub print_status: bool,
+    /// Serve the IPC endpoint even if it is not enabled in the config.
+    pub serve_ipc: bool,
+    /// IPC path of a running instance to act as a client of, instead of using Bluetooth.
+    pub remote: Option<String>,
+    /// Run only the backend, without the automation tasks and endpoints, e.g. for a one-shot
+    /// command that should not fire schedules or bind the ports of a running daemon.
+    pub without_automation: bool,
+    /// The app was started by SteamVR, so SteamVR running at startup counts as it starting.
+    pub launched_by_steamvr: bool,
+    /// Backend to use instead of any of the above.
+    pub backend: Option<Arc<dyn Backend>>,
+}
+
+impl BackendOptions {
+    /// The backend selected by these options, Bluetooth unless another one is given.
+    pub fn backend(&self) -> Arc<dyn Backend> {
+        if let Some(backend) = &self.backend {
+            backend.clone()
+        } else if let Some(path) = &self.remote {
+            Arc::new(Remote { path: path.clone() })
+        } else if let Some(count) = self.simulate {
+            Arc::new(Simulated { count })
+        } else {
+            Arc::new(Bluetooth)
+        }
+    }
+}
+
+/// Runs the backend selected by `options` until it receives [`Command::Shutdown`].
+pub async fn run(
+    app_state: Arc<Mutex<AppState>>,
+    options: BackendOptions,
+    cmd_tx: Sender<Command>,
+    cmd_rx: Receiver<Command>,
+) {
+    let backend = options.backend();
+
+    if backend.automated() && !options.without_automation {
+        spawn_tasks(&app_state, &options, cmd_tx).await;
+    }
+
+    backend.run(app_state, cmd_rx).await;
+}
+
+async fn spawn_tasks(
+    app_state: &Arc<Mutex<AppState>>,
+    options: &BackendOptions,
+    cmd_tx: Sender<Command>,
+) {
+    let config = app_state.lock().await.config.clone();
+
+    tokio::task::spawn(steamvr::watch(
+        app_state.clone(),
+        cmd_tx.clone(),
+        options.launched_by_steamvr,
+    ));
+    tokio::task::spawn(launcher::run(app_state.clone(), cmd_tx.clone()));
+    tokio::task::spawn(suspend::watch(app_state.clone(), cmd_tx.clone()));
+    tokio::task::spawn(schedule::run(app_state.clone(), cmd_tx.clone()));
+    tokio::task::spawn(hooks::run(app_state.clone()));
+
+    #[cfg(feature = "openvr")]
+    vr::spawn(app_state.clone(), cmd_tx.clone());
+
+    if config.osc_enabled {
+        let app_state = app_state.clone();
+        let cmd_tx = cmd_tx.clone();
+        let port = config.osc_port;
+        let send_to = config.osc_send_to.clone();
+
+        tokio::task::spawn(async move {
+            if let Err(e) = osc::serve(port, send_to, app_state, cmd_tx).await {
+                log::error!("Could not start OSC endpoint on port {}: {}", port, e);
+            }
+        });
+    }
+
+    if config.ipc_enabled || options.serve_ipc {
+        let path = config.ipc_path.unwrap_or_else(ipc::default_path);
+        let app_state = app_state.clone();
+
+        tokio::task::spawn(async move {
+            if let Err(e) = ipc::serve(path.clone(), app_state, cmd_tx).await {
+                log::error!("Could not start IPC endpoint at {}: {}", path, e);
+            }
+        });
+    }
+
+    if options.print_status || config.status_file.is_some() {
+        tokio::task::spawn(status::run(
+            app_state.clone(),
+            config.status_file.clone(),
+            options.print_status,
+            Duration::from_millis(config.status_interval_ms),
+        ));
+    }
+
+    if !config.webhook_urls.is_empty() {
+        tokio::task::spawn(webhook::run(app_state.clone(), config.webhook_urls.clone()));
+    }
+
+    if config.metrics_enabled || config.overlay_enabled {
+        let app_state = app_state.clone();
+        let port = config.metrics_port;
+
+        tokio::task::spawn(async move {
+            if let Err(e) = metrics::serve(port, app_state).await {
+                log::error!("Could not start HTTP endpoint on port {}: {}", port, e);
+            }
+        });
+    }
+
+    if config.check_for_updates {
+        tokio::task::spawn(update::check(app_state.clone()));
+    }
+}
@@ -0,0 +1,1057 @@
+//! The Bluetooth backend: scanning, polling power states and executing commands.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bleasy::{BDAddr, Characteristic, Device, DeviceEvent, Error, ScanConfig, Scanner};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::backend::Backend;
+use crate::pairing;
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{AppState, Command, CommandError, ErrorState};
+
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const TASK_RESTART_DELAY: Duration = Duration::from_secs(1);
+/// Number of consecutive poll cycles in which every read failed before the adapter is
+/// considered lost.
+const ADAPTER_LOST_FAILED_CYCLES: u32 = 10;
+const ADAPTER_RECOVERY_INTERVAL: Duration = Duration::from_secs(5);
+const CONFIG_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const DIRECT_CONNECT_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+/// How long to wait for the exit power state to be written before stopping anyway.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn start_scan(app_state: Arc<Mutex<AppState>>, clear: bool) {
+    start_scanner(app_state, |app_state| app_state.prepare_scan(clear)).await;
+}
+
+/// Replaces the running scan, if any, with one configured by `prepare`.
+async fn start_scanner(
+    app_state: Arc<Mutex<AppState>>,
+    prepare: impl FnOnce(&mut AppState) -> ScanConfig,
+) {
+    let (mut scanner, scan_config) = {
+        let mut app_state = app_state.lock().await;
+        app_state.stop_scan().await;
+        app_state.scan_finished_at = None;
+
+        let scan_config = prepare(&mut app_state);
+        (
+            mem::replace(&mut app_state.scanner, Scanner::new()),
+            scan_config,
+        )
+    };
+
+    // Starting the scanner can take a while, so the UI is not kept waiting for the lock meanwhile
+    let started_at = Instant::now();
+    let result = scanner.start(scan_config).await;
+
+    {
+        let mut app_state = app_state.lock().await;
+        app_state.scanner = scanner;
+
+        match result {
+            Ok(()) => {
+                app_state.error_state = None;
+                app_state.has_scanned = true;
+                app_state.scan_started_at = Some(started_at);
+                app_state.scan_failures = 0;
+            }
+            Err(e) => {
+                app_state.error_state = Some(ErrorState::StartFailed(format!("{:?}", e)));
+                app_state.scan_failures += 1;
+            }
+        }
+    }
+
+    let mut event_stream = app_state.lock().await.scanner.device_event_stream();
+
+    let scan_task = tokio::task::spawn({
+        let app_state = app_state.clone();
+
+        async move {
+            while let Some(event) = event_stream.next().await {
+                match event {
+                    DeviceEvent::Discovered(device) => {
+                        app_state
+                            .lock()
+                            .await
+                            .insert_device(device.address(), device.clone())
+                            .await;
+                    }
+                    DeviceEvent::Updated(device) => {
+                        if let Some(d) = app_state
+                            .lock()
+                            .await
+                            .device_entries
+                            .get_mut(&device.address())
+                        {
+                            d.name = device.local_name().await;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    });
+
+    app_state.lock().await.set_scan_task(scan_task);
+}
+
+/// The Bluetooth LE backend, used unless another one is selected.
+pub struct Bluetooth;
+
+impl Backend for Bluetooth {
+    fn run(
+        &self,
+        app_state: Arc<Mutex<AppState>>,
+        cmd_rx: Receiver<Command>,
+    ) -> BoxFuture<'static, ()> {
+        Box::pin(run(app_state, cmd_rx))
+    }
+}
+
+async fn run(app_state: Arc<Mutex<AppState>>, cmd_rx: Receiver<Command>) {
+    let config = app_state.lock().await.config.clone();
+
+    if config.direct_connect {
+        tokio::task::spawn(connect_known(app_state.clone(), config.scan_on_startup));
+    } else if config.scan_on_startup {
+        start_scan(app_state.clone(), false).await;
+    } else {
+        app_state.lock().await.show_known_devices();
+    }
+
+    // Shared so that a restarted command task can keep receiving from the same channel
+    let cmd_rx = Arc::new(Mutex::new(cmd_rx));
+
+    let mut poll_task = spawn_poll_task(app_state.clone());
+    let mut cmd_task = spawn_cmd_task(app_state.clone(), cmd_rx.clone());
+
+    // Restart either task if it dies so that the other one keeps running
+    loop {
+        tokio::select! {
+            result = &mut poll_task => {
+                log::error!("Poll task exited unexpectedly: {:?}", result);
+                sleep(TASK_RESTART_DELAY).await;
+                poll_task = spawn_poll_task(app_state.clone());
+            }
+            result = &mut cmd_task => {
+                if result.is_ok() {
+                    // The command task only returns once it has handled a shutdown
+                    poll_task.abort();
+                    return;
+                }
+
+                log::error!("Command task exited unexpectedly: {:?}", result);
+                sleep(TASK_RESTART_DELAY).await;
+                cmd_task = spawn_cmd_task(app_state.clone(), cmd_rx.clone());
+            }
+        }
+    }
+}
+
+fn spawn_poll_task(app_state: Arc<Mutex<AppState>>) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut config_saved_at = Instant::now();
+        let mut failed_cycles = 0;
+        let mut recovery_attempted_at = Instant::now();
+
+        loop {
+            let (
+                devices,
+                power_uuid,
+                channel_uuid,
+                temperature_uuid,
+                acknowledged,
+                timeout,
+                jitter,
+            ) = {
+                let app_state = app_state.lock().await;
+                (
+                    app_state.devices_to_poll(),
+                    app_state.config.power_uuid,
+                    app_state.config.channel_uuid,
+                    app_state.config.temperature_uuid,
+                    app_state.config.acknowledged_writes,
+                    app_state.config.operation_timeout(),
+                    app_state.config.poll_jitter(STATE_POLL_INTERVAL),
+                )
+            };
+
+            let cycle_start = Instant::now();
+            let mut reads_ok = 0;
+            let mut reads_failed = 0;
+            let mut last_error = None;
+            let read_jitter = jitter / devices.len().max(1) as u32;
+
+            for (i, (addr, device)) in devices.into_iter().enumerate() {
+                // Spread the reads out a little so that they don't keep colliding on a busy adapter
+                if i > 0 {
+                    sleep(random_duration(read_jitter)).await;
+                }
+
+                let data =
+                    match power_characteristic(&app_state, &device, power_uuid, timeout).await {
+                        Ok(Some(power)) => timed(timeout, power.read()).await.map(Some),
+                        Ok(None) => Ok(None),
+                        Err(e) => Err(e),
+                    };
+
+                let data = match data {
+                    Err(e) if pairing::is_auth_error(&e) => {
+                        let attempt_pairing =
+                            app_state.lock().await.record_pairing_required(addr, &e);
+
+                        if attempt_pairing {
+                            tokio::task::spawn(pair_device(app_state.clone(), addr));
+                        }
+
+                        None
+                    }
+                    Err(e) => {
+                        last_error = Some(format!("{:?}", e));
+                        None
+                    }
+                    Ok(data) => data,
+                };
+
+                let rssi = device.rssi().await;
+                let state = data.as_ref().map(|data| PowerState::from(data.as_slice()));
+
+                if state.is_some() {
+                    reads_ok += 1;
+                } else {
+                    reads_failed += 1;
+                }
+
+                let correction = app_state.lock().await.record_poll(addr, rssi, state);
+
+                let channel_unknown = app_state
+                    .lock()
+                    .await
+                    .device_entries
+                    .get(&addr)
+                    .is_some_and(|d| d.channel.is_none());
+
+                if state.is_some() && channel_unknown {
+                    if let Some(channel) = read_channel(&device, channel_uuid, timeout).await {
+                        if let Some(d) = app_state.lock().await.device_entries.get_mut(&addr) {
+                            d.channel = Some(channel);
+                        }
+                    }
+                }
+
+                let temperature_due = app_state.lock().await.temperature_due(addr);
+
+                if let (true, true, Some(temperature_uuid)) =
+                    (state.is_some(), temperature_due, temperature_uuid)
+                {
+                    if let Some(temperature) =
+                        read_temperature(&device, temperature_uuid, timeout).await
+                    {
+                        let overheated =
+                            app_state.lock().await.record_temperature(addr, temperature);
+
+                        if let Some(cmd) = overheated {
+                            write_power_state(
+                                &app_state,
+                                &device,
+                                power_uuid,
+                                cmd,
+                                acknowledged,
+                                timeout,
+                            )
+                            .await;
+                        }
+                    }
+                }
+
+                if let Some(cmd) = correction {
+                    log::info!("Restoring {} to {:?}", addr, cmd);
+                    write_power_state(&app_state, &device, power_uuid, cmd, acknowledged, timeout)
+                        .await;
+                }
+            }
+
+            let retries = app_state.lock().await.pending_retries();
+
+            for (addr, device, cmd) in retries {
+                log::info!("Resending {:?} to {}", cmd, addr);
+                write_power_state(&app_state, &device, power_uuid, cmd, acknowledged, timeout)
+                    .await;
+            }
+
+            {
+                let mut app_state = app_state.lock().await;
+                app_state
+                    .poll_stats
+                    .record_cycle(cycle_start.elapsed(), reads_ok, reads_failed);
+                app_state.record_scan_end();
+
+                if reads_ok > 0 {
+                    app_state.last_poll_error = None;
+                    app_state.recovery_attempts = 0;
+                } else if last_error.is_some() {
+                    app_state.last_poll_error = last_error;
+                }
+            }
+
+            if reads_ok > 0 {
+                failed_cycles = 0;
+            } else if reads_failed > 0 {
+                failed_cycles += 1;
+
+                if failed_cycles == ADAPTER_LOST_FAILED_CYCLES {
+                    log::warn!("All reads are failing, the Bluetooth adapter may be unavailable");
+                    app_state.lock().await.adapter_lost = true;
+                }
+            }
+
+            if app_state.lock().await.adapter_unavailable()
+                && recovery_attempted_at.elapsed() >= ADAPTER_RECOVERY_INTERVAL
+            {
+                recover_adapter(&app_state).await;
+                recovery_attempted_at = Instant::now();
+                failed_cycles = 0;
+            }
+
+            // Persist the accumulated on-time and error counts every now and then
+            if config_saved_at.elapsed() >= CONFIG_SAVE_INTERVAL {
+                app_state.lock().await.config.save();
+                config_saved_at = Instant::now();
+            }
+
+            sleep(STATE_POLL_INTERVAL - jitter / 2 + random_duration(jitter)).await;
+        }
+    })
+}
+
+fn spawn_cmd_task(
+    app_state: Arc<Mutex<AppState>>,
+    cmd_rx: Arc<Mutex<Receiver<Command>>>,
+) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let panic_off = app_state.lock().await.panic_off.clone();
+
+        loop {
+            let sleep_timer_at = app_state.lock().await.sleep_timer_at;
+            let mut rx = cmd_rx.lock().await;
+
+            let cmd = tokio::select! {
+                biased;
+
+                _ = panic_off.notified() => {
+                    // Commands queued before the panic would only turn devices back on
+                    while rx.try_recv().is_ok() {}
+                    drop(rx);
+
+                    sleep_all_devices(&app_state).await;
+                    continue;
+                }
+                _ = tokio::time::sleep_until(sleep_timer_at.unwrap_or_else(Instant::now).into()),
+                    if sleep_timer_at.is_some() =>
+                {
+                    drop(rx);
+
+                    let addrs = {
+                        let mut app_state = app_state.lock().await;
+                        app_state.sleep_timer_at = None;
+                        app_state.auto_managed()
+                    };
+
+                    log::info!("Sleep timer finished, putting all base stations to sleep");
+                    change_power_states(&app_state, addrs, PowerStateCommand::Sleep).await;
+                    continue;
+                }
+                cmd = rx.recv() => match cmd {
+                    Some(cmd) => cmd,
+                    None => break,
+                },
+            };
+
+            drop(rx);
+
+            match cmd {
+                Command::StartScan => {
+                    start_scan(app_state.clone(), false).await;
+                }
+                Command::ClearAndScan => {
+                    start_scan(app_state.clone(), true).await;
+                }
+                Command::StopScan => {
+                    app_state.lock().await.stop_scan().await;
+                }
+                Command::ReconnectAll => {
+                    reconnect_all(&app_state).await;
+                }
+                Command::SetSleepTimer(duration) => {
+                    app_state.lock().await.sleep_timer_at =
+                        duration.map(|duration| Instant::now() + duration);
+
+                    match duration {
+                        Some(duration) => {
+                            log::info!("Sleep timer started for {} min", duration.as_secs() / 60)
+                        }
+                        None => log::info!("Sleep timer cancelled"),
+                    }
+                }
+                Command::Shutdown(reply) => {
+                    let (addrs, state) = {
+                        let app_state = app_state.lock().await;
+                        (
+                            app_state.auto_managed(),
+                            app_state.config.exit_power_state.command(),
+                        )
+                    };
+
+                    if let Some(state) = state {
+                        log::info!("Sending {:?} to all base stations before exiting", state);
+
+                        // The exit state is the last chance to change them, so it is sent even
+                        // to devices that have just changed state
+                        let writes = send_power_states(&app_state, addrs, state);
+
+                        if tokio::time::timeout(SHUTDOWN_TIMEOUT, writes)
+                            .await
+                            .is_err()
+                        {
+                            log::warn!("Not all base stations could be reached before exiting");
+                        }
+                    }
+
+                    reply.send(()).ok();
+                    break;
+                }
+                Command::ChangePowerState(addr, state, reply) => {
+                    let respond = |result| {
+                        if let Some(reply) = reply {
+                            reply.send(result).ok();
+                        }
+                    };
+
+                    let (device, power_uuid, acknowledged, timeout) = {
+                        let mut app_state = app_state.lock().await;
+
+                        // Nothing is recorded for a device that is not known
+                        let device = match app_state.ble_devices.get(&addr) {
+                            Some(device) => device.clone(),
+                            None => {
+                                respond(Err(CommandError::UnknownDevice));
+                                continue;
+                            }
+                        };
+
+                        if app_state.in_cooldown(addr, state) {
+                            log::info!(
+                                "Not sending {:?} to {}, it changed state too recently",
+                                state,
+                                addr
+                            );
+                            respond(Err(CommandError::Cooldown));
+                            continue;
+                        }
+
+                        if let Some(d) = app_state.device_entries.get(&addr) {
+                            if !d.accepts(state) {
+                                log::info!(
+                                    "Not sending {:?} to {}, it is not allowed from {}",
+                                    state,
+                                    addr,
+                                    d.power_state
+                                );
+                                respond(Err(CommandError::NotAllowed));
+                                continue;
+                            }
+                        }
+
+                        app_state.record_command(&[addr], state);
+                        app_state.desired_states.insert(addr, state);
+
+                        if let Some(d) = app_state.device_entries.get_mut(&addr) {
+                            d.pending_command = None;
+                        }
+
+                        (
+                            device,
+                            app_state.config.power_uuid,
+                            app_state.config.acknowledged_writes,
+                            app_state.config.operation_timeout(),
+                        )
+                    };
+
+                    let outcome = write_power_state(
+                        &app_state,
+                        &device,
+                        power_uuid,
+                        state,
+                        acknowledged,
+                        timeout,
+                    )
+                    .await;
+
+                    log::info!("Sent {:?} to {}: {:?}", state, addr, outcome);
+
+                    respond(match outcome {
+                        WriteOutcome::Failed => Err(CommandError::WriteFailed),
+                        WriteOutcome::Acknowledged | WriteOutcome::Sent => Ok(()),
+                    });
+                }
+                Command::ChangeAllPowerStates(state) => {
+                    let addrs = app_state.lock().await.auto_managed();
+
+                    change_power_states(&app_state, addrs, state).await;
+                }
+                Command::ChangeGroupPowerState(group, state) => {
+                    let addrs = app_state.lock().await.group_members(&group);
+
+                    change_power_states(&app_state, addrs, state).await;
+                }
+                Command::ChangePowerStates(addrs, state) => {
+                    change_power_states(&app_state, addrs, state).await;
+                }
+                Command::SendPowerStates(addrs, state, reply) => {
+                    send_power_states(&app_state, addrs, state).await;
+                    reply.send(()).ok();
+                }
+                Command::AssignUniqueChannels => {
+                    let (assignments, channel_uuid, timeout) = {
+                        let app_state = app_state.lock().await;
+
+                        let assignments: Vec<_> = app_state
+                            .unique_channel_assignments()
+                            .into_iter()
+                            .filter_map(|(addr, channel)| {
+                                Some((addr, app_state.ble_devices.get(&addr)?.clone(), channel))
+                            })
+                            .collect();
+
+                        (
+                            assignments,
+                            app_state.config.channel_uuid,
+                            app_state.config.operation_timeout(),
+                        )
+                    };
+
+                    for (addr, device, channel) in assignments {
+                        log::info!("Setting channel of {} to {}", addr, channel);
+
+                        if write_channel(&device, channel_uuid, channel, timeout).await {
+                            if let Some(d) = app_state.lock().await.device_entries.get_mut(&addr) {
+                                d.channel = Some(channel);
+                            }
+                        }
+                    }
+                }
+                Command::Pair(addr) => {
+                    if let Some(d) = app_state.lock().await.device_entries.get_mut(&addr) {
+                        d.pairing_attempted = true;
+                    }
+
+                    pair_device(app_state.clone(), addr).await;
+                }
+                Command::Reboot(addr) => {
+                    // The write can take up to the operation timeout, so the lock is not held
+                    // meanwhile
+                    let (device, uuid, value, timeout) = {
+                        let app_state = app_state.lock().await;
+
+                        let (uuid, value) = match app_state.config.reboot_command() {
+                            Some(reboot) => reboot,
+                            None => continue,
+                        };
+
+                        let device = match app_state.ble_devices.get(&addr) {
+                            Some(device) => device.clone(),
+                            None => continue,
+                        };
+
+                        (device, uuid, value, app_state.config.operation_timeout())
+                    };
+
+                    log::warn!("Rebooting {}", addr);
+
+                    if write_reboot(&device, uuid, value, timeout).await {
+                        // The device drops off while it restarts. Treating it as disconnected
+                        // lets the next successful poll restore its desired state.
+                        if let Some(d) = app_state.lock().await.device_entries.get_mut(&addr) {
+                            d.reachable = false;
+                            d.power_state = PowerState::Unknown;
+                            d.pending_command = None;
+                            d.optimistic_hold = None;
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Connects to the known devices by their addresses, and scans for the rest if `scan` is set and
+/// not all of them were found, or if there are no known devices.
+async fn connect_known(app_state: Arc<Mutex<AppState>>, scan: bool) {
+    let addrs = {
+        let mut app_state = app_state.lock().await;
+        app_state.show_known_devices();
+        app_state.direct_addresses()
+    };
+
+    if addrs.is_empty() {
+        if scan {
+            start_scan(app_state, false).await;
+        } else {
+            app_state.lock().await.scan_finished_at = Some(Instant::now());
+        }
+
+        return;
+    }
+
+    log::info!("Connecting to {} known devices", addrs.len());
+
+    start_scanner(app_state.clone(), {
+        let addrs = addrs.clone();
+        move |app_state| app_state.prepare_direct_connect(addrs)
+    })
+    .await;
+
+    while app_state.lock().await.scanner.is_active() {
+        sleep(DIRECT_CONNECT_CHECK_INTERVAL).await;
+    }
+
+    let missing = {
+        let app_state = app_state.lock().await;
+        addrs
+            .iter()
+            .filter(|addr| !app_state.ble_devices.contains_key(addr))
+            .count()
+    };
+
+    if missing > 0 && scan {
+        log::info!("{} known devices were not found, scanning", missing);
+        start_scan(app_state, false).await;
+        return;
+    }
+
+    if missing == 0 {
+        log::info!("Connected to the known devices without scanning");
+    }
+
+    app_state.lock().await.scan_finished_at = Some(Instant::now());
+}
+
+/// Recreates the scanner and rescans, to recover after the adapter has been turned off and on.
+async fn recover_adapter(app_state: &Arc<Mutex<AppState>>) {
+    log::info!("Reinitializing the Bluetooth adapter");
+
+    {
+        let mut app_state = app_state.lock().await;
+        app_state.stop_scan().await;
+        app_state.scanner = Scanner::new();
+        app_state.recovery_attempts += 1;
+    }
+
+    // The devices found with the old scanner are not usable anymore
+    start_scan(app_state.clone(), true).await;
+
+    let mut app_state = app_state.lock().await;
+
+    if app_state.error_state.is_none() {
+        if app_state.adapter_lost {
+            log::info!("Bluetooth adapter is available again");
+        }
+
+        app_state.adapter_lost = false;
+    }
+}
+
+/// Drops and re-establishes the connection to every discovered device, for when reads keep
+/// failing but the devices are still around, e.g. after the computer has been suspended.
+async fn reconnect_all(app_state: &Arc<Mutex<AppState>>) {
+    let (devices, timeout): (Vec<Device>, _) = {
+        let mut app_state = app_state.lock().await;
+
+        // Characteristics from the old connections are not usable anymore
+        app_state.power_characteristics.clear();
+
+        (
+            app_state.ble_devices.values().cloned().collect(),
+            app_state.config.operation_timeout(),
+        )
+    };
+
+    log::info!("Reconnecting to {} devices", devices.len());
+
+    let mut reconnected = 0;
+
+    for device in &devices {
+        if let Err(e) = timed(timeout, device.disconnect()).await {
+            log::debug!("Could not disconnect from {}: {:?}", device.address(), e);
+        }
+
+        match timed(timeout, device.characteristics()).await {
+            Ok(_) => {
+                log::info!("Reconnected to {}", device.address());
+                reconnected += 1;
+            }
+            Err(e) => log::warn!("Could not reconnect to {}: {:?}", device.address(), e),
+        }
+    }
+
+    log::info!(
+        "Reconnected to {} of {} devices",
+        reconnected,
+        devices.len()
+    );
+}
+
+/// Sends sleep to every device, including the ones that are believed to be asleep already.
+async fn sleep_all_devices(app_state: &Arc<Mutex<AppState>>) {
+    log::warn!("Panic off: sleeping all devices");
+
+    let (devices, power_uuid, acknowledged, timeout): (Vec<Device>, _, _, _) = {
+        let mut app_state = app_state.lock().await;
+
+        let addrs: Vec<_> = app_state.ble_devices.keys().copied().collect();
+        app_state
+            .desired_states
+            .extend(addrs.iter().map(|addr| (*addr, PowerStateCommand::Sleep)));
+        app_state.record_command(&addrs, PowerStateCommand::Sleep);
+        app_state.track_pending(&addrs, PowerStateCommand::Sleep);
+
+        (
+            app_state.ble_devices.values().cloned().collect(),
+            app_state.config.power_uuid,
+            app_state.config.acknowledged_writes,
+            app_state.config.operation_timeout(),
+        )
+    };
+
+    for device in devices {
+        write_power_state(
+            app_state,
+            &device,
+            power_uuid,
+            PowerStateCommand::Sleep,
+            acknowledged,
+            timeout,
+        )
+        .await;
+    }
+}
+
+/// Sends `state` to each of `addrs` that is not already in the target state. Devices that do not
+/// confirm the change are retried by the poll task.
+async fn change_power_states(
+    app_state: &Arc<Mutex<AppState>>,
+    addrs: Vec<BDAddr>,
+    state: PowerStateCommand,
+) {
+    let addrs = {
+        let app_state = app_state.lock().await;
+
+        let (addrs, cooling): (Vec<_>, Vec<_>) = addrs
+            .into_iter()
+            .partition(|addr| !app_state.in_cooldown(*addr, state));
+
+        for addr in cooling {
+            log::info!(
+                "Not sending {:?} to {}, it changed state too recently",
+                state,
+                addr
+            );
+        }
+
+        addrs
+    };
+
+    send_power_states(app_state, addrs, state).await;
+}
+
+/// Like `change_power_states`, but ignores the cooldown.
+async fn send_power_states(
+    app_state: &Arc<Mutex<AppState>>,
+    addrs: Vec<BDAddr>,
+    state: PowerStateCommand,
+) {
+    let (devices, power_uuid, acknowledged, timeout) = {
+        let mut app_state = app_state.lock().await;
+
+        app_state
+            .desired_states
+            .extend(addrs.iter().map(|addr| (*addr, state)));
+
+        let devices = app_state.devices_not_in(&addrs, state.target_state());
+        let pending: Vec<_> = devices.iter().map(|d| d.address()).collect();
+        app_state.record_command(&pending, state);
+        app_state.track_pending(&pending, state);
+
+        (
+            devices,
+            app_state.config.power_uuid,
+            app_state.config.acknowledged_writes,
+            app_state.config.operation_timeout(),
+        )
+    };
+
+    for device in devices {
+        write_power_state(app_state, &device, power_uuid, state, acknowledged, timeout).await;
+    }
+}
+
+async fn pair_device(app_state: Arc<Mutex<AppState>>, addr: BDAddr) {
+    log::info!("Pairing with {}", addr);
+
+    match pairing::pair(addr).await {
+        Ok(()) => {
+            log::info!("Paired with {}", addr);
+
+            let mut app_state = app_state.lock().await;
+
+            if let Some(device) = app_state.device_entries.get_mut(&addr) {
+                device.pairing_required = false;
+            }
+
+            if !app_state.config.paired_devices.contains(&addr.to_string()) {
+                app_state.config.paired_devices.push(addr.to_string());
+                app_state.config.save();
+            }
+        }
+        Err(e) => log::error!("Pairing with {} failed: {}", addr, e),
+    }
+}
+
+async fn read_channel(device: &Device, channel_uuid: Uuid, timeout: Duration) -> Option<u8> {
+    let characteristic = timed(timeout, device.characteristic(channel_uuid))
+        .await
+        .ok()??;
+
+    match timed(timeout, characteristic.read()).await {
+        Ok(data) => data.first().copied(),
+        Err(e) => {
+            log::warn!("Could not read channel of {}: {:?}", device.address(), e);
+            None
+        }
+    }
+}
+
+/// Reads the temperature in °C. See `Config::temperature_uuid` for the expected format.
+async fn read_temperature(
+    device: &Device,
+    temperature_uuid: Uuid,
+    timeout: Duration,
+) -> Option<f32> {
+    let characteristic = timed(timeout, device.characteristic(temperature_uuid))
+        .await
+        .ok()??;
+
+    match timed(timeout, characteristic.read()).await {
+        Ok(data) => match data.as_slice() {
+            [low, high, ..] => Some(i16::from_le_bytes([*low, *high]) as f32 / 100.0),
+            _ => {
+                log::warn!(
+                    "Unexpected temperature value from {}: {:?}",
+                    device.address(),
+                    data
+                );
+                None
+            }
+        },
+        Err(e) => {
+            log::warn!(
+                "Could not read temperature of {}: {:?}",
+                device.address(),
+                e
+            );
+            None
+        }
+    }
+}
+
+async fn write_channel(
+    device: &Device,
+    channel_uuid: Uuid,
+    channel: u8,
+    timeout: Duration,
+) -> bool {
+    match timed(timeout, device.characteristic(channel_uuid)).await {
+        Ok(Some(characteristic)) => {
+            match timed(timeout, characteristic.write_request(&[channel])).await {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("Could not set channel of {}: {:?}", device.address(), e);
+                    false
+                }
+            }
+        }
+        _ => {
+            log::error!("{} has no channel characteristic", device.address());
+            false
+        }
+    }
+}
+
+async fn write_reboot(device: &Device, reboot_uuid: Uuid, value: u8, timeout: Duration) -> bool {
+    match timed(timeout, device.characteristic(reboot_uuid)).await {
+        Ok(Some(characteristic)) => {
+            match timed(timeout, characteristic.write_request(&[value])).await {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("Could not reboot {}: {:?}", device.address(), e);
+                    false
+                }
+            }
+        }
+        _ => {
+            log::error!("{} has no reboot characteristic", device.address());
+            false
+        }
+    }
+}
+
+/// Returns the power characteristic of `device`, resolving it on first use.
+async fn power_characteristic(
+    app_state: &Arc<Mutex<AppState>>,
+    device: &Device,
+    power_uuid: Uuid,
+    timeout: Duration,
+) -> Result<Option<Characteristic>, Error> {
+    let addr = device.address();
+
+    if let Some(power) = app_state.lock().await.power_characteristics.get(&addr) {
+        return Ok(Some(power.clone()));
+    }
+
+    let power = resolve_power_characteristic(device, power_uuid, timeout).await?;
+
+    if let Some(power) = &power {
+        app_state
+            .lock()
+            .await
+            .power_characteristics
+            .insert(addr, power.clone());
+    }
+
+    Ok(power)
+}
+
+/// Finds the characteristic with `power_uuid`. Some firmware revisions expose more than one, in
+/// which case the one whose value reads as a known power state is used.
+async fn resolve_power_characteristic(
+    device: &Device,
+    power_uuid: Uuid,
+    timeout: Duration,
+) -> Result<Option<Characteristic>, Error> {
+    let candidates: Vec<_> = timed(timeout, device.characteristics())
+        .await?
+        .into_iter()
+        .filter(|c| c.uuid() == power_uuid)
+        .collect();
+
+    if candidates.len() > 1 {
+        log::warn!(
+            "{} has {} power characteristics, looking for the one with a valid state",
+            device.address(),
+            candidates.len()
+        );
+
+        for (i, candidate) in candidates.iter().enumerate() {
+            match timed(timeout, candidate.read()).await {
+                Ok(data) if PowerState::from(data.as_slice()) != PowerState::Unknown => {
+                    log::info!("Using power characteristic {} of {}", i, device.address());
+                    return Ok(Some(candidate.clone()));
+                }
+                Ok(data) => log::info!("Power characteristic {} reads {:?}", i, data),
+                Err(e) => log::info!("Power characteristic {} is not readable: {:?}", i, e),
+            }
+        }
+    }
+
+    Ok(candidates.into_iter().next())
+}
+
+/// How a power command write ended.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum WriteOutcome {
+    /// The device confirmed receiving the command.
+    Acknowledged,
+    /// The command was sent without asking for a response.
+    Sent,
+    Failed,
+}
+
+/// Writes `state` to the power characteristic. With `acknowledged`, the write asks for a
+/// response, and falls back to a write without response if the device does not support it.
+async fn write_power_state(
+    app_state: &Arc<Mutex<AppState>>,
+    device: &Device,
+    power_uuid: Uuid,
+    state: PowerStateCommand,
+    acknowledged: bool,
+    timeout: Duration,
+) -> WriteOutcome {
+    let power = match power_characteristic(app_state, device, power_uuid, timeout).await {
+        Ok(Some(power)) => power,
+        _ => {
+            app_state
+                .lock()
+                .await
+                .error_counts(device.address())
+                .write_failures += 1;
+            return WriteOutcome::Failed;
+        }
+    };
+
+    if acknowledged {
+        match timed(timeout, power.write_request(&[state.into()])).await {
+            Ok(()) => return WriteOutcome::Acknowledged,
+            Err(e) => log::info!(
+                "{} did not acknowledge the write, sending without response: {:?}",
+                device.address(),
+                e
+            ),
+        }
+    }
+
+    match timed(timeout, power.write_command(&[state.into()])).await {
+        Ok(()) => WriteOutcome::Sent,
+        Err(e) => {
+            log::error!("Could not send command to device: {:?}", e);
+            app_state
+                .lock()
+                .await
+                .error_counts(device.address())
+                .write_failures += 1;
+            WriteOutcome::Failed
+        }
+    }
+}
+
+/// A random duration between zero and `max`.
+fn random_duration(max: Duration) -> Duration {
+    // Every `RandomState` is seeded differently, which is random enough for spreading out reads
+    let random = RandomState::new().build_hasher().finish();
+
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+/// Runs a Bluetooth operation, failing it if it does not finish within `timeout`. Without this, a
+/// device that is powered but not responding could stall the task using it indefinitely.
+async fn timed<T>(
+    timeout: Duration,
+    operation: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::time::timeout(timeout, operation)
+        .await
+        .unwrap_or(Err(Error::TimedOut(timeout)))
+}
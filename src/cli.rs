@@ -0,0 +1,310 @@
+//! One-shot commands for scripts: runs a single command and exits. The command is forwarded to a
+//! running daemon if there is one, otherwise the devices are scanned for first.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use steamvr_lighthouse_control::state::{AppState, Command};
+use steamvr_lighthouse_control::{
+    ipc, launcher, status, BDAddr, Config, LighthouseController, PowerState,
+};
+use tokio::sync::Mutex;
+
+use crate::args::{CliCommand, Target};
+
+/// How long to wait for a scan to finish. A continuous scan never does, so its results so far
+/// are used after this.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long to wait for the power states to be read after the scan.
+const POLL_TIMEOUT: Duration = Duration::from_secs(10);
+const CHECK_INTERVAL: Duration = Duration::from_millis(200);
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Exit codes, documented in the README.
+const EXIT_OK: i32 = 0;
+/// Any other error, such as an invalid address.
+const EXIT_ERROR: i32 = 1;
+const EXIT_NOT_FOUND: i32 = 2;
+const EXIT_ADAPTER_UNAVAILABLE: i32 = 3;
+/// The command could not be written to a device, or was refused because of the cooldown or the
+/// state of the device.
+const EXIT_WRITE_FAILED: i32 = 4;
+/// A device did not report the requested state in time.
+const EXIT_TIMEOUT: i32 = 5;
+
+/// Runs `command` on a daemon listening on the configured IPC path. Returns `None` if there is
+/// none, or the exit code of the process.
+pub fn run_remote(
+    config: &Config,
+    command: &CliCommand,
+    json: bool,
+    wait: bool,
+    launch: Option<&str>,
+) -> Option<i32> {
+    let path = config.ipc_path.clone().unwrap_or_else(ipc::default_path);
+    let mut client = ipc::Client::connect(&path).ok()?;
+
+    log::debug!("Forwarding the command to the daemon at {}", path);
+
+    Some(execute(command, json, wait, launch, |line| {
+        client
+            .request(line)
+            .unwrap_or_else(|e| json!({ "ok": false, "error": e.to_string() }))
+    }))
+}
+
+/// Scans for the devices, runs `command` and returns the exit code of the process. With `json`
+/// set the output is a JSON array of device records, or an object with an `error` if the command
+/// could not be run. With `wait` set, power commands only succeed once the devices report the
+/// requested state. `launch` is opened once a power command has succeeded.
+pub fn run(
+    controller: &LighthouseController,
+    command: &CliCommand,
+    json: bool,
+    wait: bool,
+    launch: Option<&str>,
+) -> i32 {
+    let app_state = controller.state();
+    let cmd_tx = controller.command_sender();
+    let runtime = controller.runtime();
+
+    let target = match target_addr(command) {
+        Ok(target) => target,
+        Err(e) => return fail(json, EXIT_ERROR, &e),
+    };
+
+    // Only a scan that finishes after this one was asked for counts
+    let mut scan_requested_at = None;
+
+    if !app_state.blocking_lock().config.scan_on_startup {
+        scan_requested_at = Some(Instant::now());
+        cmd_tx.blocking_send(Command::StartScan).ok();
+    }
+
+    // Devices found during the scan are printed as they appear
+    if !matches!(command, CliCommand::Watch) {
+        wait_for_scan(&app_state, target, scan_requested_at);
+    }
+
+    // Power commands are only accepted once the state they change from is known
+    if matches!(command, CliCommand::Status | CliCommand::Power(..)) {
+        wait_for_states(&app_state);
+    }
+
+    execute(command, json, wait, launch, |line| {
+        runtime.block_on(ipc::handle_request(line, &app_state, &cmd_tx))
+    })
+}
+
+/// Runs `command` as an IPC request and prints the response.
+fn execute(
+    command: &CliCommand,
+    json: bool,
+    wait: bool,
+    launch: Option<&str>,
+    mut request: impl FnMut(&str) -> Value,
+) -> i32 {
+    if let Err(e) = target_addr(command) {
+        return fail(json, EXIT_ERROR, &e);
+    }
+
+    let line = match command {
+        CliCommand::Watch => return watch(json, request),
+        CliCommand::List | CliCommand::Status => "list".to_string(),
+        CliCommand::Power(cmd, target) => format!(
+            "power {} {:?}{}",
+            match target {
+                Target::All => "all",
+                Target::Device(addr) => addr,
+            },
+            cmd,
+            if wait { " wait" } else { "" }
+        ),
+    };
+
+    let response = request(&line);
+
+    let devices = match response["devices"].as_array() {
+        Some(devices) => devices,
+        None => {
+            let error = response["error"].as_str().unwrap_or("Unknown error");
+            return fail(json, exit_code(&response["code"]), error);
+        }
+    };
+
+    if response["adapter_available"] == false && devices.is_empty() {
+        return fail(
+            json,
+            EXIT_ADAPTER_UNAVAILABLE,
+            "Bluetooth adapter unavailable",
+        );
+    }
+
+    if json {
+        println!("{}", Value::Array(devices.clone()));
+    } else if let CliCommand::Power(cmd, _) = command {
+        for device in devices {
+            let addr = device["address"].as_str().unwrap_or("?");
+
+            match device["error"].as_str() {
+                None => println!("{} {:?}", addr, cmd),
+                Some(e) => eprintln!("{}: {}", addr, e),
+            }
+        }
+    } else {
+        print_devices(devices);
+    }
+
+    if response["ok"].as_bool() == Some(true) {
+        if let (CliCommand::Power(..), Some(target)) = (command, launch) {
+            log::info!("Starting {}", target);
+
+            if let Err(e) = launcher::open(target) {
+                log::error!("Could not start {}: {}", target, e);
+                return EXIT_ERROR;
+            }
+        }
+
+        return EXIT_OK;
+    }
+
+    // The first failed device decides, as a script can only branch on one reason
+    devices
+        .iter()
+        .find(|device| device["ok"] == false)
+        .map_or(EXIT_ERROR, |device| exit_code(&device["code"]))
+}
+
+/// Prints the status record of every device that is found or changes, in the same format as
+/// `--watch`. Only returns if the devices can't be listed anymore, e.g. because the daemon has
+/// stopped.
+fn watch(json: bool, mut request: impl FnMut(&str) -> Value) -> i32 {
+    let mut last_records = Vec::new();
+
+    loop {
+        let response = request("list");
+
+        let records = match response["devices"].as_array() {
+            Some(devices) => devices.clone(),
+            None => {
+                let error = response["error"].as_str().unwrap_or("Unknown error");
+                return fail(json, exit_code(&response["code"]), error);
+            }
+        };
+
+        for record in status::changed_records(&records, &last_records) {
+            println!("{}", record);
+        }
+
+        last_records = records;
+
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// Returns the exit code for the `code` of a failed IPC response.
+fn exit_code(code: &Value) -> i32 {
+    match code.as_str() {
+        Some("not_found") => EXIT_NOT_FOUND,
+        Some("adapter_unavailable") => EXIT_ADAPTER_UNAVAILABLE,
+        Some("write_failed" | "cooldown" | "not_allowed") => EXIT_WRITE_FAILED,
+        Some("timeout") => EXIT_TIMEOUT,
+        _ => EXIT_ERROR,
+    }
+}
+
+/// Returns the address of the device the command targets, if it targets a single device.
+fn target_addr(command: &CliCommand) -> Result<Option<BDAddr>, String> {
+    match command {
+        CliCommand::Power(_, Target::Device(addr)) => BDAddr::from_str(addr)
+            .map(Some)
+            .map_err(|_| format!("Invalid address: {}", addr)),
+        _ => Ok(None),
+    }
+}
+
+/// Reports an error that kept the command from running and returns `code`.
+fn fail(json: bool, code: i32, message: &str) -> i32 {
+    if json {
+        println!("{}", json!({ "error": message }));
+    } else {
+        eprintln!("{}", message);
+    }
+
+    code
+}
+
+/// Waits until the scan has finished, or the target device has been found if there is one. A scan
+/// only counts if it finished after `requested_at`. Returns right away if there is no usable
+/// Bluetooth adapter, as nothing can be found then.
+fn wait_for_scan(
+    app_state: &Arc<Mutex<AppState>>,
+    target: Option<BDAddr>,
+    requested_at: Option<Instant>,
+) {
+    let started_at = Instant::now();
+
+    while started_at.elapsed() < SCAN_TIMEOUT {
+        {
+            let app_state = app_state.blocking_lock();
+
+            if app_state.adapter_unavailable() {
+                return;
+            }
+
+            if target.is_some_and(|addr| app_state.device_entries.contains_key(&addr)) {
+                return;
+            }
+
+            let finished = app_state
+                .scan_finished_at
+                .is_some_and(|at| requested_at.is_none_or(|requested_at| at >= requested_at));
+
+            if finished {
+                return;
+            }
+        }
+
+        std::thread::sleep(CHECK_INTERVAL);
+    }
+}
+
+/// Waits until the power state of every found device is known.
+fn wait_for_states(app_state: &Arc<Mutex<AppState>>) {
+    let started_at = Instant::now();
+
+    while started_at.elapsed() < POLL_TIMEOUT {
+        let polled = app_state
+            .blocking_lock()
+            .device_entries
+            .values()
+            .all(|d| d.power_state != PowerState::Unknown || d.last_polled_at.is_some());
+
+        if polled {
+            return;
+        }
+
+        std::thread::sleep(CHECK_INTERVAL);
+    }
+}
+
+/// Prints the device records of a `list` response as a table.
+fn print_devices(devices: &[Value]) {
+    for device in devices {
+        println!(
+            "{}  {:<20} {:<9} {}",
+            device["address"].as_str().unwrap_or("?"),
+            device["alias"]
+                .as_str()
+                .or_else(|| device["name"].as_str())
+                .unwrap_or("?"),
+            device["state"].as_str().unwrap_or("?"),
+            device["rssi"]
+                .as_i64()
+                .map(|rssi| format!("{} dBm", rssi))
+                .unwrap_or_default()
+        );
+    }
+}
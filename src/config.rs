@@ -0,0 +1,585 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::power::PowerStateCommand;
+
+const CONFIG_DIR_NAME: &str = "steamvr-lighthouse-control";
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Power characteristic of Valve's 2.0 base stations.
+pub const VALVE_POWER_UUID: Uuid = Uuid::from_u128(0x00001525_1212_EFDE_1523_785FEABCD124);
+/// Channel (mode) characteristic of Valve's 2.0 base stations.
+pub const VALVE_CHANNEL_UUID: Uuid = Uuid::from_u128(0x00001524_1212_EFDE_1523_785FEABCD124);
+
+/// Name prefix of Valve's 2.0 base stations.
+pub const VALVE_NAME_PREFIX: &str = "LHB-";
+
+/// How scanned devices are recognized as base stations.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanFilter {
+    /// Connects to each device during the scan and checks for the power characteristic.
+    Characteristic,
+    /// Matches the advertised name against `scan_name_prefix`. The power characteristic is only
+    /// resolved when the device is polled.
+    Name,
+}
+
+/// What happens when devices are turned off from the UI while SteamVR is running, which would
+/// stop tracking mid-session.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SteamVrInterlock {
+    Off,
+    /// Asks for confirmation first.
+    Confirm,
+    /// Refuses the change.
+    Block,
+}
+
+/// What happens to the base stations when the app is closed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitPowerState {
+    /// Leaves them as they are.
+    Unchanged,
+    Standby,
+    Sleep,
+}
+
+impl ExitPowerState {
+    pub fn command(self) -> Option<PowerStateCommand> {
+        match self {
+            ExitPowerState::Unchanged => None,
+            ExitPowerState::Standby => Some(PowerStateCommand::Standby),
+            ExitPowerState::Sleep => Some(PowerStateCommand::Sleep),
+        }
+    }
+}
+
+/// What happens to the base stations when the computer resumes from suspend.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumeAction {
+    /// Leaves them asleep.
+    Nothing,
+    /// Turns on the ones that are not manual only.
+    TurnOn,
+    /// Puts each one back into the state it was in before the suspend.
+    Restore,
+}
+
+/// A power change made at a set time of day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub enabled: bool,
+    /// Local time of day as `HH:MM`.
+    pub time: String,
+    /// Days of the week that the change is made on, 1 being Monday. Every day if empty.
+    pub days: Vec<u8>,
+    pub command: PowerStateCommand,
+    /// Group to change. Every device that is not manual only if unset.
+    pub group: Option<String>,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            time: "23:00".to_string(),
+            days: Vec::new(),
+            command: PowerStateCommand::Sleep,
+            group: None,
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the time of day in minutes after midnight, if `time` is valid.
+    pub fn minute_of_day(&self) -> Option<u32> {
+        let (hours, minutes) = self.time.trim().split_once(':')?;
+        let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+
+        (hours < 24 && minutes < 60).then(|| hours * 60 + minutes)
+    }
+
+    /// Returns true if the change is due at `minute_of_day` on `weekday`, 1 being Monday.
+    pub fn is_due(&self, weekday: u8, minute_of_day: u32) -> bool {
+        self.enabled
+            && self.minute_of_day() == Some(minute_of_day)
+            && (self.days.is_empty() || self.days.contains(&weekday))
+    }
+}
+
+/// A shell command that is run when a device changes state. The device is passed to it in the
+/// `LIGHTHOUSE_ADDRESS`, `LIGHTHOUSE_NAME`, `LIGHTHOUSE_STATE` and `LIGHTHOUSE_OLD_STATE`
+/// environment variables.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hook {
+    pub command: String,
+    /// State that a device has to change to. Every change runs the command if unset.
+    pub state: Option<PowerStateCommand>,
+    /// Runs the command once every device that is not manual only has reached `state`, instead
+    /// of for each device.
+    pub all: bool,
+}
+
+/// Errors seen with a device across all sessions.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorCounts {
+    pub read_failures: u64,
+    pub write_failures: u64,
+    /// Times the device became reachable again after failed reads.
+    pub reconnects: u64,
+}
+
+/// User settings persisted between sessions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub compact_mode: bool,
+    pub always_on_top: bool,
+    pub window_size: [f32; 2],
+    pub compact_window_size: [f32; 2],
+    /// Enables the local IPC endpoint (Unix socket or named pipe).
+    pub ipc_enabled: bool,
+    /// Socket path or pipe name. Uses a platform default when unset.
+    pub ipc_path: Option<String>,
+    /// Serves the IPC endpoint in every instance, so that instances started later hand over to it
+    /// instead of using the Bluetooth adapter as well. Off by default, like the endpoint itself.
+    pub single_instance: bool,
+    /// User defined device names, keyed by address.
+    pub aliases: HashMap<String, String>,
+    /// Device groups (rooms), keyed by address.
+    pub groups: HashMap<String, String>,
+    /// File to write the device status JSON to whenever it changes.
+    pub status_file: Option<String>,
+    /// How often the device status is checked for changes, in milliseconds.
+    pub status_interval_ms: u64,
+    /// Re-sends the last commanded power state to devices that reconnect in a different state.
+    pub restore_desired_state: bool,
+    /// Skips polling already known devices while a scan is active.
+    pub throttle_polling_while_scanning: bool,
+    /// Enables the keyboard shortcut that sleeps every device without confirmation.
+    pub panic_off_shortcut: bool,
+    /// Accumulated time each device has spent in the `On` state, keyed by address.
+    pub on_time_secs: HashMap<String, f64>,
+    /// Errors seen with each device, keyed by address.
+    pub error_counts: HashMap<String, ErrorCounts>,
+    /// Addresses of devices that have been paired successfully.
+    pub paired_devices: Vec<String>,
+    /// Characteristic used for reading and writing the power state.
+    pub power_uuid: Uuid,
+    /// Characteristic used for reading and writing the RF channel.
+    pub channel_uuid: Uuid,
+    /// Reads focused widgets and device state changes out loud.
+    pub screen_reader: bool,
+    /// Address of the device controlled by the keyboard shortcuts.
+    pub selected_device: Option<String>,
+    /// Characteristic that reboots the device when `reboot_value` is written to it. Rebooting is
+    /// only offered when both are set.
+    pub reboot_uuid: Option<Uuid>,
+    pub reboot_value: Option<u8>,
+    /// Characteristic holding the device temperature as a signed 16-bit little-endian value in
+    /// hundredths of a degree Celsius, like the standard Temperature characteristic. Temperatures
+    /// are only read and shown when this is set.
+    pub temperature_uuid: Option<Uuid>,
+    /// Temperature in °C above which it is shown as a warning.
+    pub temperature_warning: f32,
+    /// Puts devices in standby when they reach `overheat_temperature`.
+    pub overheat_standby: bool,
+    pub overheat_temperature: f32,
+    pub scan_filter: ScanFilter,
+    /// Name prefix used with `ScanFilter::Name`.
+    pub scan_name_prefix: String,
+    /// Looks for a newer release on GitHub at startup.
+    pub check_for_updates: bool,
+    /// Starts scanning as soon as the app is launched.
+    pub scan_on_startup: bool,
+    /// Advertised names of previously discovered devices, keyed by address.
+    pub known_devices: HashMap<String, String>,
+    /// Connects to the known devices by their addresses at startup instead of scanning, falling
+    /// back to a scan if any of them is not found.
+    pub direct_connect: bool,
+    /// Addresses of devices to connect to directly even if they have not been discovered yet.
+    pub device_addresses: Vec<String>,
+    /// Keeps scanning until stopped instead of stopping after a timeout.
+    pub continuous_scan: bool,
+    /// Addresses of devices that are left out of group and automatic power changes.
+    pub manual_only_devices: Vec<String>,
+    /// Addresses of devices that are shown first in the device list.
+    pub pinned_devices: Vec<String>,
+    /// Sends power commands as writes with response, so that the device acknowledges them.
+    pub acknowledged_writes: bool,
+    /// Receives power commands over OSC (UDP) on `osc_port`.
+    pub osc_enabled: bool,
+    pub osc_port: u16,
+    /// Address to send the device states to over OSC, e.g. `127.0.0.1:9000`.
+    pub osc_send_to: Option<String>,
+    /// Serves Prometheus metrics over HTTP at `/metrics`.
+    pub metrics_enabled: bool,
+    pub metrics_port: u16,
+    /// Serves a status overlay page for streaming at `/overlay`, on the same port as the metrics.
+    pub overlay_enabled: bool,
+    /// CSS file served to the overlay page for restyling it.
+    pub overlay_css: Option<String>,
+    /// URLs that are sent a POST request whenever a device changes state.
+    pub webhook_urls: Vec<String>,
+    /// Time limit for a single Bluetooth connect, read or write.
+    pub operation_timeout_ms: u64,
+    /// Minimum time between changes of a device's state, to avoid cycling the lasers rapidly.
+    pub power_cooldown_secs: u64,
+    /// Time a device has to have been on before it is considered ready to track. Some base
+    /// stations report being on a moment before they actually are.
+    pub warm_up_secs: u64,
+    /// Random variation of the poll timing in percent of the poll interval, so that the reads of
+    /// different devices spread out instead of repeatedly colliding.
+    pub poll_jitter_percent: u32,
+    /// Language code of the UI strings.
+    pub language: String,
+    /// Window title, e.g. including the room or profile name. The default title is used if empty.
+    pub window_title: String,
+    /// Turns all devices on when SteamVR starts.
+    pub power_on_with_steamvr: bool,
+    /// Along with `power_on_with_steamvr`, turns all devices on when the headset is put on and puts
+    /// them in standby when it has been taken off for `hmd_idle_mins`. Needs the `openvr` feature.
+    pub wake_on_hmd_proximity: bool,
+    /// Puts all devices to sleep when SteamVR has been closed for `steamvr_sleep_delay_mins`.
+    pub sleep_after_steamvr: bool,
+    pub steamvr_sleep_delay_mins: u64,
+    /// Puts all devices in standby when the headset has not been used for `hmd_idle_mins`, and
+    /// turns them on again when it is. Needs the `openvr` feature.
+    pub hmd_idle_standby: bool,
+    pub hmd_idle_mins: u64,
+    /// Puts all devices in standby when the headset goes to standby, and turns them on again when
+    /// it wakes up. Needs the `openvr` feature.
+    pub standby_with_hmd: bool,
+    /// Wakes the devices again if SteamVR starts or the headset is used within this many seconds
+    /// of an automatic sleep or standby. 0 turns it off.
+    pub auto_off_grace_secs: u64,
+    /// How long the sleep timer in the header runs.
+    pub sleep_timer_mins: u64,
+    pub steamvr_interlock: SteamVrInterlock,
+    /// Leaves the automatic power changes to SteamVR's own power management while SteamVR is
+    /// running, so that the two don't fight.
+    pub defer_to_steamvr: bool,
+    /// Program started by the launcher instead of SteamVR.
+    pub launch_command: Option<String>,
+    /// Power state that the devices that are not manual only are put into when the app closes.
+    pub exit_power_state: ExitPowerState,
+    /// Puts the devices that are not manual only to sleep when the computer suspends or shuts
+    /// down.
+    pub sleep_on_suspend: bool,
+    pub resume_action: ResumeAction,
+    /// States of the devices when the computer last suspended, keyed by address.
+    pub pre_suspend_states: HashMap<String, PowerStateCommand>,
+    /// How long the launcher waits for the devices to be ready before starting anyway.
+    pub launch_timeout_secs: u64,
+    /// Steam config directory to import base station names from, if not in the default location.
+    pub steamvr_config_dir: Option<String>,
+    /// Power changes made at set times.
+    pub schedules: Vec<Schedule>,
+    /// Shell commands that are run when a device changes state.
+    pub hooks: Vec<Hook>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            compact_mode: false,
+            always_on_top: false,
+            window_size: [450.0, 300.0],
+            compact_window_size: [300.0, 60.0],
+            ipc_enabled: false,
+            ipc_path: None,
+            single_instance: false,
+            aliases: HashMap::new(),
+            groups: HashMap::new(),
+            status_file: None,
+            status_interval_ms: 1000,
+            restore_desired_state: false,
+            throttle_polling_while_scanning: true,
+            panic_off_shortcut: true,
+            on_time_secs: HashMap::new(),
+            error_counts: HashMap::new(),
+            paired_devices: Vec::new(),
+            power_uuid: VALVE_POWER_UUID,
+            channel_uuid: VALVE_CHANNEL_UUID,
+            screen_reader: false,
+            selected_device: None,
+            reboot_uuid: None,
+            reboot_value: None,
+            temperature_uuid: None,
+            temperature_warning: 50.0,
+            overheat_standby: false,
+            overheat_temperature: 60.0,
+            scan_filter: ScanFilter::Characteristic,
+            scan_name_prefix: VALVE_NAME_PREFIX.to_string(),
+            check_for_updates: false,
+            scan_on_startup: true,
+            known_devices: HashMap::new(),
+            direct_connect: true,
+            device_addresses: Vec::new(),
+            continuous_scan: false,
+            manual_only_devices: Vec::new(),
+            pinned_devices: Vec::new(),
+            acknowledged_writes: false,
+            osc_enabled: false,
+            osc_port: 9010,
+            osc_send_to: None,
+            metrics_enabled: false,
+            metrics_port: 9464,
+            overlay_enabled: false,
+            overlay_css: None,
+            webhook_urls: Vec::new(),
+            operation_timeout_ms: 5000,
+            power_cooldown_secs: 10,
+            warm_up_secs: 0,
+            poll_jitter_percent: 20,
+            language: "en".to_string(),
+            window_title: String::new(),
+            steamvr_config_dir: None,
+            schedules: Vec::new(),
+            hooks: Vec::new(),
+            power_on_with_steamvr: false,
+            wake_on_hmd_proximity: false,
+            sleep_after_steamvr: false,
+            steamvr_sleep_delay_mins: 5,
+            hmd_idle_standby: false,
+            hmd_idle_mins: 10,
+            standby_with_hmd: false,
+            auto_off_grace_secs: 30,
+            sleep_timer_mins: 30,
+            steamvr_interlock: SteamVrInterlock::Confirm,
+            defer_to_steamvr: false,
+            launch_command: None,
+            exit_power_state: ExitPowerState::Unchanged,
+            sleep_on_suspend: false,
+            resume_action: ResumeAction::Nothing,
+            pre_suspend_states: HashMap::new(),
+            launch_timeout_secs: 60,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+    }
+
+    pub fn operation_timeout(&self) -> Duration {
+        Duration::from_millis(self.operation_timeout_ms)
+    }
+
+    pub fn power_cooldown(&self) -> Duration {
+        Duration::from_secs(self.power_cooldown_secs)
+    }
+
+    pub fn warm_up(&self) -> Duration {
+        Duration::from_secs(self.warm_up_secs)
+    }
+
+    /// Grace period after SteamVR exits before the devices are put to sleep.
+    pub fn steamvr_sleep_delay(&self) -> Duration {
+        Duration::from_secs(self.steamvr_sleep_delay_mins * 60)
+    }
+
+    /// How long the headset has to be idle before the devices are put in standby.
+    pub fn hmd_idle_delay(&self) -> Duration {
+        Duration::from_secs(self.hmd_idle_mins * 60)
+    }
+
+    /// Whether the devices follow the headset's proximity sensor, as a part of the SteamVR
+    /// integration.
+    pub fn hmd_proximity(&self) -> bool {
+        self.power_on_with_steamvr && self.wake_on_hmd_proximity
+    }
+
+    pub fn auto_off_grace(&self) -> Duration {
+        Duration::from_secs(self.auto_off_grace_secs)
+    }
+
+    pub fn sleep_timer(&self) -> Duration {
+        Duration::from_secs(self.sleep_timer_mins * 60)
+    }
+
+    pub fn launch_timeout(&self) -> Duration {
+        Duration::from_secs(self.launch_timeout_secs)
+    }
+
+    /// The largest random variation of a poll with the given interval.
+    pub fn poll_jitter(&self, interval: Duration) -> Duration {
+        interval * self.poll_jitter_percent.min(100) / 100
+    }
+
+    /// The characteristic and value used for rebooting devices, if configured.
+    pub fn reboot_command(&self) -> Option<(Uuid, u8)> {
+        self.reboot_uuid.zip(self.reboot_value)
+    }
+
+    /// Loads the config file, falling back to defaults if it is missing or unreadable. An invalid
+    /// file is backed up before any of it is discarded.
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+
+        let value = match serde_json::from_str::<Value>(&contents) {
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Could not parse config file {:?}: {}", path, e);
+                backup(&path);
+                return Self::default();
+            }
+        };
+
+        match serde_json::from_value(value.clone()) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("Invalid config file {:?}: {}", path, e);
+                backup(&path);
+                Self::repair(value).unwrap_or_default()
+            }
+        }
+    }
+
+    /// Keeps every setting of a partially invalid config that is valid on its own, using defaults
+    /// for the rest.
+    fn repair(value: Value) -> Option<Self> {
+        let mut repaired = serde_json::to_value(Self::default()).ok()?;
+
+        for (key, setting) in value.as_object()? {
+            let mut candidate = repaired.clone();
+            candidate[key] = setting.clone();
+
+            if serde_json::from_value::<Self>(candidate.clone()).is_ok() {
+                repaired = candidate;
+            } else {
+                log::warn!("Ignoring invalid setting {:?} in the config file", key);
+            }
+        }
+
+        serde_json::from_value(repaired).ok()
+    }
+
+    pub fn save(&self) {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).ok();
+        }
+
+        match serde_json::to_string_pretty(self) {
+            Ok(contents) => {
+                if let Err(e) = fs::write(&path, contents) {
+                    log::error!("Could not write config file {:?}: {}", path, e);
+                }
+            }
+            Err(e) => log::error!("Could not serialize config: {}", e),
+        }
+    }
+}
+
+/// Copies an unusable config file aside so that saving the defaults does not lose it.
+fn backup(path: &Path) {
+    let backup_path = path.with_extension("json.bak");
+
+    match fs::copy(path, &backup_path) {
+        Ok(_) => log::warn!("Backed up the invalid config file to {:?}", backup_path),
+        Err(e) => log::error!("Could not back up the invalid config file: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schedule(time: &str, days: Vec<u8>) -> Schedule {
+        Schedule {
+            time: time.to_string(),
+            days,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn repair_keeps_valid_settings_only() {
+        let config = Config::repair(json!({
+            "compact_mode": true,
+            "ipc_enabled": "yes",
+            "hmd_idle_mins": 5,
+            "unknown_setting": 1,
+        }))
+        .unwrap();
+
+        assert!(config.compact_mode);
+        assert!(!config.ipc_enabled);
+        assert_eq!(config.hmd_idle_mins, 5);
+    }
+
+    #[test]
+    fn repair_needs_an_object() {
+        assert!(Config::repair(json!([1, 2, 3])).is_none());
+    }
+
+    #[test]
+    fn minute_of_day_parses_valid_times() {
+        assert_eq!(schedule("00:00", vec![]).minute_of_day(), Some(0));
+        assert_eq!(schedule("07:30", vec![]).minute_of_day(), Some(450));
+        assert_eq!(schedule(" 23:59 ", vec![]).minute_of_day(), Some(1439));
+    }
+
+    #[test]
+    fn minute_of_day_rejects_invalid_times() {
+        for time in ["24:00", "12:60", "7", "aa:bb", ""] {
+            assert_eq!(schedule(time, vec![]).minute_of_day(), None, "{}", time);
+        }
+    }
+
+    #[test]
+    fn is_due_on_every_day_without_days() {
+        let schedule = schedule("07:30", vec![]);
+
+        assert!((1..=7).all(|weekday| schedule.is_due(weekday, 450)));
+        assert!(!schedule.is_due(1, 451));
+    }
+
+    #[test]
+    fn is_due_only_on_its_days() {
+        let schedule = schedule("07:30", vec![1, 5]);
+
+        assert!(schedule.is_due(1, 450));
+        assert!(schedule.is_due(5, 450));
+        assert!(!schedule.is_due(3, 450));
+    }
+
+    #[test]
+    fn is_due_never_when_disabled() {
+        let schedule = Schedule {
+            enabled: false,
+            ..schedule("07:30", vec![])
+        };
+
+        assert!(!schedule.is_due(1, 450));
+    }
+}
@@ -0,0 +1,198 @@
+//! A programmatic interface for discovering and controlling base stations without the UI.
+
+use std::sync::Arc;
+use std::thread::{spawn, JoinHandle};
+
+use bleasy::BDAddr;
+use tokio::runtime::{Builder, Handle};
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::backend::{self, BackendOptions};
+use crate::config::Config;
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{AppState, Command, CommandError};
+
+/// Snapshot of a single discovered device.
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub address: BDAddr,
+    pub alias: Option<String>,
+    pub name: Option<String>,
+    pub power_state: PowerState,
+    pub rssi: Option<i16>,
+    pub channel: Option<u8>,
+}
+
+/// Runs the backend on its own thread and provides methods for talking to it. Dropping it stops
+/// the backend like [`shutdown`](Self::shutdown) does.
+pub struct LighthouseController {
+    state: Arc<Mutex<AppState>>,
+    cmd_tx: Sender<Command>,
+    backend: Option<JoinHandle<()>>,
+    runtime: Handle,
+}
+
+impl LighthouseController {
+    /// Starts the backend. Scanning begins right away.
+    pub fn start(config: Config, options: BackendOptions) -> Self {
+        let state = Arc::new(Mutex::new(AppState::new(config)));
+
+        let (cmd_tx, cmd_rx) = channel::<Command>(16);
+
+        let (runtime_tx, runtime_rx) = std::sync::mpsc::channel();
+
+        let backend = {
+            let state = state.clone();
+            let cmd_tx = cmd_tx.clone();
+
+            spawn(move || {
+                let runtime = Builder::new_multi_thread()
+                    .enable_all()
+                    .build()
+                    .expect("Could not start the Tokio runtime");
+
+                runtime_tx.send(runtime.handle().clone()).ok();
+                runtime.block_on(backend::run(state, options, cmd_tx, cmd_rx));
+            })
+        };
+
+        let runtime = runtime_rx
+            .recv()
+            .expect("Backend thread exited before starting");
+
+        Self {
+            state,
+            cmd_tx,
+            backend: Some(backend),
+            runtime,
+        }
+    }
+
+    /// The state shared with the backend.
+    pub fn state(&self) -> Arc<Mutex<AppState>> {
+        self.state.clone()
+    }
+
+    /// The runtime of the backend, for running async code such as
+    /// [`ipc::handle_request`](crate::ipc::handle_request) from a thread without one.
+    pub fn runtime(&self) -> Handle {
+        self.runtime.clone()
+    }
+
+    /// A sender for queueing commands directly.
+    pub fn command_sender(&self) -> Sender<Command> {
+        self.cmd_tx.clone()
+    }
+
+    /// Returns the currently known devices, sorted by address.
+    pub async fn devices(&self) -> Vec<DeviceInfo> {
+        let state = self.state.lock().await;
+
+        let mut devices: Vec<_> = state
+            .device_entries
+            .iter()
+            .map(|(addr, device)| DeviceInfo {
+                address: *addr,
+                alias: device.alias.clone(),
+                name: device.name.clone(),
+                power_state: device.power_state,
+                rssi: device.rssi,
+                channel: device.channel,
+            })
+            .collect();
+
+        devices.sort_by_key(|d| d.address.to_string());
+        devices
+    }
+
+    pub async fn scan(&self) -> Result<(), SendError<Command>> {
+        self.cmd_tx.send(Command::StartScan).await
+    }
+
+    pub async fn set_power(
+        &self,
+        addr: BDAddr,
+        cmd: PowerStateCommand,
+    ) -> Result<(), SendError<Command>> {
+        self.cmd_tx
+            .send(Command::ChangePowerState(addr, cmd, None))
+            .await
+    }
+
+    /// Like [`set_power`](Self::set_power), but waits until the command has been written to the
+    /// device, or acknowledged by it if acknowledged writes are enabled.
+    pub async fn set_power_confirmed(
+        &self,
+        addr: BDAddr,
+        cmd: PowerStateCommand,
+    ) -> Result<(), CommandError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        self.cmd_tx
+            .send(Command::ChangePowerState(addr, cmd, Some(reply_tx)))
+            .await
+            .map_err(|_| CommandError::BackendStopped)?;
+
+        reply_rx.await.unwrap_or(Err(CommandError::BackendStopped))
+    }
+
+    pub async fn set_all_power(&self, cmd: PowerStateCommand) -> Result<(), SendError<Command>> {
+        self.cmd_tx.send(Command::ChangeAllPowerStates(cmd)).await
+    }
+
+    pub async fn set_group_power(
+        &self,
+        group: &str,
+        cmd: PowerStateCommand,
+    ) -> Result<(), SendError<Command>> {
+        self.cmd_tx
+            .send(Command::ChangeGroupPowerState(group.to_string(), cmd))
+            .await
+    }
+
+    /// Stops the backend, putting the devices into the configured exit state first, and blocks
+    /// until it has stopped.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    /// Blocks until the backend thread exits.
+    pub fn wait(mut self) {
+        if let Some(backend) = self.backend.take() {
+            join(backend);
+        }
+    }
+
+    fn stop(&mut self) {
+        let backend = match self.backend.take() {
+            Some(backend) => backend,
+            None => return,
+        };
+
+        // Sent from the runtime of the backend, as this may be called from async code where
+        // blocking on the channel would panic
+        let cmd_tx = self.cmd_tx.clone();
+        let (reply_tx, _reply_rx) = oneshot::channel();
+
+        self.runtime.spawn(async move {
+            cmd_tx.send(Command::Shutdown(reply_tx)).await.ok();
+        });
+
+        join(backend);
+    }
+}
+
+impl Drop for LighthouseController {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Blocks until the backend thread exits, after which the runtime is gone too.
+fn join(backend: JoinHandle<()>) {
+    if backend.join().is_err() {
+        log::error!("Backend thread panicked");
+    }
+}
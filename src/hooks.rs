@@ -0,0 +1,141 @@
+//! User configured shell commands that are run when a device changes state, e.g. to start a
+//! script once all base stations are on.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bleasy::BDAddr;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::config::Hook;
+use crate::power::PowerState;
+use crate::state::AppState;
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A device that has changed state since the last check.
+struct Transition {
+    addr: BDAddr,
+    name: Option<String>,
+    old_state: PowerState,
+    new_state: PowerState,
+}
+
+/// Watches the polled device states and runs the configured hooks for every transition.
+pub async fn run(app_state: Arc<Mutex<AppState>>) {
+    let mut last_states: HashMap<BDAddr, PowerState> = HashMap::new();
+
+    loop {
+        sleep(CHECK_INTERVAL).await;
+
+        let app_state = app_state.lock().await;
+        let mut transitions = Vec::new();
+
+        for (addr, device) in &app_state.device_entries {
+            let state = match device.history.back() {
+                Some((_, state)) => *state,
+                None => continue,
+            };
+
+            // Devices seen for the first time have no transition to report
+            match last_states.insert(*addr, state) {
+                Some(old_state) if old_state != state => transitions.push(Transition {
+                    addr: *addr,
+                    name: device.alias.clone().or_else(|| device.name.clone()),
+                    old_state,
+                    new_state: state,
+                }),
+                _ => {}
+            }
+        }
+
+        if transitions.is_empty() || app_state.config.hooks.is_empty() {
+            continue;
+        }
+
+        for hook in &app_state.config.hooks {
+            let target = hook.state.map(|state| state.target_state());
+
+            let matching: Vec<_> = transitions
+                .iter()
+                .filter(|t| target.is_none_or(|target| t.new_state == target))
+                .collect();
+
+            if hook.all {
+                let target = match target {
+                    Some(target) => target,
+                    None => continue,
+                };
+
+                let all_reached = app_state.auto_managed().iter().all(|addr| {
+                    app_state
+                        .device_entries
+                        .get(addr)
+                        .and_then(|d| d.history.back())
+                        .is_some_and(|(_, state)| *state == target)
+                });
+
+                // Runs for the device that was the last one to get there
+                if let (true, Some(transition)) = (all_reached, matching.last()) {
+                    tokio::task::spawn(run_hook(hook.clone(), env(transition)));
+                }
+            } else {
+                for transition in matching {
+                    tokio::task::spawn(run_hook(hook.clone(), env(transition)));
+                }
+            }
+        }
+    }
+}
+
+fn env(transition: &Transition) -> Vec<(&'static str, String)> {
+    vec![
+        ("LIGHTHOUSE_ADDRESS", transition.addr.to_string()),
+        (
+            "LIGHTHOUSE_NAME",
+            transition.name.clone().unwrap_or_default(),
+        ),
+        ("LIGHTHOUSE_STATE", transition.new_state.to_string()),
+        ("LIGHTHOUSE_OLD_STATE", transition.old_state.to_string()),
+    ]
+}
+
+/// Runs the command of `hook` in the shell and logs it if it can't be started or fails.
+async fn run_hook(hook: Hook, env: Vec<(&'static str, String)>) {
+    log::debug!("Running hook `{}`", hook.command);
+
+    let result = shell_command(&hook.command)
+        .envs(env)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await;
+
+    match result {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => log::error!(
+            "Hook `{}` failed ({}): {}",
+            hook.command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => log::error!("Could not run hook `{}`: {}", hook.command, e),
+    }
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> tokio::process::Command {
+    let mut shell = tokio::process::Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> tokio::process::Command {
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut shell = tokio::process::Command::new("cmd");
+    shell.args(["/C", command]).creation_flags(CREATE_NO_WINDOW);
+    shell
+}
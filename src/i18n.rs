@@ -0,0 +1,1430 @@
+//! Translations of the UI strings.
+//!
+//! Each language is a table from a key to its text. Keys missing from a language fall back to
+//! English, so a new language can be added with a partial table and filled in over time. In texts
+//! with values, each `{}` is replaced by the next value in order.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use steamvr_lighthouse_control::power::PowerState;
+
+type Strings = &'static [(&'static str, &'static str)];
+
+/// Selectable languages as (code, name in the language itself, strings). English comes first and
+/// is the default.
+pub const LANGUAGES: &[(&str, &str, Strings)] = &[
+    ("en", "English", EN),
+    ("fi", "Suomi", FI),
+    ("de", "Deutsch", DE),
+];
+
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Switches to the language with `code`, or to English if it is not known.
+pub fn set_language(code: &str) {
+    let index = LANGUAGES.iter().position(|(c, _, _)| *c == code);
+
+    if index.is_none() {
+        log::warn!("Unknown language {:?}, using English", code);
+    }
+
+    ACTIVE.store(index.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Returns the text of `key` in the active language.
+pub fn t(key: &'static str) -> &'static str {
+    let (_, _, strings) = LANGUAGES[ACTIVE.load(Ordering::Relaxed)];
+
+    lookup(strings, key)
+        .or_else(|| lookup(EN, key))
+        .unwrap_or(key)
+}
+
+/// Returns the text of `key` in the active language, with `args` filled in.
+pub fn tf(key: &'static str, args: &[&dyn Display]) -> String {
+    let mut parts = t(key).split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            text += &arg.to_string();
+        }
+
+        text += part;
+    }
+
+    text
+}
+
+/// Returns the name of `state` in the active language.
+pub fn state_name(state: PowerState) -> &'static str {
+    t(match state {
+        PowerState::On => "state_on",
+        PowerState::Standby => "state_standby",
+        PowerState::Sleep => "state_sleep",
+        PowerState::Starting => "state_starting",
+        PowerState::Unknown => "state_unknown",
+    })
+}
+
+fn lookup(strings: Strings, key: &str) -> Option<&'static str> {
+    strings
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, text)| *text)
+}
+
+const EN: Strings = &[
+    ("state_on", "On"),
+    ("state_standby", "Standby"),
+    ("state_sleep", "Sleep"),
+    ("state_starting", "Starting"),
+    ("state_unknown", "Unknown"),
+    ("warming_up", "Warming up"),
+    (
+        "warming_up_hint",
+        "Reports being on, but may not be ready to track yet",
+    ),
+    // Device list
+    ("ungrouped", "Ungrouped"),
+    ("on", "on"),
+    ("standby", "standby"),
+    ("stand_by", "stand by"),
+    ("sleep", "sleep"),
+    ("include_in_bulk", "Include {} in bulk actions"),
+    ("name", "Name: "),
+    (
+        "name_hint",
+        "Click to select for keyboard control, right click to set the group",
+    ),
+    ("group", "Group:"),
+    ("pin", "Pin {} to the top"),
+    ("unpin", "Unpin {}"),
+    ("manual_only", "Manual control only"),
+    (
+        "manual_only_hint",
+        "Leave out of group, all and automatic power changes",
+    ),
+    ("manual_only_badge", "manual only"),
+    (
+        "manual_only_badge_hint",
+        "Left out of group, all and automatic power changes",
+    ),
+    ("advanced", "Advanced"),
+    ("reboot_menu", "⚠ Reboot…"),
+    ("channel_badge", "ch {}"),
+    ("channel_hint", "Channel: {}"),
+    ("steamvr_badge", "SteamVR"),
+    ("serial_hint", "Known to SteamVR as {}"),
+    ("tracking_badge", "Tracking"),
+    (
+        "tracking_badge_hint",
+        "SteamVR is using this base station for tracking",
+    ),
+    ("not_tracking_badge", "Not tracking"),
+    (
+        "not_tracking_badge_hint",
+        "The base station is on, but SteamVR is not using it for tracking. Check that the \
+         headset can see it",
+    ),
+    ("steamvr_channel_hint", "Channel in SteamVR: {}"),
+    (
+        "channel_mismatch",
+        "The device reports channel {}, but SteamVR has it on channel {}. SteamVR updates its \
+         record when it sees the station again",
+    ),
+    ("position_hint", "Position: {} m"),
+    ("pair", "pair"),
+    ("pair_device", "Pair {}"),
+    (
+        "pair_hint",
+        "The device refused access and may need to be paired",
+    ),
+    ("state", "State: "),
+    (
+        "pending_hint",
+        "Waiting for the device to confirm {} (attempt {})",
+    ),
+    ("on_time_hint", "Total time on: {}\nThis session: {}"),
+    ("toggle_hint", "Switch between on and sleep (T)"),
+    ("put_in_standby", "Put {} in standby"),
+    ("put_to_sleep", "Put {} to sleep"),
+    ("turn_on", "Turn {} on"),
+    ("state_changed", "{} is now {}"),
+    ("state_since", "{} since {} ago"),
+    ("channel_conflict", "⚠ Channel {} is used by {}"),
+    ("assign_channels", "Assign unique channels"),
+    (
+        "assign_channels_hint",
+        "Moves all but one device on each shared channel to a free channel",
+    ),
+    // Header
+    ("scan_failed", "Scan failed. Is bluetooth enabled? Retrying"),
+    ("scan_failed_attempt", "Scan failed (attempt {}): {}"),
+    (
+        "adapter_unavailable",
+        "Bluetooth adapter unavailable, reconnecting",
+    ),
+    (
+        "adapter_unavailable_error",
+        "Bluetooth adapter unavailable: {}. Reconnecting",
+    ),
+    (
+        "adapter_unavailable_attempt",
+        "Bluetooth adapter unavailable (attempt {}): {}",
+    ),
+    ("scanning_continuously", "Scanning continuously"),
+    (
+        "scanning_continuously_hint",
+        "Scanning uses more power. Press ⏹ to stop",
+    ),
+    ("scanning", "Scanning for base stations"),
+    ("press_scan", "Press 🔃 to scan for base stations"),
+    (
+        "no_devices",
+        "No base stations found. Are they powered and in range?",
+    ),
+    ("no_devices_hint", "Press 🔃 to retry"),
+    ("found_devices", "Found {} devices"),
+    ("stop_scanning", "Stop scanning"),
+    ("scan", "Scan for base stations"),
+    (
+        "scan_hint",
+        "Scan for base stations. Right-click to clear the list first",
+    ),
+    ("clear_and_scan", "Clear the list and scan"),
+    ("reconnect_all", "Reconnect all"),
+    (
+        "reconnect_all_hint",
+        "Reconnect to every found base station without scanning again",
+    ),
+    ("poll_diagnostics", "Poll diagnostics"),
+    ("settings", "Settings"),
+    ("undo", "Undo"),
+    ("undo_hint", "Undo the last power state change (Ctrl+Z)"),
+    (
+        "undo_cooldown_hint",
+        "Can be undone once the devices have been in their new state for the cooldown time",
+    ),
+    ("update_available", "A newer version is available"),
+    ("compact_mode", "Compact mode"),
+    ("full_mode", "Full mode"),
+    ("compact_summary", "On: {}  Standby: {}  Sleep: {}"),
+    ("always_on_top", "Always on top"),
+    (
+        "always_on_top_hint",
+        "Always on top (applied on next launch)",
+    ),
+    ("reboot_title", "Reboot device?"),
+    (
+        "reboot_warning",
+        "{} will disconnect and stop tracking until it has restarted.",
+    ),
+    ("reboot", "Reboot"),
+    ("cancel", "Cancel"),
+    ("close", "Close"),
+    ("dismiss", "Dismiss"),
+    (
+        "steamvr_power_management",
+        "⚠ SteamVR's base station power management is enabled",
+    ),
+    (
+        "steamvr_power_management_help",
+        "SteamVR turns the base stations on and off by itself as well, which can undo the \
+         changes made here. To leave power management to this app, turn off \
+         \"Base Station Power Management\" under SteamVR Settings > Devices.",
+    ),
+    ("defer_to_steamvr", "Defer to SteamVR while it runs"),
+    (
+        "defer_to_steamvr_hint",
+        "While SteamVR is running, automatic power changes such as restoring the last state are \
+         left to SteamVR. Buttons keep working",
+    ),
+    ("steamvr_running", "SteamVR is running"),
+    ("all_base_stations", "all base stations"),
+    (
+        "power_off_warning",
+        "SteamVR is running. Turning off {} will stop tracking. Continue?",
+    ),
+    (
+        "power_off_blocked",
+        "SteamVR is running, so {} will stay on. The interlock can be changed in the settings.",
+    ),
+    ("steamvr_interlock", "Turning off during SteamVR:"),
+    (
+        "steamvr_interlock_hint",
+        "What happens when base stations are put to sleep or standby from the app while \
+         SteamVR is running",
+    ),
+    ("interlock_off", "allowed"),
+    ("interlock_confirm", "ask first"),
+    ("interlock_block", "blocked"),
+    ("exit_power_state", "When the app closes:"),
+    ("exit_unchanged", "leave as is"),
+    (
+        "sleep_on_suspend",
+        "Sleep when the computer suspends or shuts down",
+    ),
+    (
+        "sleep_on_suspend_hint",
+        "Needs gdbus and systemd-inhibit on Linux. Shutting down is only handled on Linux.",
+    ),
+    ("resume_action", "On resume:"),
+    ("resume_nothing", "leave asleep"),
+    ("resume_turn_on", "turn on"),
+    ("resume_restore", "restore previous state"),
+    ("schedules", "Schedules"),
+    ("schedule_time_hint", "Local time as HH:MM"),
+    ("schedule_days_hint", "Every day if none are selected"),
+    ("add_schedule", "Add"),
+    ("remove", "Remove"),
+    ("monday", "Mo"),
+    ("tuesday", "Tu"),
+    ("wednesday", "We"),
+    ("thursday", "Th"),
+    ("friday", "Fr"),
+    ("saturday", "Sa"),
+    ("sunday", "Su"),
+    // Power buttons
+    ("on_all", "on all"),
+    ("standby_all", "standby all"),
+    ("sleep_all", "sleep all"),
+    ("pending_count", "⏳ {} pending"),
+    ("pending_list", "Retrying until confirmed:\n{}"),
+    ("panic_off", "panic off"),
+    (
+        "panic_off_hint",
+        "Immediately sleep every device, dropping any queued commands ({})",
+    ),
+    ("checked_count", "{} checked:"),
+    ("checked_devices", "the {} checked base stations"),
+    ("group_target", "the group {}"),
+    ("clear", "clear"),
+    // Diagnostics
+    ("diagnostics", "Diagnostics"),
+    ("last_poll", "Last poll:"),
+    ("average_poll", "Average poll:"),
+    ("reads_per_second", "Reads/s:"),
+    ("failure_rate", "Failure rate:"),
+    ("copy_diagnostics", "Copy diagnostics"),
+    (
+        "copy_diagnostics_hint",
+        "Copies a report for bug reports. Note that it includes device addresses.",
+    ),
+    ("log", "Log"),
+    ("on_time", "On-time:"),
+    ("copy_csv", "Copy as CSV"),
+    ("reset", "Reset"),
+    ("error_counts", "Errors per device:"),
+    ("no_errors", "No errors recorded"),
+    ("device", "Device"),
+    ("tracking_health", "Tracking (SteamVR)"),
+    ("tracking_result", "Tracking result"),
+    ("last_pose", "Last pose"),
+    ("seconds_ago", "{} s ago"),
+    ("never", "never"),
+    ("read_failures", "Failed reads"),
+    ("write_failures", "Failed writes"),
+    ("reconnects", "Reconnects"),
+    // Settings
+    ("language", "Language"),
+    ("import_steamvr", "Import names from SteamVR"),
+    (
+        "import_steamvr_hint",
+        "Give the found base stations without an alias the names they have in SteamVR",
+    ),
+    ("imported_names", "Named {} base stations"),
+    ("unmatched_names", "Not found: {}"),
+    ("steamvr_registered", "Starts with SteamVR"),
+    ("steamvr_not_registered", "Not registered with SteamVR"),
+    ("register_steamvr", "Register with SteamVR"),
+    ("unregister_steamvr", "Unregister"),
+    (
+        "register_steamvr_hint",
+        "Adds the app to SteamVR so that it is started with SteamVR and quits when SteamVR \
+         exits. Do this while SteamVR is closed",
+    ),
+    ("window_title", "Window title:"),
+    ("window_title_hint", "Leave empty to use the default title"),
+    ("launch", "Launch"),
+    (
+        "launch_hint",
+        "Turns all base stations on and starts SteamVR once they are ready",
+    ),
+    ("launching", "Waiting for base stations ({} s)"),
+    ("launch_command", "Launch program"),
+    (
+        "launch_command_hint",
+        "Program started by the launch button and --launch. SteamVR is started if empty",
+    ),
+    ("launch_timeout", "Launch timeout"),
+    (
+        "launch_timeout_hint",
+        "How long to wait for the base stations before launching anyway",
+    ),
+    (
+        "restore_desired_state",
+        "Restore last commanded state on reconnect",
+    ),
+    ("panic_off_shortcut", "Panic off with {}"),
+    ("throttle_polling", "Only poll new devices while scanning"),
+    ("recognize_by", "Recognize base stations by"),
+    ("by_characteristic", "characteristic"),
+    (
+        "by_characteristic_hint",
+        "Most reliable, but connects to every device while scanning",
+    ),
+    ("by_name", "name"),
+    (
+        "by_name_hint",
+        "Only matches names starting with \"{}\". Try this if scanning reports characteristic \
+         errors",
+    ),
+    ("screen_reader", "Screen reader"),
+    (
+        "screen_reader_hint",
+        "Read focused controls and device state changes out loud",
+    ),
+    (
+        "acknowledged_writes",
+        "Wait for devices to acknowledge commands",
+    ),
+    (
+        "acknowledged_writes_hint",
+        "More reliable but slower. Falls back automatically if not supported",
+    ),
+    ("cooldown_remaining", "Recently changed state, wait {} s"),
+    ("already_on", "Already on"),
+    ("already_starting", "Already starting up"),
+    ("already_standby", "Already in standby"),
+    ("already_asleep", "Already asleep"),
+    (
+        "state_unknown_hint",
+        "State unknown. Wait for it to be read or scan again",
+    ),
+    ("power_cooldown", "Minimum time between state changes"),
+    (
+        "power_cooldown_hint",
+        "Protects the lasers from being cycled on and off rapidly. Applies to all commands \
+         except panic off",
+    ),
+    ("warm_up", "Warm-up time"),
+    (
+        "warm_up_hint",
+        "How long a base station has to be on before it is considered ready to track",
+    ),
+    ("temperature_hint", "Temperature: {} °C"),
+    ("temperature_warning", "Warn about temperatures above"),
+    ("overheat_standby", "Put in standby above"),
+    (
+        "overheat_standby_hint",
+        "Puts devices that get too hot in standby so that they can cool down",
+    ),
+    ("device_timeout", "Device timeout"),
+    (
+        "device_timeout_hint",
+        "How long to wait for a device to respond before giving up",
+    ),
+    ("poll_jitter", "Poll timing variation"),
+    (
+        "poll_jitter_hint",
+        "Randomizes the poll timing by up to this share of the interval, so that reads of \
+         different devices don't keep colliding",
+    ),
+    ("power_on_with_steamvr", "Turn on when SteamVR starts"),
+    (
+        "power_on_with_steamvr_hint",
+        "Turns all base stations on when SteamVR is launched, except manual only ones",
+    ),
+    (
+        "wake_on_hmd_proximity",
+        "Follow the headset's proximity sensor",
+    ),
+    (
+        "wake_on_hmd_proximity_hint",
+        "Also turns all base stations on when the headset is put on, and puts them in standby \
+         when it has been taken off for as long as set for the idle standby",
+    ),
+    ("sleep_after_steamvr", "Sleep after SteamVR exits"),
+    (
+        "sleep_after_steamvr_hint",
+        "Puts all base stations to sleep when SteamVR has been closed for this many minutes. \
+         Starting SteamVR again cancels it",
+    ),
+    ("hmd_idle_standby", "Standby while the headset is idle"),
+    (
+        "hmd_idle_standby_hint",
+        "Puts all base stations in standby when the headset has not been used for this many \
+         minutes, and turns them on again when it is",
+    ),
+    ("standby_with_hmd", "Standby with the headset"),
+    (
+        "standby_with_hmd_hint",
+        "Puts all base stations in standby when the headset goes to standby, and turns them on \
+         again when it wakes up",
+    ),
+    ("auto_off_grace", "Undo automatic sleep within"),
+    (
+        "auto_off_grace_hint",
+        "Wakes the base stations right away if SteamVR starts or the headset is used again this \
+         many seconds after they were put to sleep or in standby automatically. 0 turns it off",
+    ),
+    (
+        "steamvr_sleep_countdown",
+        "SteamVR was closed, base stations will be put to sleep",
+    ),
+    ("sleep_timer", "Sleep timer"),
+    (
+        "sleep_timer_hint",
+        "Put all base stations to sleep in {} minutes",
+    ),
+    (
+        "sleep_timer_countdown",
+        "Base stations will be put to sleep",
+    ),
+    ("cancel_sleep_timer", "Cancel the sleep timer"),
+    ("sleep_timer_duration", "Sleep timer:"),
+    ("continuous_scan", "Scan continuously"),
+    (
+        "continuous_scan_hint",
+        "Keep scanning for new devices until stopped. Uses more power",
+    ),
+    ("scan_on_startup", "Scan on startup"),
+    (
+        "scan_on_startup_hint",
+        "When off, the app starts idle until 🔃 is pressed",
+    ),
+    ("direct_connect", "Connect to known devices directly"),
+    (
+        "direct_connect_hint",
+        "Skips the scan at startup when all previously found devices are around",
+    ),
+    ("check_for_updates", "Check for updates at startup"),
+    (
+        "check_for_updates_hint",
+        "Asks GitHub for the latest release",
+    ),
+    ("version", "Version {}"),
+];
+
+const FI: Strings = &[
+    ("state_on", "Päällä"),
+    ("state_standby", "Valmiustila"),
+    ("state_sleep", "Lepotila"),
+    ("state_starting", "Käynnistyy"),
+    ("state_unknown", "Tuntematon"),
+    ("warming_up", "Lämpenee"),
+    (
+        "warming_up_hint",
+        "Ilmoittaa olevansa päällä, mutta ei ehkä ole vielä valmis seurantaan",
+    ),
+    // Device list
+    ("ungrouped", "Ryhmittelemättömät"),
+    ("on", "päälle"),
+    ("standby", "valmiustila"),
+    ("stand_by", "valmiustila"),
+    ("sleep", "lepotila"),
+    ("include_in_bulk", "Sisällytä {} joukkotoimintoihin"),
+    ("name", "Nimi: "),
+    (
+        "name_hint",
+        "Valitse näppäimistöohjausta varten napsauttamalla, aseta ryhmä oikealla painikkeella",
+    ),
+    ("group", "Ryhmä:"),
+    ("pin", "Kiinnitä {} ylös"),
+    ("unpin", "Irrota {}"),
+    ("manual_only", "Vain manuaalinen ohjaus"),
+    (
+        "manual_only_hint",
+        "Jätä pois ryhmä-, kaikki- ja automaattisista tilamuutoksista",
+    ),
+    ("manual_only_badge", "vain manuaalinen"),
+    (
+        "manual_only_badge_hint",
+        "Jätetty pois ryhmä-, kaikki- ja automaattisista tilamuutoksista",
+    ),
+    ("advanced", "Lisäasetukset"),
+    ("reboot_menu", "⚠ Käynnistä uudelleen…"),
+    ("channel_badge", "kan. {}"),
+    ("channel_hint", "Kanava: {}"),
+    ("steamvr_badge", "SteamVR"),
+    ("serial_hint", "SteamVR tuntee tunnuksella {}"),
+    ("tracking_badge", "Seuraa"),
+    (
+        "tracking_badge_hint",
+        "SteamVR käyttää tätä tukiasemaa seurantaan",
+    ),
+    ("not_tracking_badge", "Ei seuraa"),
+    (
+        "not_tracking_badge_hint",
+        "Tukiasema on päällä, mutta SteamVR ei käytä sitä seurantaan. Tarkista, että lasit \
+         näkevät sen",
+    ),
+    ("steamvr_channel_hint", "Kanava SteamVR:ssä: {}"),
+    (
+        "channel_mismatch",
+        "Laite ilmoittaa kanavan {}, mutta SteamVR:ssä sillä on kanava {}. SteamVR päivittää \
+         tiedon nähdessään tukiaseman uudelleen",
+    ),
+    ("position_hint", "Sijainti: {} m"),
+    ("pair", "muodosta pari"),
+    ("pair_device", "Muodosta pari laitteen {} kanssa"),
+    (
+        "pair_hint",
+        "Laite esti pääsyn, ja sen kanssa voi olla tarpeen muodostaa pari",
+    ),
+    ("state", "Tila: "),
+    (
+        "pending_hint",
+        "Odotetaan, että laite vahvistaa tilan {} (yritys {})",
+    ),
+    ("on_time_hint", "Käyttöaika yhteensä: {}\nTämä istunto: {}"),
+    ("toggle_hint", "Vaihda päällä- ja lepotilan välillä (T)"),
+    ("put_in_standby", "Aseta {} valmiustilaan"),
+    ("put_to_sleep", "Aseta {} lepotilaan"),
+    ("turn_on", "Kytke {} päälle"),
+    ("state_changed", "{} on nyt tilassa {}"),
+    ("state_since", "{} {} ajan"),
+    ("channel_conflict", "⚠ Kanavaa {} käyttävät {}"),
+    ("assign_channels", "Määritä yksilölliset kanavat"),
+    (
+        "assign_channels_hint",
+        "Siirtää jaetulta kanavalta kaikki paitsi yhden laitteen vapaalle kanavalle",
+    ),
+    // Header
+    (
+        "scan_failed",
+        "Haku epäonnistui. Onko Bluetooth päällä? Yritetään uudelleen",
+    ),
+    ("scan_failed_attempt", "Haku epäonnistui (yritys {}): {}"),
+    (
+        "adapter_unavailable",
+        "Bluetooth-sovitin ei ole käytettävissä, yhdistetään uudelleen",
+    ),
+    (
+        "adapter_unavailable_error",
+        "Bluetooth-sovitin ei ole käytettävissä: {}. Yhdistetään uudelleen",
+    ),
+    (
+        "adapter_unavailable_attempt",
+        "Bluetooth-sovitin ei ole käytettävissä (yritys {}): {}",
+    ),
+    ("scanning_continuously", "Haetaan jatkuvasti"),
+    (
+        "scanning_continuously_hint",
+        "Haku kuluttaa enemmän virtaa. Pysäytä painamalla ⏹",
+    ),
+    ("scanning", "Haetaan tukiasemia"),
+    ("press_scan", "Hae tukiasemia painamalla 🔃"),
+    (
+        "no_devices",
+        "Tukiasemia ei löytynyt. Ovatko ne päällä ja kantaman sisällä?",
+    ),
+    ("no_devices_hint", "Yritä uudelleen painamalla 🔃"),
+    ("found_devices", "Löydettiin {} laitetta"),
+    ("stop_scanning", "Pysäytä haku"),
+    ("scan", "Hae tukiasemia"),
+    (
+        "scan_hint",
+        "Hae tukiasemia. Tyhjennä lista ensin hiiren oikealla painikkeella",
+    ),
+    ("clear_and_scan", "Tyhjennä lista ja hae"),
+    ("reconnect_all", "Yhdistä kaikki uudelleen"),
+    (
+        "reconnect_all_hint",
+        "Yhdistä uudelleen kaikkiin löydettyihin tukiasemiin hakematta uudelleen",
+    ),
+    ("poll_diagnostics", "Kyselyn diagnostiikka"),
+    ("settings", "Asetukset"),
+    ("undo", "Kumoa"),
+    ("undo_hint", "Kumoa viimeisin tilamuutos (Ctrl+Z)"),
+    (
+        "undo_cooldown_hint",
+        "Voidaan kumota, kun laitteet ovat olleet uudessa tilassaan odotusajan",
+    ),
+    ("update_available", "Uudempi versio on saatavilla"),
+    ("compact_mode", "Pieni näkymä"),
+    ("full_mode", "Täysi näkymä"),
+    (
+        "compact_summary",
+        "Päällä: {}  Valmiustila: {}  Lepotila: {}",
+    ),
+    ("always_on_top", "Aina päällimmäisenä"),
+    (
+        "always_on_top_hint",
+        "Aina päällimmäisenä (otetaan käyttöön seuraavalla käynnistyksellä)",
+    ),
+    ("reboot_title", "Käynnistetäänkö laite uudelleen?"),
+    (
+        "reboot_warning",
+        "{} katkaisee yhteyden ja lakkaa seuraamasta, kunnes se on käynnistynyt uudelleen.",
+    ),
+    ("reboot", "Käynnistä uudelleen"),
+    ("cancel", "Peruuta"),
+    ("close", "Sulje"),
+    ("dismiss", "Ohita"),
+    (
+        "steamvr_power_management",
+        "⚠ SteamVR:n tukiasemien virranhallinta on käytössä",
+    ),
+    (
+        "steamvr_power_management_help",
+        "SteamVR kytkee tukiasemia päälle ja pois myös itse, mikä voi kumota täällä tehdyt \
+         muutokset. Jos haluat jättää virranhallinnan tälle sovellukselle, poista käytöstä \
+         \"Base Station Power Management\" kohdasta SteamVR Settings > Devices.",
+    ),
+    (
+        "defer_to_steamvr",
+        "Anna SteamVR:n hoitaa sen ollessa käynnissä",
+    ),
+    (
+        "defer_to_steamvr_hint",
+        "SteamVR:n ollessa käynnissä automaattiset muutokset, kuten viimeisen tilan palautus, \
+         jätetään SteamVR:lle. Painikkeet toimivat edelleen",
+    ),
+    ("steamvr_running", "SteamVR on käynnissä"),
+    ("all_base_stations", "kaikki tukiasemat"),
+    (
+        "power_off_warning",
+        "SteamVR on käynnissä. Kohteen {} sammuttaminen pysäyttää seurannan. Jatketaanko?",
+    ),
+    (
+        "power_off_blocked",
+        "SteamVR on käynnissä, joten {} pysyy päällä. Lukituksen voi muuttaa asetuksista.",
+    ),
+    ("steamvr_interlock", "Sammuttaminen SteamVR:n aikana:"),
+    (
+        "steamvr_interlock_hint",
+        "Mitä tapahtuu, kun tukiasemat asetetaan sovelluksesta lepo- tai valmiustilaan \
+         SteamVR:n ollessa käynnissä",
+    ),
+    ("interlock_off", "sallittu"),
+    ("interlock_confirm", "kysy ensin"),
+    ("interlock_block", "estetty"),
+    ("exit_power_state", "Kun sovellus suljetaan:"),
+    ("exit_unchanged", "jätä ennalleen"),
+    (
+        "sleep_on_suspend",
+        "Lepotila, kun tietokone menee lepotilaan tai sammuu",
+    ),
+    (
+        "sleep_on_suspend_hint",
+        "Vaatii Linuxissa gdbusin ja systemd-inhibitin. Sammuminen huomataan vain Linuxissa.",
+    ),
+    ("resume_action", "Lepotilasta palattaessa:"),
+    ("resume_nothing", "jätä lepotilaan"),
+    ("resume_turn_on", "käynnistä"),
+    ("resume_restore", "palauta edellinen tila"),
+    ("schedules", "Ajastukset"),
+    ("schedule_time_hint", "Paikallinen aika muodossa HH:MM"),
+    (
+        "schedule_days_hint",
+        "Joka päivä, jos yhtään ei ole valittu",
+    ),
+    ("add_schedule", "Lisää"),
+    ("remove", "Poista"),
+    ("monday", "ma"),
+    ("tuesday", "ti"),
+    ("wednesday", "ke"),
+    ("thursday", "to"),
+    ("friday", "pe"),
+    ("saturday", "la"),
+    ("sunday", "su"),
+    // Power buttons
+    ("on_all", "kaikki päälle"),
+    ("standby_all", "kaikki valmiustilaan"),
+    ("sleep_all", "kaikki lepotilaan"),
+    ("pending_count", "⏳ {} odottaa"),
+    (
+        "pending_list",
+        "Yritetään uudelleen, kunnes vahvistettu:\n{}",
+    ),
+    ("panic_off", "hätäsammutus"),
+    (
+        "panic_off_hint",
+        "Aseta kaikki laitteet heti lepotilaan ja hylkää jonossa olevat komennot ({})",
+    ),
+    ("checked_count", "{} valittu:"),
+    ("checked_devices", "{} valittua tukiasemaa"),
+    ("group_target", "ryhmä {}"),
+    ("clear", "tyhjennä"),
+    // Diagnostics
+    ("diagnostics", "Diagnostiikka"),
+    ("last_poll", "Viimeisin kysely:"),
+    ("average_poll", "Keskimääräinen kysely:"),
+    ("reads_per_second", "Lukuja/s:"),
+    ("failure_rate", "Virheprosentti:"),
+    ("copy_diagnostics", "Kopioi diagnostiikka"),
+    (
+        "copy_diagnostics_hint",
+        "Kopioi raportin vikailmoitusta varten. Huomaa, että se sisältää laitteiden osoitteet.",
+    ),
+    ("log", "Loki"),
+    ("on_time", "Käyttöaika:"),
+    ("copy_csv", "Kopioi CSV-muodossa"),
+    ("reset", "Nollaa"),
+    ("error_counts", "Virheet laitteittain:"),
+    ("no_errors", "Ei kirjattuja virheitä"),
+    ("device", "Laite"),
+    ("tracking_health", "Seuranta (SteamVR)"),
+    ("tracking_result", "Seurannan tila"),
+    ("last_pose", "Viimeisin sijainti"),
+    ("seconds_ago", "{} s sitten"),
+    ("never", "ei koskaan"),
+    ("read_failures", "Epäonnistuneet luvut"),
+    ("write_failures", "Epäonnistuneet kirjoitukset"),
+    ("reconnects", "Uudelleenyhdistykset"),
+    // Settings
+    ("language", "Kieli"),
+    ("import_steamvr", "Tuo nimet SteamVR:stä"),
+    (
+        "import_steamvr_hint",
+        "Anna löydetyille tukiasemille, joilla ei ole aliasta, niiden SteamVR-nimet",
+    ),
+    ("imported_names", "Nimettiin {} tukiasemaa"),
+    ("unmatched_names", "Ei löytynyt: {}"),
+    ("steamvr_registered", "Käynnistyy SteamVR:n kanssa"),
+    ("steamvr_not_registered", "Ei rekisteröity SteamVR:ään"),
+    ("register_steamvr", "Rekisteröi SteamVR:ään"),
+    ("unregister_steamvr", "Poista rekisteröinti"),
+    (
+        "register_steamvr_hint",
+        "Lisää sovelluksen SteamVR:ään, jolloin se käynnistyy SteamVR:n kanssa ja sulkeutuu \
+         SteamVR:n sulkeutuessa. Tee tämä SteamVR:n ollessa suljettuna",
+    ),
+    ("window_title", "Ikkunan otsikko:"),
+    (
+        "window_title_hint",
+        "Jätä tyhjäksi käyttääksesi oletusotsikkoa",
+    ),
+    ("launch", "Käynnistä"),
+    (
+        "launch_hint",
+        "Kytkee kaikki tukiasemat päälle ja käynnistää SteamVR:n, kun ne ovat valmiina",
+    ),
+    ("launching", "Odotetaan tukiasemia ({} s)"),
+    ("launch_command", "Käynnistettävä ohjelma"),
+    (
+        "launch_command_hint",
+        "Käynnistyspainikkeen ja --launch-valitsimen käynnistämä ohjelma. Tyhjänä käynnistetään \
+         SteamVR",
+    ),
+    ("launch_timeout", "Käynnistyksen aikaraja"),
+    (
+        "launch_timeout_hint",
+        "Kuinka kauan tukiasemia odotetaan ennen käynnistämistä joka tapauksessa",
+    ),
+    (
+        "restore_desired_state",
+        "Palauta viimeksi asetettu tila yhteyden palatessa",
+    ),
+    ("panic_off_shortcut", "Hätäsammutus näppäimillä {}"),
+    (
+        "throttle_polling",
+        "Kysy vain uusilta laitteilta haun aikana",
+    ),
+    ("recognize_by", "Tunnista tukiasemat"),
+    ("by_characteristic", "ominaisuudesta"),
+    (
+        "by_characteristic_hint",
+        "Luotettavin, mutta yhdistää haun aikana jokaiseen laitteeseen",
+    ),
+    ("by_name", "nimestä"),
+    (
+        "by_name_hint",
+        "Tunnistaa vain nimet, jotka alkavat \"{}\". Kokeile tätä, jos haku ilmoittaa \
+         ominaisuusvirheistä",
+    ),
+    ("screen_reader", "Näytönlukija"),
+    (
+        "screen_reader_hint",
+        "Lue kohdistetut säätimet ja laitteiden tilamuutokset ääneen",
+    ),
+    (
+        "acknowledged_writes",
+        "Odota, että laitteet kuittaavat komennot",
+    ),
+    (
+        "acknowledged_writes_hint",
+        "Luotettavampi mutta hitaampi. Palaa automaattisesti, jos laite ei tue sitä",
+    ),
+    ("cooldown_remaining", "Tila vaihtui äskettäin, odota {} s"),
+    ("already_on", "Jo päällä"),
+    ("already_starting", "Käynnistyy jo"),
+    ("already_standby", "Jo valmiustilassa"),
+    ("already_asleep", "Jo lepotilassa"),
+    (
+        "state_unknown_hint",
+        "Tila tuntematon. Odota, että se luetaan, tai hae uudelleen",
+    ),
+    ("power_cooldown", "Vähimmäisaika tilamuutosten välillä"),
+    (
+        "power_cooldown_hint",
+        "Suojaa lasereita nopealta päälle ja pois kytkemiseltä. Koskee kaikkia komentoja \
+         hätäsammutusta lukuun ottamatta",
+    ),
+    ("warm_up", "Lämpenemisaika"),
+    (
+        "warm_up_hint",
+        "Kuinka kauan tukiaseman on oltava päällä ennen kuin se katsotaan valmiiksi seurantaan",
+    ),
+    ("temperature_hint", "Lämpötila: {} °C"),
+    ("temperature_warning", "Varoita lämpötiloista yli"),
+    ("overheat_standby", "Aseta valmiustilaan yli"),
+    (
+        "overheat_standby_hint",
+        "Asettaa liian kuumiksi käyvät laitteet valmiustilaan, jotta ne ehtivät jäähtyä",
+    ),
+    ("device_timeout", "Laitteen aikakatkaisu"),
+    (
+        "device_timeout_hint",
+        "Kuinka kauan laitteen vastausta odotetaan ennen luovuttamista",
+    ),
+    ("poll_jitter", "Lukuajoituksen vaihtelu"),
+    (
+        "poll_jitter_hint",
+        "Vaihtelee lukujen ajoitusta satunnaisesti enintään tämän osuuden välistä, jotta eri \
+         laitteiden luvut eivät törmää toistuvasti",
+    ),
+    ("power_on_with_steamvr", "Käynnistä SteamVR:n käynnistyessä"),
+    (
+        "power_on_with_steamvr_hint",
+        "Kytkee kaikki tukiasemat päälle, kun SteamVR käynnistetään, paitsi vain manuaalisesti \
+         ohjattavat",
+    ),
+    ("wake_on_hmd_proximity", "Seuraa lasien läheisyysanturia"),
+    (
+        "wake_on_hmd_proximity_hint",
+        "Kytkee myös kaikki tukiasemat päälle, kun lasit laitetaan päähän, ja asettaa ne \
+         valmiustilaan, kun lasit ovat olleet poissa päästä joutokäynnin valmiustilan ajan",
+    ),
+    ("sleep_after_steamvr", "Lepotila SteamVR:n sulkeuduttua"),
+    (
+        "sleep_after_steamvr_hint",
+        "Asettaa kaikki tukiasemat lepotilaan, kun SteamVR on ollut suljettuna näin monta \
+         minuuttia. SteamVR:n uudelleenkäynnistys peruu sen",
+    ),
+    (
+        "hmd_idle_standby",
+        "Valmiustila, kun lasit ovat käyttämättä",
+    ),
+    (
+        "hmd_idle_standby_hint",
+        "Asettaa kaikki tukiasemat valmiustilaan, kun laseja ei ole käytetty näin moneen \
+         minuuttiin, ja käynnistää ne taas, kun niitä käytetään",
+    ),
+    ("standby_with_hmd", "Valmiustila lasien mukana"),
+    (
+        "standby_with_hmd_hint",
+        "Asettaa kaikki tukiasemat valmiustilaan, kun lasit menevät valmiustilaan, ja käynnistää \
+         ne taas, kun lasit heräävät",
+    ),
+    ("auto_off_grace", "Peru automaattinen lepotila"),
+    (
+        "auto_off_grace_hint",
+        "Käynnistää tukiasemat heti, jos SteamVR käynnistyy tai laseja käytetään näin monen \
+         sekunnin kuluessa siitä, kun ne asetettiin automaattisesti lepo- tai valmiustilaan. 0 \
+         poistaa käytöstä",
+    ),
+    (
+        "steamvr_sleep_countdown",
+        "SteamVR suljettiin, tukiasemat asetetaan lepotilaan",
+    ),
+    ("sleep_timer", "Uniajastin"),
+    (
+        "sleep_timer_hint",
+        "Aseta kaikki tukiasemat lepotilaan {} minuutin kuluttua",
+    ),
+    ("sleep_timer_countdown", "Tukiasemat asetetaan lepotilaan"),
+    ("cancel_sleep_timer", "Peru uniajastin"),
+    ("sleep_timer_duration", "Uniajastin:"),
+    ("continuous_scan", "Hae jatkuvasti"),
+    (
+        "continuous_scan_hint",
+        "Hae uusia laitteita, kunnes haku pysäytetään. Kuluttaa enemmän virtaa",
+    ),
+    ("scan_on_startup", "Hae käynnistyksen yhteydessä"),
+    (
+        "scan_on_startup_hint",
+        "Kun pois päältä, sovellus ei hae ennen kuin 🔃 painetaan",
+    ),
+    ("direct_connect", "Yhdistä tunnettuihin laitteisiin suoraan"),
+    (
+        "direct_connect_hint",
+        "Ohittaa haun käynnistyksen yhteydessä, kun kaikki aiemmin löydetyt laitteet ovat lähellä",
+    ),
+    (
+        "check_for_updates",
+        "Tarkista päivitykset käynnistyksen yhteydessä",
+    ),
+    (
+        "check_for_updates_hint",
+        "Kysyy GitHubista uusimman julkaisun",
+    ),
+    ("version", "Versio {}"),
+];
+
+const DE: Strings = &[
+    ("state_on", "An"),
+    ("state_standby", "Standby"),
+    ("state_sleep", "Ruhezustand"),
+    ("state_starting", "Startet"),
+    ("state_unknown", "Unbekannt"),
+    ("warming_up", "Wärmt auf"),
+    (
+        "warming_up_hint",
+        "Meldet sich als an, ist aber eventuell noch nicht bereit für das Tracking",
+    ),
+    // Device list
+    ("ungrouped", "Ohne Gruppe"),
+    ("on", "an"),
+    ("standby", "Standby"),
+    ("stand_by", "Standby"),
+    ("sleep", "Ruhezustand"),
+    ("include_in_bulk", "{} in Sammelaktionen einbeziehen"),
+    ("name", "Name: "),
+    (
+        "name_hint",
+        "Klicken, um für die Tastatursteuerung auszuwählen, Rechtsklick, um die Gruppe festzulegen",
+    ),
+    ("group", "Gruppe:"),
+    ("pin", "{} oben anheften"),
+    ("unpin", "{} lösen"),
+    ("manual_only", "Nur manuelle Steuerung"),
+    (
+        "manual_only_hint",
+        "Von Gruppen-, Alle- und automatischen Änderungen ausnehmen",
+    ),
+    ("manual_only_badge", "nur manuell"),
+    (
+        "manual_only_badge_hint",
+        "Von Gruppen-, Alle- und automatischen Änderungen ausgenommen",
+    ),
+    ("advanced", "Erweitert"),
+    ("reboot_menu", "⚠ Neu starten…"),
+    ("channel_badge", "Kan. {}"),
+    ("channel_hint", "Kanal: {}"),
+    ("steamvr_badge", "SteamVR"),
+    ("serial_hint", "In SteamVR bekannt als {}"),
+    ("tracking_badge", "Tracking"),
+    (
+        "tracking_badge_hint",
+        "SteamVR verwendet diese Basisstation für das Tracking",
+    ),
+    ("not_tracking_badge", "Kein Tracking"),
+    (
+        "not_tracking_badge_hint",
+        "Die Basisstation ist an, wird von SteamVR aber nicht für das Tracking verwendet. \
+         Prüfen, ob das Headset sie sehen kann",
+    ),
+    ("steamvr_channel_hint", "Kanal in SteamVR: {}"),
+    (
+        "channel_mismatch",
+        "Das Gerät meldet Kanal {}, aber SteamVR führt es auf Kanal {}. SteamVR aktualisiert \
+         den Eintrag, wenn es die Station wieder sieht",
+    ),
+    ("position_hint", "Position: {} m"),
+    ("pair", "koppeln"),
+    ("pair_device", "{} koppeln"),
+    (
+        "pair_hint",
+        "Das Gerät hat den Zugriff verweigert und muss eventuell gekoppelt werden",
+    ),
+    ("state", "Zustand: "),
+    (
+        "pending_hint",
+        "Warte auf Bestätigung von {} durch das Gerät (Versuch {})",
+    ),
+    ("on_time_hint", "Betriebszeit gesamt: {}\nDiese Sitzung: {}"),
+    ("toggle_hint", "Zwischen an und Ruhezustand wechseln (T)"),
+    ("put_in_standby", "{} in Standby versetzen"),
+    ("put_to_sleep", "{} in den Ruhezustand versetzen"),
+    ("turn_on", "{} einschalten"),
+    ("state_changed", "{} ist jetzt {}"),
+    ("state_since", "{} seit {}"),
+    ("channel_conflict", "⚠ Kanal {} wird verwendet von {}"),
+    ("assign_channels", "Eindeutige Kanäle zuweisen"),
+    (
+        "assign_channels_hint",
+        "Verschiebt alle Geräte bis auf eines pro geteiltem Kanal auf einen freien Kanal",
+    ),
+    // Header
+    (
+        "scan_failed",
+        "Suche fehlgeschlagen. Ist Bluetooth aktiviert? Neuer Versuch",
+    ),
+    (
+        "scan_failed_attempt",
+        "Suche fehlgeschlagen (Versuch {}): {}",
+    ),
+    (
+        "adapter_unavailable",
+        "Bluetooth-Adapter nicht verfügbar, verbinde neu",
+    ),
+    (
+        "adapter_unavailable_error",
+        "Bluetooth-Adapter nicht verfügbar: {}. Verbinde neu",
+    ),
+    (
+        "adapter_unavailable_attempt",
+        "Bluetooth-Adapter nicht verfügbar (Versuch {}): {}",
+    ),
+    ("scanning_continuously", "Suche läuft dauerhaft"),
+    (
+        "scanning_continuously_hint",
+        "Die Suche verbraucht mehr Strom. ⏹ drücken zum Beenden",
+    ),
+    ("scanning", "Suche nach Basisstationen"),
+    ("press_scan", "🔃 drücken, um nach Basisstationen zu suchen"),
+    (
+        "no_devices",
+        "Keine Basisstationen gefunden. Sind sie eingeschaltet und in Reichweite?",
+    ),
+    ("no_devices_hint", "🔃 drücken für einen neuen Versuch"),
+    ("found_devices", "{} Geräte gefunden"),
+    ("stop_scanning", "Suche beenden"),
+    ("scan", "Nach Basisstationen suchen"),
+    (
+        "scan_hint",
+        "Nach Basisstationen suchen. Rechtsklick, um die Liste vorher zu leeren",
+    ),
+    ("clear_and_scan", "Liste leeren und suchen"),
+    ("reconnect_all", "Alle neu verbinden"),
+    (
+        "reconnect_all_hint",
+        "Alle gefundenen Basisstationen neu verbinden, ohne erneut zu suchen",
+    ),
+    ("poll_diagnostics", "Abfragediagnose"),
+    ("settings", "Einstellungen"),
+    ("undo", "Rückgängig"),
+    (
+        "undo_hint",
+        "Letzte Zustandsänderung rückgängig machen (Strg+Z)",
+    ),
+    (
+        "undo_cooldown_hint",
+        "Kann rückgängig gemacht werden, sobald die Sperrzeit der Geräte abgelaufen ist",
+    ),
+    ("update_available", "Eine neuere Version ist verfügbar"),
+    ("compact_mode", "Kompakte Ansicht"),
+    ("full_mode", "Volle Ansicht"),
+    ("compact_summary", "An: {}  Standby: {}  Ruhezustand: {}"),
+    ("always_on_top", "Immer im Vordergrund"),
+    (
+        "always_on_top_hint",
+        "Immer im Vordergrund (wird beim nächsten Start übernommen)",
+    ),
+    ("reboot_title", "Gerät neu starten?"),
+    (
+        "reboot_warning",
+        "{} trennt die Verbindung und unterbricht das Tracking, bis es neu gestartet ist.",
+    ),
+    ("reboot", "Neu starten"),
+    ("cancel", "Abbrechen"),
+    ("close", "Schließen"),
+    ("dismiss", "Ausblenden"),
+    (
+        "steamvr_power_management",
+        "⚠ Die Energieverwaltung der Basisstationen in SteamVR ist aktiviert",
+    ),
+    (
+        "steamvr_power_management_help",
+        "SteamVR schaltet die Basisstationen auch selbst ein und aus, was die hier gemachten \
+         Änderungen rückgängig machen kann. Um die Energieverwaltung dieser App zu überlassen, \
+         \"Base Station Power Management\" unter SteamVR Settings > Devices ausschalten.",
+    ),
+    ("defer_to_steamvr", "SteamVR überlassen, solange es läuft"),
+    (
+        "defer_to_steamvr_hint",
+        "Solange SteamVR läuft, werden automatische Änderungen wie das Wiederherstellen des \
+         letzten Zustands SteamVR überlassen. Die Knöpfe funktionieren weiterhin",
+    ),
+    ("steamvr_running", "SteamVR läuft"),
+    ("all_base_stations", "alle Basisstationen"),
+    (
+        "power_off_warning",
+        "SteamVR läuft. Das Ausschalten von {} beendet das Tracking. Fortfahren?",
+    ),
+    (
+        "power_off_blocked",
+        "SteamVR läuft, daher ist das Ausschalten von {} gesperrt. Die Sperre kann in den \
+         Einstellungen geändert werden.",
+    ),
+    ("steamvr_interlock", "Ausschalten während SteamVR:"),
+    (
+        "steamvr_interlock_hint",
+        "Was passiert, wenn Basisstationen in der App in den Schlaf- oder Standby-Modus \
+         versetzt werden, während SteamVR läuft",
+    ),
+    ("interlock_off", "erlaubt"),
+    ("interlock_confirm", "nachfragen"),
+    ("interlock_block", "gesperrt"),
+    ("exit_power_state", "Beim Schließen der App:"),
+    ("exit_unchanged", "unverändert lassen"),
+    (
+        "sleep_on_suspend",
+        "Ruhezustand, wenn der Computer in den Energiesparmodus wechselt oder herunterfährt",
+    ),
+    (
+        "sleep_on_suspend_hint",
+        "Benötigt unter Linux gdbus und systemd-inhibit. Das Herunterfahren wird nur unter Linux erkannt.",
+    ),
+    ("resume_action", "Beim Aufwachen:"),
+    ("resume_nothing", "im Ruhezustand lassen"),
+    ("resume_turn_on", "einschalten"),
+    ("resume_restore", "vorherigen Zustand wiederherstellen"),
+    ("schedules", "Zeitpläne"),
+    ("schedule_time_hint", "Ortszeit als HH:MM"),
+    (
+        "schedule_days_hint",
+        "Jeden Tag, wenn keiner ausgewählt ist",
+    ),
+    ("add_schedule", "Hinzufügen"),
+    ("remove", "Entfernen"),
+    ("monday", "Mo"),
+    ("tuesday", "Di"),
+    ("wednesday", "Mi"),
+    ("thursday", "Do"),
+    ("friday", "Fr"),
+    ("saturday", "Sa"),
+    ("sunday", "So"),
+    // Power buttons
+    ("on_all", "alle an"),
+    ("standby_all", "alle Standby"),
+    ("sleep_all", "alle Ruhezustand"),
+    ("pending_count", "⏳ {} ausstehend"),
+    ("pending_list", "Wird wiederholt, bis bestätigt:\n{}"),
+    ("panic_off", "Notaus"),
+    (
+        "panic_off_hint",
+        "Alle Geräte sofort in den Ruhezustand versetzen und wartende Befehle verwerfen ({})",
+    ),
+    ("checked_count", "{} ausgewählt:"),
+    ("checked_devices", "die {} ausgewählten Basisstationen"),
+    ("group_target", "die Gruppe {}"),
+    ("clear", "leeren"),
+    // Diagnostics
+    ("diagnostics", "Diagnose"),
+    ("last_poll", "Letzte Abfrage:"),
+    ("average_poll", "Durchschnittliche Abfrage:"),
+    ("reads_per_second", "Lesevorgänge/s:"),
+    ("failure_rate", "Fehlerquote:"),
+    ("copy_diagnostics", "Diagnose kopieren"),
+    (
+        "copy_diagnostics_hint",
+        "Kopiert einen Bericht für Fehlermeldungen. Er enthält die Geräteadressen.",
+    ),
+    ("log", "Protokoll"),
+    ("on_time", "Betriebszeit:"),
+    ("copy_csv", "Als CSV kopieren"),
+    ("reset", "Zurücksetzen"),
+    ("error_counts", "Fehler pro Gerät:"),
+    ("no_errors", "Keine Fehler aufgezeichnet"),
+    ("device", "Gerät"),
+    ("tracking_health", "Tracking (SteamVR)"),
+    ("tracking_result", "Tracking-Status"),
+    ("last_pose", "Letzte Position"),
+    ("seconds_ago", "vor {} s"),
+    ("never", "nie"),
+    ("read_failures", "Fehlgeschlagene Lesevorgänge"),
+    ("write_failures", "Fehlgeschlagene Schreibvorgänge"),
+    ("reconnects", "Wiederverbindungen"),
+    // Settings
+    ("language", "Sprache"),
+    ("import_steamvr", "Namen aus SteamVR importieren"),
+    (
+        "import_steamvr_hint",
+        "Gefundenen Basisstationen ohne Alias ihre Namen aus SteamVR geben",
+    ),
+    ("imported_names", "{} Basisstationen benannt"),
+    ("unmatched_names", "Nicht gefunden: {}"),
+    ("steamvr_registered", "Startet mit SteamVR"),
+    ("steamvr_not_registered", "Nicht bei SteamVR registriert"),
+    ("register_steamvr", "Bei SteamVR registrieren"),
+    ("unregister_steamvr", "Registrierung entfernen"),
+    (
+        "register_steamvr_hint",
+        "Fügt die App zu SteamVR hinzu, damit sie mit SteamVR startet und beim Beenden von \
+         SteamVR geschlossen wird. Dies bei geschlossenem SteamVR tun",
+    ),
+    ("window_title", "Fenstertitel:"),
+    (
+        "window_title_hint",
+        "Leer lassen, um den Standardtitel zu verwenden",
+    ),
+    ("launch", "Starten"),
+    (
+        "launch_hint",
+        "Schaltet alle Basisstationen ein und startet SteamVR, sobald sie bereit sind",
+    ),
+    ("launching", "Warte auf Basisstationen ({} s)"),
+    ("launch_command", "Zu startendes Programm"),
+    (
+        "launch_command_hint",
+        "Programm, das der Start-Knopf und --launch starten. Ist es leer, wird SteamVR gestartet",
+    ),
+    ("launch_timeout", "Zeitlimit beim Starten"),
+    (
+        "launch_timeout_hint",
+        "Wie lange auf die Basisstationen gewartet wird, bevor trotzdem gestartet wird",
+    ),
+    (
+        "restore_desired_state",
+        "Zuletzt befohlenen Zustand nach Wiederverbindung herstellen",
+    ),
+    ("panic_off_shortcut", "Notaus mit {}"),
+    (
+        "throttle_polling",
+        "Während der Suche nur neue Geräte abfragen",
+    ),
+    ("recognize_by", "Basisstationen erkennen am"),
+    ("by_characteristic", "Merkmal"),
+    (
+        "by_characteristic_hint",
+        "Am zuverlässigsten, verbindet sich während der Suche aber mit jedem Gerät",
+    ),
+    ("by_name", "Namen"),
+    (
+        "by_name_hint",
+        "Erkennt nur Namen, die mit \"{}\" beginnen. Bei Merkmalsfehlern während der Suche \
+         ausprobieren",
+    ),
+    ("screen_reader", "Bildschirmleser"),
+    (
+        "screen_reader_hint",
+        "Fokussierte Bedienelemente und Zustandsänderungen vorlesen",
+    ),
+    (
+        "acknowledged_writes",
+        "Auf Bestätigung der Befehle durch die Geräte warten",
+    ),
+    (
+        "acknowledged_writes_hint",
+        "Zuverlässiger, aber langsamer. Fällt automatisch zurück, wenn nicht unterstützt",
+    ),
+    (
+        "cooldown_remaining",
+        "Zustand kürzlich geändert, {} s warten",
+    ),
+    ("already_on", "Bereits an"),
+    ("already_starting", "Startet bereits"),
+    ("already_standby", "Bereits im Standby"),
+    ("already_asleep", "Bereits im Ruhezustand"),
+    (
+        "state_unknown_hint",
+        "Zustand unbekannt. Warten, bis er gelesen wurde, oder erneut suchen",
+    ),
+    ("power_cooldown", "Mindestzeit zwischen Zustandsänderungen"),
+    (
+        "power_cooldown_hint",
+        "Schützt die Laser vor schnellem Ein- und Ausschalten. Gilt für alle Befehle außer \
+         Notaus",
+    ),
+    ("warm_up", "Aufwärmzeit"),
+    (
+        "warm_up_hint",
+        "Wie lange eine Basisstation an sein muss, bevor sie als bereit für das Tracking gilt",
+    ),
+    ("temperature_hint", "Temperatur: {} °C"),
+    ("temperature_warning", "Warnen bei Temperaturen über"),
+    ("overheat_standby", "In Standby versetzen über"),
+    (
+        "overheat_standby_hint",
+        "Versetzt zu heiße Geräte in Standby, damit sie abkühlen können",
+    ),
+    ("device_timeout", "Zeitlimit für Geräte"),
+    (
+        "device_timeout_hint",
+        "Wie lange auf eine Antwort des Geräts gewartet wird",
+    ),
+    ("poll_jitter", "Zufällige Abfrageverzögerung"),
+    (
+        "poll_jitter_hint",
+        "Verschiebt Abfragen zufällig um bis zu diesen Anteil des Intervalls, damit Lesevorgänge \
+         verschiedener Geräte nicht ständig kollidieren",
+    ),
+    (
+        "power_on_with_steamvr",
+        "Beim Start von SteamVR einschalten",
+    ),
+    (
+        "power_on_with_steamvr_hint",
+        "Schaltet alle Basisstationen ein, wenn SteamVR gestartet wird, außer nur manuell \
+         gesteuerte",
+    ),
+    ("wake_on_hmd_proximity", "Dem Näherungssensor des Headsets folgen"),
+    (
+        "wake_on_hmd_proximity_hint",
+        "Schaltet alle Basisstationen auch ein, wenn das Headset aufgesetzt wird, und versetzt \
+         sie in Bereitschaft, wenn es so lange abgesetzt war wie für die Leerlauf-Bereitschaft \
+         eingestellt",
+    ),
+    (
+        "sleep_after_steamvr",
+        "Nach dem Beenden von SteamVR schlafen",
+    ),
+    (
+        "sleep_after_steamvr_hint",
+        "Versetzt alle Basisstationen in den Schlafmodus, wenn SteamVR so viele Minuten \
+         geschlossen war. Ein erneuter Start von SteamVR bricht dies ab",
+    ),
+    (
+        "hmd_idle_standby",
+        "Bereitschaft, solange das Headset ungenutzt ist",
+    ),
+    (
+        "hmd_idle_standby_hint",
+        "Versetzt alle Basisstationen in Bereitschaft, wenn das Headset so viele Minuten nicht \
+         benutzt wurde, und schaltet sie wieder ein, sobald es benutzt wird",
+    ),
+    ("standby_with_hmd", "Bereitschaft mit dem Headset"),
+    (
+        "standby_with_hmd_hint",
+        "Versetzt alle Basisstationen in Bereitschaft, wenn das Headset in Bereitschaft geht, \
+         und schaltet sie wieder ein, wenn es aufwacht",
+    ),
+    ("auto_off_grace", "Automatischen Ruhezustand rückgängig machen innerhalb von"),
+    (
+        "auto_off_grace_hint",
+        "Schaltet die Basisstationen sofort wieder ein, wenn SteamVR startet oder das Headset \
+         so viele Sekunden nach dem automatischen Ruhezustand oder der Bereitschaft wieder \
+         benutzt wird. 0 schaltet es aus",
+    ),
+    (
+        "steamvr_sleep_countdown",
+        "SteamVR wurde beendet, die Basisstationen werden in den Schlafmodus versetzt",
+    ),
+    ("sleep_timer", "Schlaftimer"),
+    (
+        "sleep_timer_hint",
+        "Alle Basisstationen in {} Minuten in den Schlafmodus versetzen",
+    ),
+    (
+        "sleep_timer_countdown",
+        "Die Basisstationen werden in den Schlafmodus versetzt",
+    ),
+    ("cancel_sleep_timer", "Schlaftimer abbrechen"),
+    ("sleep_timer_duration", "Schlaftimer:"),
+    ("continuous_scan", "Dauerhaft suchen"),
+    (
+        "continuous_scan_hint",
+        "Sucht bis zum Beenden nach neuen Geräten. Verbraucht mehr Strom",
+    ),
+    ("scan_on_startup", "Beim Start suchen"),
+    (
+        "scan_on_startup_hint",
+        "Wenn aus, wartet die App beim Start, bis 🔃 gedrückt wird",
+    ),
+    ("direct_connect", "Direkt mit bekannten Geräten verbinden"),
+    (
+        "direct_connect_hint",
+        "Überspringt die Suche beim Start, wenn alle bisher gefundenen Geräte in der Nähe sind",
+    ),
+    ("check_for_updates", "Beim Start nach Updates suchen"),
+    (
+        "check_for_updates_hint",
+        "Fragt GitHub nach der neuesten Version",
+    ),
+    ("version", "Version {}"),
+];
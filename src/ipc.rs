@@ -0,0 +1,482 @@
+//! Local IPC endpoint for scripting: a Unix domain socket on Linux/macOS and a
+//! named pipe on Windows. Requests are newline-delimited text commands and each
+//! one is answered with a single line of JSON.
+
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bleasy::BDAddr;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::sleep;
+
+use crate::launcher;
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{panic_off, AppState, Command, CommandError};
+use crate::status;
+
+#[cfg(unix)]
+pub fn default_path() -> String {
+    // The runtime directory belongs to the user, unlike the shared temp directory, where the
+    // socket gets a directory of its own
+    dirs::runtime_dir()
+        .unwrap_or_else(|| {
+            // SAFETY: geteuid has no preconditions and can't fail
+            let uid = unsafe { libc::geteuid() };
+            std::env::temp_dir().join(format!("steamvr-lighthouse-control-{}", uid))
+        })
+        .join("steamvr-lighthouse-control.sock")
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(windows)]
+pub fn default_path() -> String {
+    r"\\.\pipe\steamvr-lighthouse-control".to_string()
+}
+
+/// How long `power ... wait` waits for the devices to report the new state. Turning on takes the
+/// longest.
+const STATE_TIMEOUT: Duration = Duration::from_secs(60);
+const STATE_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Outcome of a power command for a single device, with the error code and message if it failed.
+type DeviceResult = Result<(), (&'static str, String)>;
+
+#[cfg(unix)]
+type ClientStream = std::os::unix::net::UnixStream;
+
+#[cfg(windows)]
+type ClientStream = std::fs::File;
+
+/// Blocking connection to the IPC endpoint of a running instance.
+pub struct Client {
+    stream: std::io::BufReader<ClientStream>,
+}
+
+impl Client {
+    /// Connects to the endpoint at `path`. Fails if no instance is listening there.
+    pub fn connect(path: &str) -> std::io::Result<Self> {
+        #[cfg(unix)]
+        let stream = ClientStream::connect(path)?;
+
+        #[cfg(windows)]
+        let stream = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+
+        Ok(Self {
+            stream: std::io::BufReader::new(stream),
+        })
+    }
+
+    /// Sends a single text command and waits for its response.
+    pub fn request(&mut self, line: &str) -> std::io::Result<Value> {
+        writeln!(self.stream.get_mut(), "{}", line.trim())?;
+
+        let mut response = String::new();
+
+        if self.stream.read_line(&mut response)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(serde_json::from_str(&response)?)
+    }
+}
+
+#[cfg(unix)]
+pub async fn serve(
+    path: String,
+    app_state: Arc<Mutex<AppState>>,
+    cmd_tx: Sender<Command>,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    use tokio::net::UnixListener;
+
+    prepare_socket_dir(&path)?;
+
+    // A socket file left behind by a previous run would make bind fail, but one that is still
+    // accepted belongs to another instance.
+    if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+        return Err(std::io::ErrorKind::AddrInUse.into());
+    }
+
+    std::fs::remove_file(&path).ok();
+
+    let listener = UnixListener::bind(&path)?;
+
+    // Only the current user may connect. The directory already keeps everyone else out, so this
+    // is only a second line of defense.
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+
+        tokio::task::spawn(handle_connection(stream, app_state.clone(), cmd_tx.clone()));
+    }
+}
+
+/// Makes sure that only the current user can reach the socket at `path` before it is bound. Its
+/// directory is created with mode 0700 if it is missing, and must be owned by the current user and
+/// closed to everyone else. Otherwise another user could connect to the socket, or replace it,
+/// before its own permissions are set.
+#[cfg(unix)]
+fn prepare_socket_dir(path: &str) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    let dir = match std::path::Path::new(path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(e) if e.kind() != ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+
+    // SAFETY: geteuid has no preconditions and can't fail
+    let uid = unsafe { libc::geteuid() };
+    // Not following a symlink, which another user could have pointed anywhere
+    let metadata = std::fs::symlink_metadata(dir)?;
+
+    if !metadata.is_dir() || metadata.uid() != uid {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "{} is not a directory owned by the current user",
+                dir.display()
+            ),
+        ));
+    }
+
+    if metadata.mode() & 0o077 != 0 {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} can be accessed by other users", dir.display()),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+pub async fn serve(
+    path: String,
+    app_state: Arc<Mutex<AppState>>,
+    cmd_tx: Sender<Command>,
+) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&path)?;
+
+    loop {
+        server.connect().await?;
+
+        // Create the next instance before handing off the connected one so that
+        // there is always a pipe available for new clients.
+        let client = std::mem::replace(&mut server, ServerOptions::new().create(&path)?);
+
+        tokio::task::spawn(handle_connection(client, app_state.clone(), cmd_tx.clone()));
+    }
+}
+
+async fn handle_connection<S>(stream: S, app_state: Arc<Mutex<AppState>>, cmd_tx: Sender<Command>)
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = handle_request(&line, &app_state, &cmd_tx).await;
+
+        if writer
+            .write_all(format!("{}\n", response).as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Executes a single text command and returns its JSON response.
+pub async fn handle_request(
+    line: &str,
+    app_state: &Arc<Mutex<AppState>>,
+    cmd_tx: &Sender<Command>,
+) -> Value {
+    let args: Vec<&str> = line.split_whitespace().collect();
+
+    let cmd = match args.as_slice() {
+        ["list"] => {
+            let app_state = app_state.lock().await;
+
+            return json!({
+                "ok": true,
+                "devices": status::device_records(&app_state),
+                "adapter_available": !app_state.adapter_unavailable(),
+            });
+        }
+        ["scan"] => Command::StartScan,
+        ["scan", "clear"] => Command::ClearAndScan,
+        ["reconnect"] => Command::ReconnectAll,
+        ["launch"] => {
+            launcher::request(&mut *app_state.lock().await);
+            return json!({ "ok": true });
+        }
+        ["panic"] => {
+            panic_off(&mut *app_state.lock().await);
+            return json!({ "ok": true });
+        }
+        ["all", state] => match PowerStateCommand::from_str(state) {
+            Ok(state) => Command::ChangeAllPowerStates(state),
+            Err(e) => return error(e),
+        },
+        ["set", addr, state] => {
+            let addr = match BDAddr::from_str(addr) {
+                Ok(addr) => addr,
+                Err(_) => return error(format!("Invalid address: {}", addr)),
+            };
+
+            if !app_state.lock().await.device_entries.contains_key(&addr) {
+                return failure("not_found", format!("Unknown device: {}", addr));
+            }
+
+            let state = match PowerStateCommand::from_str(state) {
+                Ok(state) => state,
+                Err(e) => return error(e),
+            };
+
+            // Wait for the write so that the response tells whether it succeeded
+            let (reply_tx, reply_rx) = oneshot::channel();
+
+            if cmd_tx
+                .send(Command::ChangePowerState(addr, state, Some(reply_tx)))
+                .await
+                .is_err()
+            {
+                return error("Command channel closed");
+            }
+
+            return match reply_rx.await.unwrap_or(Err(CommandError::BackendStopped)) {
+                Ok(()) => json!({ "ok": true }),
+                Err(e) => failure(e.code(), e),
+            };
+        }
+        ["power", target, state] => return power(app_state, cmd_tx, target, state, false).await,
+        ["power", target, state, "wait"] => {
+            return power(app_state, cmd_tx, target, state, true).await
+        }
+        _ => return error(format!("Unknown command: {}", line.trim())),
+    };
+
+    if cmd_tx.send(cmd).await.is_err() {
+        return error("Command channel closed");
+    }
+
+    json!({ "ok": true })
+}
+
+/// Changes the power state of a single device, or of every device that is not manual only, and
+/// reports the outcome for each of them. With `wait` set, a device only succeeds once it has
+/// reported the new state.
+async fn power(
+    app_state: &Arc<Mutex<AppState>>,
+    cmd_tx: &Sender<Command>,
+    target: &str,
+    state: &str,
+    wait: bool,
+) -> Value {
+    let cmd = match PowerStateCommand::from_str(state) {
+        Ok(cmd) => cmd,
+        Err(e) => return error(e),
+    };
+
+    let addrs = if target == "all" {
+        app_state.lock().await.auto_managed()
+    } else {
+        match BDAddr::from_str(target) {
+            Ok(addr) => vec![addr],
+            Err(_) => return error(format!("Invalid address: {}", target)),
+        }
+    };
+
+    {
+        let app_state = app_state.lock().await;
+
+        let missing = addrs
+            .iter()
+            .find(|addr| !app_state.device_entries.contains_key(addr));
+
+        if (missing.is_some() || addrs.is_empty()) && app_state.adapter_unavailable() {
+            return failure("adapter_unavailable", "Bluetooth adapter unavailable");
+        }
+
+        if let Some(addr) = missing {
+            return failure("not_found", format!("Unknown device: {}", addr));
+        }
+    }
+
+    if addrs.is_empty() {
+        return failure("not_found", "No base stations found");
+    }
+
+    let sent_at = Instant::now();
+    let mut results = Vec::new();
+
+    for addr in addrs {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        let result = match cmd_tx
+            .send(Command::ChangePowerState(addr, cmd, Some(reply_tx)))
+            .await
+        {
+            Ok(()) => reply_rx.await.unwrap_or(Err(CommandError::BackendStopped)),
+            Err(_) => Err(CommandError::BackendStopped),
+        };
+
+        results.push((addr, result.map_err(|e| (e.code(), e.to_string()))));
+    }
+
+    if wait {
+        wait_for_state(app_state, cmd.target_state(), sent_at, &mut results).await;
+    }
+
+    let app_state = app_state.lock().await;
+
+    let devices: Vec<_> = results
+        .iter()
+        .filter_map(|(addr, result)| {
+            let mut record = status::device_record(&app_state, addr)?;
+            record["ok"] = Value::Bool(result.is_ok());
+
+            if let Err((code, e)) = result {
+                record["error"] = Value::from(e.as_str());
+                record["code"] = Value::from(*code);
+            }
+
+            Some(record)
+        })
+        .collect();
+
+    json!({
+        "ok": results.iter().all(|(_, result)| result.is_ok()),
+        "devices": devices,
+    })
+}
+
+/// Waits until every device that the command was written to has reported `state` since
+/// `sent_at`. Devices that don't get there before the timeout have their result turned into an
+/// error.
+async fn wait_for_state(
+    app_state: &Mutex<AppState>,
+    state: PowerState,
+    sent_at: Instant,
+    results: &mut [(BDAddr, DeviceResult)],
+) {
+    let started_at = Instant::now();
+
+    loop {
+        {
+            let app_state = app_state.lock().await;
+
+            let mut waiting = results.iter_mut().filter(|(addr, result)| {
+                result.is_ok()
+                    && !app_state
+                        .device_entries
+                        .get(addr)
+                        .is_some_and(|d| d.has_reported(state, sent_at))
+            });
+
+            if started_at.elapsed() >= STATE_TIMEOUT {
+                for (_, result) in waiting {
+                    *result = Err((
+                        "timeout",
+                        format!(
+                            "Did not reach {} within {} s",
+                            state,
+                            STATE_TIMEOUT.as_secs()
+                        ),
+                    ));
+                }
+
+                return;
+            }
+
+            if waiting.next().is_none() {
+                return;
+            }
+        }
+
+        sleep(STATE_CHECK_INTERVAL).await;
+    }
+}
+
+fn error(message: impl ToString) -> Value {
+    json!({ "ok": false, "error": message.to_string() })
+}
+
+/// An error with a `code` that tells scripts why the command failed.
+fn failure(code: &str, message: impl ToString) -> Value {
+    json!({ "ok": false, "error": message.to_string(), "code": code })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "steamvr-lighthouse-control-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    #[test]
+    fn socket_dir_is_created_private() {
+        let dir = test_dir("create");
+        let path = dir.join("ipc.sock");
+
+        prepare_socket_dir(path.to_str().unwrap()).unwrap();
+
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn socket_dir_open_to_others_is_refused() {
+        let dir = test_dir("open");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = prepare_socket_dir(dir.join("ipc.sock").to_str().unwrap());
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::PermissionDenied
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
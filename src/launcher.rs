@@ -0,0 +1,171 @@
+//! Launcher mode: turns the base stations on, waits until they are ready and then starts SteamVR
+//! or another program. Meant to be used as the target of a Steam shortcut.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::power::PowerStateCommand;
+use crate::state::{AppState, Command};
+
+const STEAMVR_URL: &str = "steam://rungameid/250820";
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Asks the launcher to start. Does nothing if a launch is already in progress.
+pub fn request(app_state: &mut AppState) {
+    if app_state.launch_started_at.is_none() {
+        app_state.launch_started_at = Some(Instant::now());
+    }
+}
+
+/// Waits for launch requests and carries them out.
+///
+/// The program is started after the timeout even if some devices are not ready, as a partially
+/// working setup is better than none.
+pub async fn run(app_state: Arc<Mutex<AppState>>, cmd_tx: Sender<Command>) {
+    loop {
+        sleep(CHECK_INTERVAL).await;
+
+        if app_state.lock().await.launch_started_at.is_none() {
+            continue;
+        }
+
+        wait_until_ready(&app_state, &cmd_tx).await;
+
+        let command = {
+            let mut app_state = app_state.lock().await;
+            app_state.launch_started_at = None;
+            app_state.config.launch_command.clone()
+        };
+
+        match command.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            Some(program) => {
+                log::info!("Starting {}", program);
+
+                if let Err(e) = std::process::Command::new(program).spawn() {
+                    log::error!("Could not start {}: {}", program, e);
+                }
+            }
+            None => {
+                log::info!("Starting SteamVR");
+
+                if let Err(e) = open_url(STEAMVR_URL) {
+                    log::error!("Could not start SteamVR: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Opens `target`, which is either a URL such as `steam://rungameid/250820` or a program.
+pub fn open(target: &str) -> std::io::Result<()> {
+    if target.contains("://") {
+        open_url(target)
+    } else {
+        std::process::Command::new(target).spawn().map(drop)
+    }
+}
+
+/// Turns on every device that is not manual only, including ones found while waiting, until all
+/// of them are ready or the timeout passes.
+async fn wait_until_ready(app_state: &Mutex<AppState>, cmd_tx: &Sender<Command>) {
+    let (timeout, needs_scan) = {
+        let app_state = app_state.lock().await;
+
+        (
+            app_state.config.launch_timeout(),
+            !app_state.config.scan_on_startup
+                && !app_state.has_scanned
+                && !app_state.scanner.is_active(),
+        )
+    };
+
+    if needs_scan {
+        cmd_tx.send(Command::StartScan).await.ok();
+    }
+
+    let started_at = Instant::now();
+    let mut turned_on = 0;
+
+    loop {
+        let (count, ready, scanning, has_scanned) = {
+            let app_state = app_state.lock().await;
+            let warm_up = app_state.config.warm_up();
+            let addrs = app_state.auto_managed();
+
+            let ready = addrs.iter().all(|addr| {
+                app_state
+                    .device_entries
+                    .get(addr)
+                    .is_some_and(|d| d.is_ready(warm_up))
+            });
+
+            (
+                addrs.len(),
+                ready,
+                app_state.scanner.is_active() && !app_state.config.continuous_scan,
+                app_state.has_scanned,
+            )
+        };
+
+        if count > 0 && ready && !scanning {
+            log::info!("All {} base stations are ready", count);
+            return;
+        }
+
+        if count == 0 && has_scanned && !scanning {
+            log::warn!("No base stations found, launching anyway");
+            return;
+        }
+
+        if started_at.elapsed() >= timeout {
+            log::warn!(
+                "Base stations not ready after {} s, launching anyway",
+                timeout.as_secs()
+            );
+            return;
+        }
+
+        if count > turned_on {
+            turned_on = count;
+            cmd_tx
+                .send(Command::ChangeAllPowerStates(PowerStateCommand::On))
+                .await
+                .ok();
+        }
+
+        sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(windows)]
+fn open_url(url: &str) -> std::io::Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    std::process::Command::new("cmd")
+        .args(["/C", "start", "", url])
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+        .map(drop)
+}
+
+#[cfg(target_os = "macos")]
+fn open_url(url: &str) -> std::io::Result<()> {
+    std::process::Command::new("open")
+        .arg(url)
+        .spawn()
+        .map(drop)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn open_url(url: &str) -> std::io::Result<()> {
+    std::process::Command::new("xdg-open")
+        .arg(url)
+        .spawn()
+        .map(drop)
+}
@@ -0,0 +1,38 @@
+//! Discovery and power control of SteamVR base stations over Bluetooth LE.
+//!
+//! [`LighthouseController`] runs the Bluetooth backend on its own thread and is the
+//! easiest way to use this crate from other programs.
+
+pub mod backend;
+pub mod ble;
+pub mod config;
+pub mod controller;
+pub mod hooks;
+pub mod ipc;
+pub mod launcher;
+pub mod logging;
+pub mod metrics;
+pub mod osc;
+pub mod pairing;
+pub mod power;
+pub mod remote;
+pub mod schedule;
+pub mod service;
+pub mod simulate;
+pub mod state;
+pub mod status;
+pub mod steamvr;
+pub mod suspend;
+pub mod update;
+#[cfg(feature = "openvr")]
+pub mod vr;
+pub mod webhook;
+
+pub use backend::{Backend, BackendOptions};
+pub use bleasy::BDAddr;
+pub use config::Config;
+pub use controller::{DeviceInfo, LighthouseController};
+pub use power::{PowerState, PowerStateCommand};
+
+/// Version of this build.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
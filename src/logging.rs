@@ -0,0 +1,93 @@
+//! Logger that keeps the most recent log lines in memory so they can be shown in the UI and
+//! included in diagnostics, in addition to printing them like `pretty_env_logger` does.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+const MAX_LOG_LINES: usize = 200;
+
+static LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static ECHO: AtomicBool = AtomicBool::new(true);
+
+struct RingLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for RingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= Level::Info {
+            let mut lines = LOG_LINES.lock().unwrap();
+
+            if lines.len() == MAX_LOG_LINES {
+                lines.pop_front();
+            }
+
+            lines.push_back(format!(
+                "{} {:<5} {}",
+                timestamp(),
+                record.level(),
+                record.args()
+            ));
+        }
+
+        if ECHO.load(Ordering::Relaxed) && self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+pub fn init() {
+    let mut builder = pretty_env_logger::formatted_builder();
+
+    match std::env::var("RUST_LOG") {
+        Ok(filters) => builder.parse_filters(&filters),
+        Err(_) => builder.filter_level(LevelFilter::Warn),
+    };
+
+    let inner = builder.build();
+    let max_level = inner.filter().max(LevelFilter::Info);
+
+    if log::set_boxed_logger(Box::new(RingLogger {
+        inner: Box::new(inner),
+    }))
+    .is_ok()
+    {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Turns printing of log lines on or off. They are kept in memory either way.
+pub fn set_echo(enabled: bool) {
+    ECHO.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns the most recent log lines, oldest first.
+pub fn recent_lines() -> Vec<String> {
+    LOG_LINES.lock().unwrap().iter().cloned().collect()
+}
+
+/// Formats the current UTC time of day as `HH:MM:SS`.
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -1,380 +1,458 @@
-use std::collections::HashMap;
-use std::fmt::{Display, Formatter};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-
-use std::thread::spawn;
 use std::time::Duration;
 
-use bleasy::{BDAddr, Device, DeviceEvent, Error, ScanConfig, Scanner};
+use crossterm::tty::IsTty;
 use eframe::{egui, Frame};
-use egui::{Layout, Ui, Vec2, Widget};
-use futures::StreamExt;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::{Mutex, MutexGuard};
-use tokio::time::sleep;
-use uuid::Uuid;
-
-const POWER_UUID: Uuid = Uuid::from_u128(0x00001525_1212_EFDE_1523_785FEABCD124);
-const SCAN_TIMEOUT: Duration = Duration::from_millis(10000);
-const STATE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+use steamvr_lighthouse_control::state::{panic_off, AppState, Command};
+use steamvr_lighthouse_control::{
+    ipc, launcher, logging, service, steamvr, BackendOptions, Config, LighthouseController,
+};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::args::Args;
+use crate::i18n::t;
+use crate::ui::{
+    handle_selection_keys, ui_all_power_buttons, ui_bulk_actions, ui_channel_conflicts,
+    ui_compact_bar, ui_device_list, ui_diagnostics, ui_header, ui_power_change_confirmation,
+    ui_reboot_confirmation, ui_schedules, ui_settings, ui_steamvr_conflict, ui_steamvr_import,
+    ui_steamvr_registration, undo, DEFAULT_TITLE,
+};
+
+mod args;
+mod cli;
+mod i18n;
+mod stdio;
+mod tui;
+mod ui;
 
 fn main() {
-    pretty_env_logger::init();
+    logging::init();
 
-    let state = Arc::new(Mutex::new(AppState::new()));
+    let args = Args::parse();
 
-    // Channel for sending commands to ble thread
-    let (cmd_tx, cmd_rx) = channel::<Command>(16);
+    if let Some(shell) = args.completions() {
+        args::print_completions(shell);
+        return;
+    }
 
-    {
-        let app_state = state.clone();
+    let mut config = Config::load();
+
+    i18n::set_language(&config.language);
+
+    if args.register_steamvr || args.unregister_steamvr {
+        let config_dir = config.steamvr_config_dir.as_deref();
+
+        let result = if args.register_steamvr {
+            steamvr::register(config_dir)
+        } else {
+            steamvr::unregister(config_dir)
+        };
+
+        match result {
+            Ok(()) if args.register_steamvr => log::info!("Registered with SteamVR"),
+            Ok(()) => log::info!("Unregistered from SteamVR"),
+            Err(e) => log::error!("SteamVR registration failed: {}", e),
+        }
+
+        return;
+    }
+
+    if args.install_service || args.uninstall_service {
+        let result = if args.install_service {
+            service::install()
+        } else {
+            service::uninstall()
+        };
+
+        match result {
+            Ok(()) if args.install_service => log::info!("Installed the background service"),
+            Ok(()) => log::info!("Uninstalled the background service"),
+            Err(e) => log::error!("Could not change the background service: {}", e),
+        }
+
+        return;
+    }
 
-        spawn(move || ble_thread(app_state, cmd_rx));
+    if let Some(command) = args.cli_command() {
+        if let Some(code) = cli::run_remote(
+            &config,
+            &command,
+            args.json,
+            wait_for_state(&args),
+            args.launch_target(),
+        ) {
+            std::process::exit(code);
+        }
     }
 
-    let options = eframe::NativeOptions {
+    let remote = match running_instance(&config, &args) {
+        Some(RunningInstance::Handled) => return,
+        Some(RunningInstance::Connect(path)) => Some(path),
+        None => None,
+    };
+
+    if args.minimized {
+        config.compact_mode = true;
+    }
+
+    let window_size = if config.compact_mode {
+        config.compact_window_size
+    } else {
+        config.window_size
+    };
+
+    let native_options = eframe::NativeOptions {
+        always_on_top: config.always_on_top,
         decorated: true,
         drag_and_drop_support: false,
-        initial_window_size: Some(Vec2::new(450.0, 300.0)),
+        initial_window_size: Some(window_size.into()),
         resizable: false,
         transparent: false,
         ..Default::default()
     };
 
-    eframe::run_native(
-        "SteamVR Lighthouse Control",
-        options,
-        Box::new(|_| Box::new(App { state, cmd_tx })),
-    );
-}
+    let options = BackendOptions {
+        simulate: args.simulate,
+        print_status: args.watch,
+        serve_ipc: remote.is_none() && (args.daemon || config.single_instance),
+        remote,
+        without_automation: args.cli_command().is_some(),
+        launched_by_steamvr: args.steamvr,
+        ..Default::default()
+    };
 
-struct App {
-    state: Arc<Mutex<AppState>>,
-    cmd_tx: Sender<Command>,
-}
+    let controller = LighthouseController::start(config, options);
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
-        let mut state = self.state.blocking_lock();
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui_header(ui, &self.cmd_tx, &mut state);
-            ui.separator();
-            ui_device_list(ui, &self.cmd_tx, &mut state);
-        });
+    if args.steamvr {
+        quit_with_steamvr(controller.state(), controller.command_sender());
+    }
 
-        ctx.request_repaint();
+    if args.launch {
+        launcher::request(&mut controller.state().blocking_lock());
     }
-}
 
-#[derive(Default)]
-struct AppState {
-    scanner: Scanner,
-    device_entries: HashMap<BDAddr, DeviceEntry>,
-    ble_devices: HashMap<BDAddr, Device>,
-    error_state: Option<ErrorState>,
-}
+    if let Some(command) = args.cli_command() {
+        std::process::exit(cli::run(
+            &controller,
+            &command,
+            args.json,
+            wait_for_state(&args),
+            args.launch_target(),
+        ));
+    }
 
-impl AppState {
-    fn new() -> Self {
-        Self {
-            scanner: Scanner::new(),
-            device_entries: HashMap::new(),
-            ble_devices: HashMap::new(),
-            error_state: None,
+    if args.stdio {
+        if let Err(e) = stdio::run(&controller) {
+            log::error!("Could not read commands from stdin: {}", e);
         }
+
+        controller.shutdown();
+        return;
     }
 
-    async fn start_scan(&mut self) -> Result<(), Error> {
-        self.device_entries.clear();
-        self.ble_devices.clear();
-        self.scanner
-            .start(
-                ScanConfig::default()
-                    .filter_by_characteristics(|uuids| uuids.contains(&POWER_UUID))
-                    .stop_after_timeout(SCAN_TIMEOUT),
-            )
-            .await
+    if args.tui {
+        if let Err(e) = tui::run(&controller) {
+            log::error!("Terminal interface failed: {}", e);
+        }
+
+        controller.shutdown();
+        return;
     }
 
-    async fn insert_device(&mut self, device_addr: BDAddr, device: Device) {
-        self.device_entries.insert(
-            device_addr,
-            DeviceEntry {
-                name: device.local_name().await,
-                power_state: PowerState::Unknown,
-            },
-        );
+    if args.watch || args.daemon {
+        controller.wait();
+        return;
+    }
 
-        self.ble_devices.insert(device_addr, device);
+    if let Some(reason) = missing_display() {
+        log::error!("Cannot open a window: {}", reason);
+        run_without_window(controller);
+        return;
     }
-}
 
-enum ErrorState {
-    StartFailed,
-}
+    let state = controller.state();
+    let cmd_tx = controller.command_sender();
 
-#[derive(Default)]
-struct DeviceEntry {
-    name: Option<String>,
-    power_state: PowerState,
-}
+    let title = window_title(args.title.as_deref(), &state.blocking_lock().config);
 
-async fn start_scan(app_state: Arc<Mutex<AppState>>) {
-    if app_state.lock().await.start_scan().await.is_err() {
-        app_state.lock().await.error_state = Some(ErrorState::StartFailed);
-    } else {
-        app_state.lock().await.error_state = None;
-    }
+    let app = App {
+        state,
+        cmd_tx,
+        show_diagnostics: false,
+        show_settings: false,
+        title_override: args.title,
+        title: title.clone(),
+    };
 
-    let mut event_stream = app_state.lock().await.scanner.device_event_stream();
-
-    tokio::task::spawn(async move {
-        while let Some(event) = event_stream.next().await {
-            match event {
-                DeviceEvent::Discovered(device) => {
-                    app_state
-                        .lock()
-                        .await
-                        .insert_device(device.address(), device.clone())
-                        .await;
-                }
-                DeviceEvent::Updated(device) => {
-                    if let Some(d) = app_state
-                        .lock()
-                        .await
-                        .device_entries
-                        .get_mut(&device.address())
-                    {
-                        d.name = device.local_name().await;
-                    }
-                }
-                _ => {}
-            }
-        }
-    });
-}
+    // Creating the window panics if there is no display or no usable graphics driver, e.g. over
+    // some remote desktop sessions. Panics after the app has been created are real bugs.
+    let created = Arc::new(AtomicBool::new(false));
 
-#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
-enum PowerState {
-    On,
-    Standby,
-    Sleep,
-    Starting,
-    Unknown,
-}
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let created = created.clone();
+
+        eframe::run_native(
+            &title,
+            native_options,
+            Box::new(move |_| {
+                created.store(true, Ordering::SeqCst);
+                Box::new(app)
+            }),
+        );
+    }));
 
-impl From<&[u8]> for PowerState {
-    fn from(data: &[u8]) -> Self {
-        match data {
-            &[0x00] => PowerState::Sleep,
-            &[0x01] | &[0x0B] => PowerState::On,
-            &[0x02] => PowerState::Standby,
-            &[0x09] => PowerState::Starting,
-            _ => PowerState::Unknown,
+    if let Err(e) = result {
+        if created.load(Ordering::SeqCst) {
+            panic::resume_unwind(e);
         }
+
+        let reason = e
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| e.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown error");
+
+        log::error!("Could not create the window: {}", reason);
+        run_without_window(controller);
     }
 }
 
-impl Display for PowerState {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            PowerState::On => "On",
-            PowerState::Standby => "Standby",
-            PowerState::Sleep => "Sleep",
-            PowerState::Starting => "Starting",
-            PowerState::Unknown => "Unknown",
-        })
+/// Explains why a window can't be opened, if that is known before trying.
+fn missing_display() -> Option<&'static str> {
+    if cfg!(target_os = "linux")
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+    {
+        return Some("neither DISPLAY nor WAYLAND_DISPLAY is set");
     }
+
+    None
 }
 
-impl Default for PowerState {
-    fn default() -> Self {
-        PowerState::Unknown
+/// Falls back to the terminal interface when the window can't be used, or to running the
+/// backend alone if there is no terminal either.
+fn run_without_window(controller: LighthouseController) {
+    if io::stdout().is_tty() {
+        log::error!("Falling back to the terminal interface");
+
+        if let Err(e) = tui::run(&controller) {
+            log::error!("Terminal interface failed: {}", e);
+        }
+
+        controller.shutdown();
+    } else {
+        log::error!("Running without an interface. Use --watch or --tui to choose explicitly");
+        controller.wait();
     }
 }
 
-enum Command {
-    StartScan,
-    ChangePowerState(BDAddr, PowerStateCommand),
+/// Power commands with a launch target wait for the new state, so that the target is not opened
+/// before the devices are on.
+fn wait_for_state(args: &Args) -> bool {
+    args.wait_for_state || args.launch_target().is_some()
 }
 
-enum PowerStateCommand {
-    On,
-    Sleep,
-    Standby,
+/// What to do about an instance that is already running.
+enum RunningInstance {
+    /// The request was handed over to it, so there is nothing left to do.
+    Handled,
+    /// Show the devices of the instance listening at this IPC path and control them through it.
+    Connect(String),
 }
 
-impl From<PowerStateCommand> for u8 {
-    fn from(cmd: PowerStateCommand) -> u8 {
-        match cmd {
-            PowerStateCommand::On => 0x01,
-            PowerStateCommand::Sleep => 0x00,
-            PowerStateCommand::Standby => 0x02,
+/// Finds an instance that is already running, such as the background service, as two instances
+/// would fight over the Bluetooth adapter. Returns `None` if there is no such instance.
+fn running_instance(config: &Config, args: &Args) -> Option<RunningInstance> {
+    let path = config.ipc_path.clone().unwrap_or_else(ipc::default_path);
+    let mut client = ipc::Client::connect(&path).ok()?;
+
+    if args.launch {
+        match client.request("launch") {
+            Ok(_) => log::info!("Asked the running instance to launch"),
+            Err(e) => log::error!("Could not ask the running instance to launch: {}", e),
         }
+
+        Some(RunningInstance::Handled)
+    } else if args.daemon || args.watch {
+        log::error!("Another instance is already running");
+        Some(RunningInstance::Handled)
+    } else {
+        log::info!("Connecting to the running instance at {}", path);
+        Some(RunningInstance::Connect(path))
     }
 }
 
-#[tokio::main]
-async fn ble_thread(app_state: Arc<Mutex<AppState>>, mut cmd_rx: Receiver<Command>) {
-    start_scan(app_state.clone()).await;
-
-    let poll_task = {
-        let app_state = app_state.clone();
-        tokio::task::spawn(async move {
-            loop {
-                let devices = app_state.lock().await.ble_devices.clone();
-
-                for (addr, device) in devices {
-                    if let Ok(Some(power)) = device.characteristic(POWER_UUID).await {
-                        if let Ok(data) = power.read().await {
-                            let state = data.as_slice().into();
-
-                            if state != PowerState::Unknown {
-                                if let Some(mut d) =
-                                    app_state.lock().await.device_entries.get_mut(&addr)
-                                {
-                                    d.power_state = state;
-                                }
-                            }
-                        }
-                    }
-                }
+/// Quits the app when SteamVR exits, as SteamVR only stops the apps that use OpenVR themselves.
+fn quit_with_steamvr(state: Arc<Mutex<AppState>>, cmd_tx: Sender<Command>) {
+    std::thread::spawn(move || {
+        let mut was_running = false;
 
-                sleep(STATE_POLL_INTERVAL).await;
-            }
-        })
-    };
+        loop {
+            std::thread::sleep(Duration::from_secs(3));
+
+            let running = steamvr::is_running();
+
+            if was_running && !running {
+                log::info!("SteamVR exited, quitting");
+                state.blocking_lock().config.save();
+
+                // Apply the exit power state first, as on_exit is not called
+                let (reply_tx, reply_rx) = oneshot::channel();
 
-    let cmd_task = {
-        let app_state = app_state.clone();
-
-        tokio::task::spawn(async move {
-            while let Some(cmd) = cmd_rx.recv().await {
-                match cmd {
-                    Command::StartScan => {
-                        start_scan(app_state.clone()).await;
-                    }
-                    Command::ChangePowerState(addr, state) => {
-                        if let Some(device) = app_state.lock().await.ble_devices.get(&addr) {
-                            if let Ok(Some(power)) = device.characteristic(POWER_UUID).await {
-                                if let Err(e) = power.write_command(&[state.into()]).await {
-                                    println!("Could not send command to device: {:?}", e);
-                                }
-                            }
-                        }
-                    }
+                if cmd_tx.blocking_send(Command::Shutdown(reply_tx)).is_ok() {
+                    reply_rx.blocking_recv().ok();
                 }
+
+                std::process::exit(0);
             }
-        })
-    };
 
-    poll_task.await.unwrap();
-    cmd_task.await.unwrap();
+            was_running = running;
+        }
+    });
 }
 
-fn ui_device_list(ui: &mut Ui, cmd_tx: &Sender<Command>, app_state: &mut MutexGuard<AppState>) {
-    egui::Grid::new("grid")
-        .num_columns(3)
-        .striped(true)
-        .spacing([15.0, 4.0])
-        .show(ui, |ui| {
-            for (addr, device) in &mut app_state.device_entries {
-                ui_device_entry(ui, cmd_tx, addr, device);
-            }
-        });
+/// The title given on the command line, the configured title, or the default one.
+fn window_title(title_override: Option<&str>, config: &Config) -> String {
+    let title = title_override.unwrap_or_else(|| config.window_title.trim());
+
+    if title.is_empty() {
+        DEFAULT_TITLE.to_string()
+    } else {
+        title.to_string()
+    }
+}
+
+struct App {
+    state: Arc<Mutex<AppState>>,
+    cmd_tx: Sender<Command>,
+    show_diagnostics: bool,
+    show_settings: bool,
+    /// Title given on the command line, which takes precedence over the settings.
+    title_override: Option<String>,
+    /// Title currently shown on the window.
+    title: String,
 }
 
-fn ui_device_entry(ui: &mut Ui, cmd_tx: &Sender<Command>, addr: &BDAddr, device: &mut DeviceEntry) {
-    let power_state = device.power_state;
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
+        let mut state = self.state.blocking_lock();
+
+        ctx.options().screen_reader = state.config.screen_reader;
 
-    ui.horizontal(|ui| {
-        ui.label("Name: ");
-        if let Some(name) = device.name.as_ref() {
-            ui.label(name);
-        } else {
-            ui.label("?");
+        if state.config.panic_off_shortcut {
+            let input = ctx.input();
+
+            if input.modifiers.ctrl && input.modifiers.shift && input.key_pressed(egui::Key::S) {
+                panic_off(&mut state);
+            }
         }
-    });
 
-    ui.horizontal(|ui| {
-        ui.label("State: ");
-        ui.label(power_state.to_string());
-    });
+        let undo_pressed = {
+            let input = ctx.input();
+            input.modifiers.command && input.key_pressed(egui::Key::Z)
+        };
 
-    ui.allocate_ui(ui.available_size(), |ui| {
-        ui.with_layout(Layout::right_to_left(), |ui| {
-            ui.horizontal(|ui| {
-                if ui
-                    .add_enabled(
-                        ![PowerState::Standby, PowerState::Unknown].contains(&power_state),
-                        egui::Button::new("stand by"),
-                    )
-                    .clicked()
-                {
-                    device.power_state = PowerState::Standby;
-                    cmd_tx
-                        .blocking_send(Command::ChangePowerState(*addr, PowerStateCommand::Standby))
-                        .ok();
-                }
+        if undo_pressed {
+            undo(&self.cmd_tx, &mut state);
+        }
 
-                if ui
-                    .add_enabled(
-                        ![PowerState::Sleep, PowerState::Unknown].contains(&power_state),
-                        egui::Button::new("sleep"),
-                    )
-                    .clicked()
-                {
-                    device.power_state = PowerState::Sleep;
-                    cmd_tx
-                        .blocking_send(Command::ChangePowerState(*addr, PowerStateCommand::Sleep))
-                        .ok();
-                }
+        handle_selection_keys(ctx, &self.cmd_tx, &mut state);
 
-                if ui
-                    .add_enabled(
-                        [PowerState::Sleep, PowerState::Standby].contains(&power_state),
-                        egui::Button::new("on"),
-                    )
-                    .clicked()
-                {
-                    device.power_state = PowerState::Starting;
-                    cmd_tx
-                        .blocking_send(Command::ChangePowerState(*addr, PowerStateCommand::On))
-                        .ok();
-                }
-            });
+        let mut toggle_compact = false;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if state.config.compact_mode {
+                toggle_compact = ui_compact_bar(ui, &self.cmd_tx, &mut state);
+            } else {
+                toggle_compact = ui_header(
+                    ui,
+                    &self.cmd_tx,
+                    &mut state,
+                    &mut self.show_diagnostics,
+                    &mut self.show_settings,
+                );
+                ui_channel_conflicts(ui, &self.cmd_tx, &mut state);
+                ui_steamvr_conflict(ui, &mut state);
+                ui.horizontal(|ui| ui_all_power_buttons(ui, &self.cmd_tx, &mut state));
+                ui_bulk_actions(ui, &self.cmd_tx, &mut state);
+                ui.separator();
+                ui_device_list(ui, &self.cmd_tx, &mut state);
+            }
         });
-    });
 
-    ui.end_row();
-}
+        if toggle_compact {
+            let size = ctx.input().screen_rect().size();
+            let current_size = [size.x, size.y];
+            let config = &mut state.config;
 
-fn ui_header(ui: &mut Ui, cmd_tx: &Sender<Command>, app_state: &mut MutexGuard<AppState>) {
-    ui.horizontal(|ui| {
-        match app_state.error_state {
-            Some(ErrorState::StartFailed) => {
-                ui.label("Scan failed. Is bluetooth enabled?");
-            }
-            None => {
-                if app_state.scanner.is_active() {
-                    egui::Spinner::default().ui(ui);
-                    ui.label("Scanning for base stations");
-                } else {
-                    ui.label(format!("Found {} devices", app_state.device_entries.len()));
-                }
+            if config.compact_mode {
+                config.compact_window_size = current_size;
+            } else {
+                config.window_size = current_size;
             }
+
+            config.compact_mode = !config.compact_mode;
+
+            frame.set_window_size(if config.compact_mode {
+                config.compact_window_size.into()
+            } else {
+                config.window_size.into()
+            });
+
+            config.save();
+        }
+
+        let title = window_title(self.title_override.as_deref(), &state.config);
+
+        if title != self.title {
+            frame.set_window_title(&title);
+            self.title = title;
         }
 
-        ui.allocate_ui(ui.available_size(), |ui| {
-            ui.with_layout(Layout::right_to_left(), |ui| {
-                if ui
-                    .add_enabled(!app_state.scanner.is_active(), egui::Button::new("🔃"))
-                    .clicked()
-                {
-                    cmd_tx.blocking_send(Command::StartScan).ok();
+        ui_reboot_confirmation(ctx, &self.cmd_tx, &mut state);
+        ui_power_change_confirmation(ctx, &self.cmd_tx, &mut state);
+
+        egui::Window::new(t("diagnostics"))
+            .open(&mut self.show_diagnostics)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| ui_diagnostics(ui, &mut state));
+
+        egui::Window::new(t("settings"))
+            .open(&mut self.show_settings)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if ui_settings(ui, &mut state.config) {
+                    state.config.save();
                 }
+
+                if ui_schedules(ui, &mut state.config) {
+                    state.config.save();
+                }
+
+                ui.separator();
+                ui_steamvr_import(ui, &mut state);
+                ui_steamvr_registration(ui, &state.config);
             });
-        });
-    });
+
+        ctx.request_repaint();
+    }
+
+    fn on_exit(&mut self, _gl: &eframe::glow::Context) {
+        // Wait for the exit power state to be written, as the process ends right after this
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if self
+            .cmd_tx
+            .blocking_send(Command::Shutdown(reply_tx))
+            .is_ok()
+        {
+            reply_rx.blocking_recv().ok();
+        }
+    }
 }
@@ -0,0 +1,205 @@
+//! Minimal HTTP endpoint serving device state and poll health in the Prometheus text format, and
+//! optionally a status overlay page that can be added as a browser source when streaming.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use bleasy::BDAddr;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+use crate::power::PowerState;
+use crate::state::AppState;
+use crate::status;
+
+const OVERLAY_PAGE: &str = include_str!("overlay.html");
+
+pub async fn serve(port: u16, app_state: Arc<Mutex<AppState>>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+
+        tokio::task::spawn(handle_connection(stream, app_state.clone()));
+    }
+}
+
+async fn handle_connection(stream: TcpStream, app_state: Arc<Mutex<AppState>>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let request_line = match lines.next_line().await {
+        Ok(Some(line)) => line,
+        _ => return,
+    };
+
+    // Skip the headers
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|target| target.split('?').next());
+
+    let response = {
+        let app_state = app_state.lock().await;
+        let config = &app_state.config;
+
+        match path {
+            Some("/metrics") if config.metrics_enabled => {
+                ok("text/plain; version=0.0.4", &render(&app_state))
+            }
+            Some("/overlay") if config.overlay_enabled => {
+                ok("text/html; charset=utf-8", OVERLAY_PAGE)
+            }
+            Some("/overlay.json") if config.overlay_enabled => ok(
+                "application/json",
+                &Value::Array(status::device_records(&app_state)).to_string(),
+            ),
+            Some("/overlay.css") if config.overlay_enabled => {
+                ok("text/css", &overlay_css(config.overlay_css.as_deref()))
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+        }
+    };
+
+    writer.write_all(response.as_bytes()).await.ok();
+}
+
+fn ok(content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Reads the user's overlay stylesheet. Without one the overlay keeps its built-in style.
+fn overlay_css(path: Option<&str>) -> String {
+    let path = match path {
+        Some(path) => path,
+        None => return String::new(),
+    };
+
+    std::fs::read_to_string(path).unwrap_or_else(|e| {
+        log::warn!("Could not read overlay stylesheet {}: {}", path, e);
+        String::new()
+    })
+}
+
+fn render(app_state: &AppState) -> String {
+    let mut out = String::new();
+    let mut devices: Vec<_> = app_state.device_entries.iter().collect();
+    devices.sort_by_key(|(addr, _)| addr.to_string());
+
+    let labels = |addr: &BDAddr, name: Option<&str>| {
+        format!(
+            "addr=\"{}\",name=\"{}\"",
+            addr,
+            escape(name.unwrap_or_default())
+        )
+    };
+
+    out.push_str(
+        "# HELP lighthouse_power_state Power state: 0 sleep, 1 on, 2 standby, 3 starting, \
+         -1 unknown.\n# TYPE lighthouse_power_state gauge\n",
+    );
+
+    for (addr, device) in &devices {
+        let name = device.alias.as_deref().or(device.name.as_deref());
+
+        writeln!(
+            out,
+            "lighthouse_power_state{{{}}} {}",
+            labels(addr, name),
+            state_value(device.power_state)
+        )
+        .ok();
+    }
+
+    out.push_str("# HELP lighthouse_rssi Signal strength in dBm.\n# TYPE lighthouse_rssi gauge\n");
+
+    for (addr, device) in &devices {
+        if let Some(rssi) = device.rssi {
+            let name = device.alias.as_deref().or(device.name.as_deref());
+            writeln!(out, "lighthouse_rssi{{{}}} {}", labels(addr, name), rssi).ok();
+        }
+    }
+
+    out.push_str(
+        "# HELP lighthouse_temperature_celsius Last read device temperature.\n\
+         # TYPE lighthouse_temperature_celsius gauge\n",
+    );
+
+    for (addr, device) in &devices {
+        if let Some((temperature, _)) = device.temperature {
+            let name = device.alias.as_deref().or(device.name.as_deref());
+
+            writeln!(
+                out,
+                "lighthouse_temperature_celsius{{{}}} {}",
+                labels(addr, name),
+                temperature
+            )
+            .ok();
+        }
+    }
+
+    let stats = &app_state.poll_stats;
+
+    writeln!(
+        out,
+        "# HELP lighthouse_poll_reads_total Successful state reads.\n\
+         # TYPE lighthouse_poll_reads_total counter\n\
+         lighthouse_poll_reads_total {}\n\
+         # HELP lighthouse_poll_failures_total Failed state reads.\n\
+         # TYPE lighthouse_poll_failures_total counter\n\
+         lighthouse_poll_failures_total {}\n\
+         # HELP lighthouse_poll_cycle_seconds Duration of the last poll cycle.\n\
+         # TYPE lighthouse_poll_cycle_seconds gauge\n\
+         lighthouse_poll_cycle_seconds {}",
+        stats.reads_ok,
+        stats.reads_failed,
+        stats.last_cycle_time.as_secs_f64()
+    )
+    .ok();
+
+    if let Some(duration) = app_state.scan_duration() {
+        writeln!(
+            out,
+            "# HELP lighthouse_scan_duration_seconds Duration of the current or last scan.\n\
+             # TYPE lighthouse_scan_duration_seconds gauge\n\
+             lighthouse_scan_duration_seconds {}",
+            duration.as_secs_f64()
+        )
+        .ok();
+    }
+
+    out
+}
+
+fn state_value(state: PowerState) -> i8 {
+    match state {
+        PowerState::Sleep => 0,
+        PowerState::On => 1,
+        PowerState::Standby => 2,
+        PowerState::Starting => 3,
+        PowerState::Unknown => -1,
+    }
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,245 @@
+//! OSC endpoint for tools that already speak OSC, such as VRChat avatar parameters or show
+//! control software.
+//!
+//! `/lighthouse/all/power` and `/lighthouse/<serial>/power` take a power state as a string, or a
+//! bool, int or float where true or non-zero means on and false or zero means sleep. State changes
+//! are sent back out as `/lighthouse/<serial>/state` with the state as a string.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bleasy::BDAddr;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{AppState, Command};
+
+const BROADCAST_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_PACKET_SIZE: usize = 1536;
+
+/// An argument of an OSC message.
+#[derive(Clone, Debug, PartialEq)]
+enum Arg {
+    Int(i32),
+    Float(f32),
+    Str(String),
+    Bool(bool),
+}
+
+/// Receives OSC messages on `port` and, if `send_to` is set, sends the device states there.
+pub async fn serve(
+    port: u16,
+    send_to: Option<String>,
+    app_state: Arc<Mutex<AppState>>,
+    cmd_tx: Sender<Command>,
+) -> std::io::Result<()> {
+    let socket = Arc::new(UdpSocket::bind(("0.0.0.0", port)).await?);
+
+    if let Some(target) = send_to {
+        tokio::task::spawn(broadcast(socket.clone(), target, app_state.clone()));
+    }
+
+    let mut buf = [0; MAX_PACKET_SIZE];
+
+    loop {
+        let (len, _) = socket.recv_from(&mut buf).await?;
+        let mut messages = Vec::new();
+
+        if parse_packet(&buf[..len], &mut messages).is_none() {
+            log::debug!("Ignoring a malformed OSC packet");
+        }
+
+        for (address, args) in messages {
+            handle_message(&address, &args, &app_state, &cmd_tx).await;
+        }
+    }
+}
+
+async fn handle_message(
+    address: &str,
+    args: &[Arg],
+    app_state: &Mutex<AppState>,
+    cmd_tx: &Sender<Command>,
+) {
+    let parts: Vec<&str> = address.split('/').collect();
+
+    let target = match parts.as_slice() {
+        ["", "lighthouse", target, "power"] => *target,
+        _ => {
+            log::debug!("Ignoring OSC message to {}", address);
+            return;
+        }
+    };
+
+    let cmd = match args.first().and_then(power_command) {
+        Some(cmd) => cmd,
+        None => {
+            log::warn!("OSC message to {} has no valid power state", address);
+            return;
+        }
+    };
+
+    let command = if target == "all" {
+        Command::ChangeAllPowerStates(cmd)
+    } else {
+        let addr = app_state
+            .lock()
+            .await
+            .device_entries
+            .iter()
+            .find(|(_, d)| d.serial().is_some_and(|s| s.eq_ignore_ascii_case(target)))
+            .map(|(addr, _)| *addr);
+
+        match addr {
+            Some(addr) => Command::ChangePowerState(addr, cmd, None),
+            None => {
+                log::warn!("OSC message for unknown device {}", target);
+                return;
+            }
+        }
+    };
+
+    cmd_tx.send(command).await.ok();
+}
+
+fn power_command(arg: &Arg) -> Option<PowerStateCommand> {
+    let on = match arg {
+        Arg::Str(state) => return PowerStateCommand::from_str(state).ok(),
+        Arg::Bool(on) => *on,
+        Arg::Int(value) => *value != 0,
+        Arg::Float(value) => *value != 0.0,
+    };
+
+    Some(if on {
+        PowerStateCommand::On
+    } else {
+        PowerStateCommand::Sleep
+    })
+}
+
+/// Sends the state of every device when it changes, and of every device found.
+async fn broadcast(socket: Arc<UdpSocket>, target: String, app_state: Arc<Mutex<AppState>>) {
+    let mut last_states: HashMap<BDAddr, PowerState> = HashMap::new();
+
+    loop {
+        let messages: Vec<_> = {
+            let app_state = app_state.lock().await;
+
+            app_state
+                .device_entries
+                .iter()
+                .filter(|(addr, d)| {
+                    last_states.insert(**addr, d.power_state) != Some(d.power_state)
+                })
+                .filter_map(|(_, d)| {
+                    let address = format!("/lighthouse/{}/state", d.serial()?);
+                    let state = d.power_state.to_string().to_ascii_lowercase();
+
+                    Some(encode_message(&address, &[Arg::Str(state)]))
+                })
+                .collect()
+        };
+
+        for message in messages {
+            if let Err(e) = socket.send_to(&message, target.as_str()).await {
+                log::warn!("Could not send OSC message to {}: {}", target, e);
+            }
+        }
+
+        sleep(BROADCAST_INTERVAL).await;
+    }
+}
+
+/// Parses a message or a bundle of them. Returns `None` if the packet is malformed, keeping the
+/// messages parsed before the error.
+fn parse_packet(data: &[u8], messages: &mut Vec<(String, Vec<Arg>)>) -> Option<()> {
+    if let Some(mut elements) = data.strip_prefix(b"#bundle\0") {
+        // Skip the time tag, the messages are handled right away
+        elements = elements.get(8..)?;
+
+        while !elements.is_empty() {
+            let size = u32::from_be_bytes(elements.get(..4)?.try_into().ok()?) as usize;
+            parse_packet(elements.get(4..4 + size)?, messages)?;
+            elements = &elements[4 + size..];
+        }
+
+        return Some(());
+    }
+
+    let mut pos = 0;
+    let address = read_string(data, &mut pos)?;
+    let tags = read_string(data, &mut pos).unwrap_or_else(|| ",".to_string());
+    let mut args = Vec::new();
+
+    for tag in tags.strip_prefix(',')?.chars() {
+        let arg = match tag {
+            'i' => Arg::Int(i32::from_be_bytes(read_bytes(data, &mut pos)?)),
+            'f' => Arg::Float(f32::from_be_bytes(read_bytes(data, &mut pos)?)),
+            's' => Arg::Str(read_string(data, &mut pos)?),
+            'T' => Arg::Bool(true),
+            'F' => Arg::Bool(false),
+            // Other types can't be skipped without knowing their size
+            _ => break,
+        };
+
+        args.push(arg);
+    }
+
+    messages.push((address, args));
+    Some(())
+}
+
+fn read_bytes(data: &[u8], pos: &mut usize) -> Option<[u8; 4]> {
+    let bytes = data.get(*pos..*pos + 4)?.try_into().ok()?;
+    *pos += 4;
+    Some(bytes)
+}
+
+/// Reads a null terminated string padded to a multiple of four bytes.
+fn read_string(data: &[u8], pos: &mut usize) -> Option<String> {
+    let len = data.get(*pos..)?.iter().position(|b| *b == 0)?;
+    let string = std::str::from_utf8(&data[*pos..*pos + len])
+        .ok()?
+        .to_string();
+    *pos = (*pos + len + 4) & !3;
+    Some(string)
+}
+
+fn encode_message(address: &str, args: &[Arg]) -> Vec<u8> {
+    let mut tags = ",".to_string();
+    let mut data = Vec::new();
+
+    for arg in args {
+        match arg {
+            Arg::Int(value) => {
+                tags.push('i');
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            Arg::Float(value) => {
+                tags.push('f');
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            Arg::Str(value) => {
+                tags.push('s');
+                write_string(&mut data, value);
+            }
+            Arg::Bool(value) => tags.push(if *value { 'T' } else { 'F' }),
+        }
+    }
+
+    let mut message = Vec::new();
+    write_string(&mut message, address);
+    write_string(&mut message, &tags);
+    message.extend(data);
+    message
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(value.as_bytes());
+    out.resize((out.len() + 4) & !3, 0);
+}
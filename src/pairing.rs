@@ -0,0 +1,45 @@
+//! Pairing (bonding) with devices that refuse GATT access until they are bonded.
+//!
+//! bleasy does not expose pairing, so this goes through the operating system instead.
+
+use bleasy::{BDAddr, Error};
+
+/// Returns true if the error looks like the device refused access because it is not bonded.
+pub fn is_auth_error(error: &Error) -> bool {
+    let error = format!("{:?}", error).to_lowercase();
+
+    [
+        "auth",
+        "insufficient",
+        "accessdenied",
+        "permissiondenied",
+        "pair",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+#[cfg(target_os = "linux")]
+pub async fn pair(addr: BDAddr) -> Result<(), String> {
+    let output = tokio::process::Command::new("bluetoothctl")
+        .arg("pair")
+        .arg(addr.to_string())
+        .output()
+        .await
+        .map_err(|e| format!("Could not run bluetoothctl: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    if output.status.success() && !stdout.contains("Failed to pair") {
+        Ok(())
+    } else {
+        Err(stdout.lines().last().unwrap_or_default().trim().to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn pair(_addr: BDAddr) -> Result<(), String> {
+    Err("Pairing is not supported on this platform. \
+         Pair the base station from the system Bluetooth settings instead."
+        .to_string())
+}
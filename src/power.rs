@@ -0,0 +1,206 @@
+//! Base station power states and the commands for changing them.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Default)]
+pub enum PowerState {
+    On,
+    Standby,
+    Sleep,
+    Starting,
+    #[default]
+    Unknown,
+}
+
+impl From<&[u8]> for PowerState {
+    fn from(data: &[u8]) -> Self {
+        match data {
+            &[0x00] => PowerState::Sleep,
+            &[0x01] | &[0x0B] => PowerState::On,
+            &[0x02] => PowerState::Standby,
+            &[0x09] => PowerState::Starting,
+            _ => PowerState::Unknown,
+        }
+    }
+}
+
+impl Display for PowerState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PowerState::On => "On",
+            PowerState::Standby => "Standby",
+            PowerState::Sleep => "Sleep",
+            PowerState::Starting => "Starting",
+            PowerState::Unknown => "Unknown",
+        })
+    }
+}
+
+impl FromStr for PowerState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "on" => Ok(PowerState::On),
+            "standby" => Ok(PowerState::Standby),
+            "sleep" => Ok(PowerState::Sleep),
+            "starting" => Ok(PowerState::Starting),
+            "unknown" => Ok(PowerState::Unknown),
+            _ => Err(format!("Unknown power state: {}", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerStateCommand {
+    On,
+    Sleep,
+    Standby,
+}
+
+impl PowerStateCommand {
+    /// The state shown in the UI right after the command has been sent.
+    pub fn expected_state(self) -> PowerState {
+        match self {
+            PowerStateCommand::On => PowerState::Starting,
+            PowerStateCommand::Sleep => PowerState::Sleep,
+            PowerStateCommand::Standby => PowerState::Standby,
+        }
+    }
+
+    /// Returns the command that puts a device into `state`, if there is one.
+    pub fn for_state(state: PowerState) -> Option<Self> {
+        match state {
+            PowerState::On | PowerState::Starting => Some(PowerStateCommand::On),
+            PowerState::Standby => Some(PowerStateCommand::Standby),
+            PowerState::Sleep => Some(PowerStateCommand::Sleep),
+            PowerState::Unknown => None,
+        }
+    }
+
+    /// Returns the command that flips a device in `from` between on and sleep. Standby counts as
+    /// off.
+    pub fn toggle(from: PowerState) -> Option<Self> {
+        match from {
+            PowerState::On | PowerState::Starting => Some(PowerStateCommand::Sleep),
+            PowerState::Standby | PowerState::Sleep => Some(PowerStateCommand::On),
+            PowerState::Unknown => None,
+        }
+    }
+
+    /// The state the device reports once the command has taken effect.
+    pub fn target_state(self) -> PowerState {
+        match self {
+            PowerStateCommand::On => PowerState::On,
+            PowerStateCommand::Sleep => PowerState::Sleep,
+            PowerStateCommand::Standby => PowerState::Standby,
+        }
+    }
+}
+
+/// The commands that make sense to send to a device in `from`. Commands that would not change the
+/// state are left out, and nothing can be sent while the state is unknown.
+pub fn allowed_transitions(from: PowerState) -> &'static [PowerStateCommand] {
+    match from {
+        PowerState::On | PowerState::Starting => {
+            &[PowerStateCommand::Standby, PowerStateCommand::Sleep]
+        }
+        PowerState::Standby => &[PowerStateCommand::On, PowerStateCommand::Sleep],
+        PowerState::Sleep => &[PowerStateCommand::On, PowerStateCommand::Standby],
+        PowerState::Unknown => &[],
+    }
+}
+
+impl FromStr for PowerStateCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "on" => Ok(PowerStateCommand::On),
+            "sleep" => Ok(PowerStateCommand::Sleep),
+            "standby" => Ok(PowerStateCommand::Standby),
+            _ => Err(format!("Unknown power state: {}", s)),
+        }
+    }
+}
+
+impl From<PowerStateCommand> for u8 {
+    fn from(cmd: PowerStateCommand) -> u8 {
+        match cmd {
+            PowerStateCommand::On => 0x01,
+            PowerStateCommand::Sleep => 0x00,
+            PowerStateCommand::Standby => 0x02,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::state::DeviceEntry;
+
+    use PowerStateCommand::{On, Sleep, Standby};
+
+    #[test]
+    fn allowed_transitions_from_each_state() {
+        assert_eq!(allowed_transitions(PowerState::On), &[Standby, Sleep]);
+        assert_eq!(allowed_transitions(PowerState::Starting), &[Standby, Sleep]);
+        assert_eq!(allowed_transitions(PowerState::Standby), &[On, Sleep]);
+        assert_eq!(allowed_transitions(PowerState::Sleep), &[On, Standby]);
+        assert!(allowed_transitions(PowerState::Unknown).is_empty());
+    }
+
+    fn device(
+        power_state: PowerState,
+        last_known_state: Option<(PowerState, Instant)>,
+    ) -> DeviceEntry {
+        DeviceEntry {
+            power_state,
+            last_known_state,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unknown_device_can_only_be_turned_on_if_recently_seen() {
+        assert!(!device(PowerState::Unknown, None).can_send(On));
+
+        let asleep = device(
+            PowerState::Unknown,
+            Some((PowerState::Sleep, Instant::now())),
+        );
+        assert!(asleep.can_send(On));
+        assert!(!asleep.can_send(Standby));
+        assert!(!asleep.can_send(Sleep));
+
+        let on = device(PowerState::Unknown, Some((PowerState::On, Instant::now())));
+        assert!(!on.can_send(On));
+
+        if let Some(long_ago) = Instant::now().checked_sub(Duration::from_secs(600)) {
+            let stale = device(PowerState::Unknown, Some((PowerState::Sleep, long_ago)));
+            assert!(!stale.can_send(On));
+        }
+    }
+
+    #[test]
+    fn backend_accepts_commands_by_last_polled_state() {
+        // The UI has already shown the expected state when the command reaches the backend
+        let starting = device(
+            PowerState::Starting,
+            Some((PowerState::Sleep, Instant::now())),
+        );
+        assert!(starting.accepts(On));
+
+        let on = device(PowerState::On, Some((PowerState::On, Instant::now())));
+        assert!(on.accepts(On));
+        assert!(on.accepts(Sleep));
+
+        assert!(!device(PowerState::Unknown, None).accepts(On));
+    }
+}
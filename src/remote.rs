@@ -0,0 +1,217 @@
+//! Runs in place of the BLE backend when another instance, usually the background service, owns
+//! the Bluetooth adapter. The device states are mirrored from its IPC endpoint and the commands
+//! are forwarded to it, so the interfaces work the same either way.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bleasy::BDAddr;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Mutex;
+use tokio::time::interval;
+
+use crate::backend::Backend;
+use crate::ipc::Client;
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{AppState, Command, CommandError};
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The instance listening at `path`, used in place of Bluetooth.
+pub struct Remote {
+    pub path: String,
+}
+
+impl Backend for Remote {
+    fn run(
+        &self,
+        app_state: Arc<Mutex<AppState>>,
+        cmd_rx: Receiver<Command>,
+    ) -> BoxFuture<'static, ()> {
+        Box::pin(run(app_state, cmd_rx, self.path.clone()))
+    }
+
+    // The running instance does the automation
+    fn automated(&self) -> bool {
+        false
+    }
+}
+
+/// Mirrors and controls the instance listening at `path` until it receives a shutdown.
+pub async fn run(app_state: Arc<Mutex<AppState>>, mut cmd_rx: Receiver<Command>, path: String) {
+    let client = match Client::connect(&path) {
+        Ok(client) => Arc::new(std::sync::Mutex::new(client)),
+        Err(e) => {
+            log::error!(
+                "Could not connect to the running instance at {}: {}",
+                path,
+                e
+            );
+            return;
+        }
+    };
+
+    log::info!("Connected to the running instance at {}", path);
+
+    let mut ticker = interval(REFRESH_INTERVAL);
+    let panic_off = app_state.lock().await.panic_off.clone();
+
+    loop {
+        tokio::select! {
+            _ = panic_off.notified() => {
+                request(&client, "panic".to_string()).await;
+            }
+            cmd = cmd_rx.recv() => {
+                let cmd = match cmd {
+                    Some(cmd) => cmd,
+                    None => return,
+                };
+
+                let lines = match cmd {
+                    Command::StartScan => vec!["scan".to_string()],
+                    Command::ClearAndScan => vec!["scan clear".to_string()],
+                    Command::ReconnectAll => vec!["reconnect".to_string()],
+                    Command::StopScan => continue,
+                    Command::Pair(_) | Command::AssignUniqueChannels | Command::Reboot(_) => {
+                        log::warn!("{:?} is not available through the running instance", cmd);
+                        continue;
+                    }
+                    Command::ChangePowerState(addr, state, reply) => {
+                        let response = request(&client, set_line(addr, state)).await;
+
+                        if let Some(reply) = reply {
+                            reply
+                                .send(if response["ok"] == true {
+                                    Ok(())
+                                } else {
+                                    Err(CommandError::WriteFailed)
+                                })
+                                .ok();
+                        }
+
+                        continue;
+                    }
+                    Command::ChangeAllPowerStates(state) => vec![format!("all {:?}", state)],
+                    Command::ChangeGroupPowerState(group, state) => app_state
+                        .lock()
+                        .await
+                        .group_members(&group)
+                        .into_iter()
+                        .map(|addr| set_line(addr, state))
+                        .collect(),
+                    Command::ChangePowerStates(addrs, state) => {
+                        addrs.into_iter().map(|addr| set_line(addr, state)).collect()
+                    }
+                    Command::SendPowerStates(addrs, state, reply) => {
+                        for addr in addrs {
+                            request(&client, set_line(addr, state)).await;
+                        }
+
+                        reply.send(()).ok();
+                        continue;
+                    }
+                    Command::SetSleepTimer(duration) => {
+                        app_state.lock().await.sleep_timer_at =
+                            duration.map(|duration| Instant::now() + duration);
+                        continue;
+                    }
+                    // The running instance keeps going, so the devices are left as they are
+                    Command::Shutdown(reply) => {
+                        reply.send(()).ok();
+                        return;
+                    }
+                };
+
+                for line in lines {
+                    request(&client, line).await;
+                }
+            }
+            _ = ticker.tick() => {
+                let due = {
+                    let mut app_state = app_state.lock().await;
+                    let due = app_state.sleep_timer_at.is_some_and(|at| at <= Instant::now());
+
+                    if due {
+                        app_state.sleep_timer_at = None;
+                    }
+
+                    due
+                };
+
+                if due {
+                    request(&client, "all sleep".to_string()).await;
+                }
+
+                let response = request(&client, "list".to_string()).await;
+
+                match response["devices"].as_array() {
+                    Some(devices) => mirror(&mut *app_state.lock().await, devices),
+                    None => log::debug!("Could not list the devices: {}", response),
+                }
+            }
+        }
+    }
+}
+
+fn set_line(addr: BDAddr, state: PowerStateCommand) -> String {
+    format!("set {} {:?}", addr, state)
+}
+
+/// Sends a request without blocking the runtime. Errors are returned as failed responses.
+async fn request(client: &Arc<std::sync::Mutex<Client>>, line: String) -> Value {
+    let client = client.clone();
+
+    let result = tokio::task::spawn_blocking(move || client.lock().unwrap().request(&line)).await;
+
+    match result {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => json!({ "ok": false, "error": e.to_string() }),
+        Err(e) => json!({ "ok": false, "error": e.to_string() }),
+    }
+}
+
+/// Updates the device entries to match the records listed by the running instance.
+fn mirror(app_state: &mut AppState, devices: &[Value]) {
+    let now = Instant::now();
+    let mut listed = Vec::new();
+
+    for record in devices {
+        let addr = match record["address"].as_str().map(BDAddr::from_str) {
+            Some(Ok(addr)) => addr,
+            _ => continue,
+        };
+
+        listed.push(addr);
+
+        if !app_state.device_entries.contains_key(&addr) {
+            let name = record["name"].as_str().map(str::to_string);
+            let entry = app_state.new_entry(addr, name);
+            app_state.device_entries.insert(addr, entry);
+        }
+
+        let device = match app_state.device_entries.get_mut(&addr) {
+            Some(device) => device,
+            None => continue,
+        };
+
+        device.rssi = record["rssi"].as_i64().map(|rssi| rssi as i16);
+        device.reachable = true;
+        device.last_polled_at = Some(now);
+
+        let state = record["state"]
+            .as_str()
+            .and_then(|state| PowerState::from_str(state).ok())
+            .unwrap_or(PowerState::Unknown);
+
+        device.set_polled_state(state);
+    }
+
+    app_state
+        .device_entries
+        .retain(|addr, _| listed.contains(addr));
+    app_state.has_scanned = true;
+    app_state.scan_finished_at = Some(Instant::now());
+}
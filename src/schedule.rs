@@ -0,0 +1,147 @@
+//! Makes the scheduled power changes from the config.
+//!
+//! std has no time zone support, so the offset of local time from UTC is asked from the operating
+//! system and refreshed every now and then to follow daylight saving time.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::state::{AppState, Command};
+
+/// Short enough that no minute is skipped.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const OFFSET_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Makes the changes that are due, once per matching minute.
+pub async fn run(app_state: Arc<Mutex<AppState>>, cmd_tx: Sender<Command>) {
+    let mut offset = utc_offset().await;
+    let mut offset_read_at = Instant::now();
+    let mut last_minute = local_minutes(offset);
+
+    loop {
+        sleep(CHECK_INTERVAL).await;
+
+        if offset_read_at.elapsed() >= OFFSET_REFRESH_INTERVAL {
+            offset = utc_offset().await;
+            offset_read_at = Instant::now();
+        }
+
+        let minute = local_minutes(offset);
+
+        // Minutes missed while the computer was asleep are not made up for
+        if minute == last_minute {
+            continue;
+        }
+
+        last_minute = minute;
+
+        let minute_of_day = minute.rem_euclid(24 * 60) as u32;
+        // 1970-01-01 was a Thursday
+        let weekday = ((minute.div_euclid(24 * 60) + 3).rem_euclid(7) + 1) as u8;
+
+        let commands: Vec<_> = {
+            let app_state = app_state.lock().await;
+
+            app_state
+                .config
+                .schedules
+                .iter()
+                .filter(|schedule| schedule.is_due(weekday, minute_of_day))
+                .map(|schedule| {
+                    log::info!(
+                        "Scheduled {:?} at {} for {}",
+                        schedule.command,
+                        schedule.time,
+                        schedule.group.as_deref().unwrap_or("all base stations")
+                    );
+
+                    match &schedule.group {
+                        Some(group) => {
+                            Command::ChangeGroupPowerState(group.clone(), schedule.command)
+                        }
+                        None => Command::ChangeAllPowerStates(schedule.command),
+                    }
+                })
+                .collect()
+        };
+
+        for command in commands {
+            cmd_tx.send(command).await.ok();
+        }
+    }
+}
+
+/// Minutes since the Unix epoch in local time.
+fn local_minutes(offset_secs: i64) -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+
+    (secs + offset_secs).div_euclid(60)
+}
+
+/// Returns the offset of local time from UTC in seconds, or zero if it can't be read.
+async fn utc_offset() -> i64 {
+    match read_utc_offset().await {
+        Some(offset) => offset,
+        None => {
+            log::warn!("Could not read the time zone, schedules use UTC");
+            0
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn read_utc_offset() -> Option<i64> {
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = tokio::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "[int][TimeZoneInfo]::Local.GetUtcOffset([DateTime]::Now).TotalMinutes",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .await
+        .ok()?;
+
+    let minutes: i64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(minutes * 60)
+}
+
+/// Reads the offset from `date`, which prints it as e.g. `+0200`.
+#[cfg(not(windows))]
+async fn read_utc_offset() -> Option<i64> {
+    let output = tokio::process::Command::new("date")
+        .arg("+%z")
+        .output()
+        .await
+        .ok()?;
+
+    let offset = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let (negative, digits) = match offset.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, offset.strip_prefix('+')?),
+    };
+
+    if digits.len() != 4 {
+        return None;
+    }
+
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    let secs = hours * 3600 + minutes * 60;
+
+    Some(if negative { -secs } else { secs })
+}
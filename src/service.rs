@@ -0,0 +1,137 @@
+//! Installs the app as a background service that runs the automation with `--daemon` from login
+//! onwards. Interfaces started after that connect to the service instead of using Bluetooth
+//! themselves.
+//!
+//! On Linux this is a systemd user service. On Windows it is a scheduled task that runs at logon,
+//! as Windows services run in a session of their own where Bluetooth LE is not usable.
+
+use std::process::Command;
+
+/// Name of the service on Linux and of the scheduled task on Windows.
+#[cfg(target_os = "linux")]
+const SERVICE_NAME: &str = "steamvr-lighthouse-control.service";
+#[cfg(windows)]
+const SERVICE_NAME: &str = "SteamVR Lighthouse Control";
+
+/// Installs the service and starts it.
+#[cfg(target_os = "linux")]
+pub fn install() -> Result<(), String> {
+    let path = unit_path().ok_or("No config directory")?;
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+
+    let contents = format!(
+        "[Unit]\n\
+         Description=SteamVR Lighthouse Control\n\
+         After=bluetooth.target\n\
+         \n\
+         [Service]\n\
+         ExecStart=\"{}\" --daemon\n\
+         Restart=on-failure\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exe.display()
+    );
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", SERVICE_NAME])
+}
+
+/// Stops the service and removes it.
+#[cfg(target_os = "linux")]
+pub fn uninstall() -> Result<(), String> {
+    let path = unit_path().ok_or("No config directory")?;
+
+    if !path.exists() {
+        return Err("The service is not installed".to_string());
+    }
+
+    systemctl(&["disable", "--now", SERVICE_NAME])?;
+    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    systemctl(&["daemon-reload"])
+}
+
+#[cfg(target_os = "linux")]
+fn unit_path() -> Option<std::path::PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join("systemd")
+            .join("user")
+            .join(SERVICE_NAME),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn systemctl(args: &[&str]) -> Result<(), String> {
+    let mut command = Command::new("systemctl");
+    command.arg("--user").args(args);
+
+    run(command)
+}
+
+/// Installs the scheduled task and starts it.
+#[cfg(windows)]
+pub fn install() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let task = format!("\"{}\" --daemon", exe.display());
+
+    schtasks(&[
+        "/Create",
+        "/F",
+        "/SC",
+        "ONLOGON",
+        "/TN",
+        SERVICE_NAME,
+        "/TR",
+        &task,
+    ])?;
+    schtasks(&["/Run", "/TN", SERVICE_NAME])
+}
+
+/// Stops the scheduled task and removes it.
+#[cfg(windows)]
+pub fn uninstall() -> Result<(), String> {
+    // Fails if the task is not running, which is fine
+    schtasks(&["/End", "/TN", SERVICE_NAME]).ok();
+    schtasks(&["/Delete", "/F", "/TN", SERVICE_NAME])
+}
+
+#[cfg(windows)]
+fn schtasks(args: &[&str]) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = Command::new("schtasks");
+    command.args(args).creation_flags(CREATE_NO_WINDOW);
+
+    run(command)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn install() -> Result<(), String> {
+    Err("Not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn uninstall() -> Result<(), String> {
+    Err("Not supported on this platform".to_string())
+}
+
+/// Runs `command` and returns its error output if it fails.
+#[cfg_attr(not(any(target_os = "linux", windows)), allow(dead_code))]
+fn run(mut command: Command) -> Result<(), String> {
+    let output = command.output().map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
@@ -0,0 +1,230 @@
+//! Simulated base stations for testing the UI without Bluetooth hardware.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bleasy::BDAddr;
+use futures::future::BoxFuture;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Mutex;
+use tokio::time::interval;
+
+use crate::backend::Backend;
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{AppState, Command, CommandError, DeviceEntry};
+
+/// How long a simulated device stays in `Starting` before reporting `On`.
+const STARTUP_DURATION: Duration = Duration::from_secs(3);
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Fake devices that react to commands like real ones, in place of Bluetooth.
+pub struct Simulated {
+    pub count: u8,
+}
+
+impl Backend for Simulated {
+    fn run(
+        &self,
+        app_state: Arc<Mutex<AppState>>,
+        cmd_rx: Receiver<Command>,
+    ) -> BoxFuture<'static, ()> {
+        Box::pin(run(app_state, cmd_rx, self.count))
+    }
+}
+
+/// Runs in place of the BLE backend, serving `count` fake devices.
+pub async fn run(app_state: Arc<Mutex<AppState>>, mut cmd_rx: Receiver<Command>, count: u8) {
+    populate(&mut *app_state.lock().await, count);
+
+    // Devices that are starting up, and when they will be on
+    let mut starting: HashMap<BDAddr, Instant> = HashMap::new();
+    let mut ticker = interval(TICK_INTERVAL);
+    let panic_off = app_state.lock().await.panic_off.clone();
+
+    loop {
+        tokio::select! {
+            _ = panic_off.notified() => {
+                starting.clear();
+
+                for device in app_state.lock().await.device_entries.values_mut() {
+                    device.set_polled_state(PowerState::Sleep);
+                }
+            }
+            cmd = cmd_rx.recv() => {
+                let cmd = match cmd {
+                    Some(cmd) => cmd,
+                    None => return,
+                };
+
+                let mut app_state = app_state.lock().await;
+
+                let forced = matches!(cmd, Command::SendPowerStates(..));
+
+                let targets: Vec<(BDAddr, PowerStateCommand)> = match cmd {
+                    // A rescan finds the same simulated devices again
+                    Command::StartScan if !app_state.device_entries.is_empty() => continue,
+                    Command::StartScan | Command::ClearAndScan => {
+                        starting.clear();
+                        populate(&mut app_state, count);
+                        continue;
+                    }
+                    Command::StopScan | Command::Pair(_) | Command::ReconnectAll => continue,
+                    Command::SetSleepTimer(duration) => {
+                        app_state.sleep_timer_at =
+                            duration.map(|duration| Instant::now() + duration);
+                        continue;
+                    }
+                    Command::Shutdown(reply) => {
+                        if let Some(state) = app_state.config.exit_power_state.command() {
+                            for addr in app_state.auto_managed() {
+                                if let Some(device) = app_state.device_entries.get_mut(&addr) {
+                                    device.set_polled_state(state.target_state());
+                                }
+                            }
+                        }
+
+                        reply.send(()).ok();
+                        return;
+                    }
+                    Command::Reboot(addr) => {
+                        if let Some(device) = app_state.device_entries.get_mut(&addr) {
+                            device.set_polled_state(PowerState::Starting);
+                            starting.insert(addr, Instant::now() + STARTUP_DURATION);
+                        }
+
+                        continue;
+                    }
+                    Command::AssignUniqueChannels => {
+                        for (addr, channel) in app_state.unique_channel_assignments() {
+                            if let Some(device) = app_state.device_entries.get_mut(&addr) {
+                                device.channel = Some(channel);
+                            }
+                        }
+
+                        continue;
+                    }
+                    Command::ChangePowerState(addr, state, reply) => {
+                        let result = match app_state.device_entries.get(&addr) {
+                            None => Err(CommandError::UnknownDevice),
+                            Some(_) if app_state.in_cooldown(addr, state) => {
+                                Err(CommandError::Cooldown)
+                            }
+                            Some(device) if !device.accepts(state) => {
+                                Err(CommandError::NotAllowed)
+                            }
+                            Some(_) => Ok(()),
+                        };
+
+                        let accepted = result.is_ok();
+
+                        if let Some(reply) = reply {
+                            reply.send(result).ok();
+                        }
+
+                        if !accepted {
+                            continue;
+                        }
+
+                        vec![(addr, state)]
+                    }
+                    Command::ChangeAllPowerStates(state) => app_state
+                        .auto_managed()
+                        .into_iter()
+                        .map(|addr| (addr, state))
+                        .collect(),
+                    Command::ChangeGroupPowerState(group, state) => app_state
+                        .group_members(&group)
+                        .into_iter()
+                        .map(|addr| (addr, state))
+                        .collect(),
+                    Command::ChangePowerStates(addrs, state) => {
+                        addrs.into_iter().map(|addr| (addr, state)).collect()
+                    }
+                    // Applied below while the state is locked, so it is done by the time it is
+                    // read again
+                    Command::SendPowerStates(addrs, state, reply) => {
+                        reply.send(()).ok();
+                        addrs.into_iter().map(|addr| (addr, state)).collect()
+                    }
+                };
+
+                for (addr, state) in targets {
+                    if !forced && app_state.in_cooldown(addr, state) {
+                        continue;
+                    }
+
+                    app_state.record_command(&[addr], state);
+                    app_state.desired_states.insert(addr, state);
+
+                    if let Some(device) = app_state.device_entries.get_mut(&addr) {
+                        match state {
+                            PowerStateCommand::On if device.power_state != PowerState::On => {
+                                device.set_polled_state(PowerState::Starting);
+                                starting.insert(addr, Instant::now() + STARTUP_DURATION);
+                            }
+                            PowerStateCommand::On => {}
+                            _ => {
+                                device.set_polled_state(state.target_state());
+                                starting.remove(&addr);
+                            }
+                        }
+                    }
+                }
+            }
+            _ = ticker.tick() => {
+                let now = Instant::now();
+                let mut app_state = app_state.lock().await;
+
+                if app_state.sleep_timer_at.is_some_and(|at| at <= now) {
+                    app_state.sleep_timer_at = None;
+
+                    for addr in app_state.auto_managed() {
+                        starting.remove(&addr);
+
+                        if let Some(device) = app_state.device_entries.get_mut(&addr) {
+                            device.set_polled_state(PowerState::Sleep);
+                        }
+                    }
+                }
+
+                starting.retain(|addr, on_at| {
+                    if *on_at > now {
+                        return true;
+                    }
+
+                    if let Some(device) = app_state.device_entries.get_mut(addr) {
+                        device.set_polled_state(PowerState::On);
+                    }
+
+                    false
+                });
+            }
+        }
+    }
+}
+
+fn populate(app_state: &mut AppState, count: u8) {
+    let states = [PowerState::On, PowerState::Standby, PowerState::Sleep];
+
+    app_state.device_entries.clear();
+    app_state.has_scanned = true;
+    app_state.scan_finished_at = Some(Instant::now());
+
+    for i in 0..count {
+        let mut device = DeviceEntry {
+            name: Some(format!("LHB-SIM{:04X}", i)),
+            rssi: Some(-50 - i as i16),
+            // Devices three apart share a channel, to exercise the conflict warning
+            channel: Some(1 + i % 3),
+            reachable: true,
+            ..Default::default()
+        };
+
+        device.set_polled_state(states[i as usize % states.len()]);
+
+        app_state
+            .device_entries
+            .insert(BDAddr::from([0x5A, 0x1B, 0x00, 0x00, 0x00, i]), device);
+    }
+}
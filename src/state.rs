@@ -0,0 +1,1408 @@
+//! Shared state of the discovered base stations and the commands that act on it.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bleasy::{BDAddr, Characteristic, Device, Error, ScanConfig, Scanner};
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+
+use crate::config::{Config, ErrorCounts, ScanFilter};
+use crate::power::{allowed_transitions, PowerState, PowerStateCommand};
+use crate::steamvr::{StationInfo, TrackingInfo};
+
+const SCAN_TIMEOUT: Duration = Duration::from_millis(10000);
+/// How long to look for the known devices before falling back to a full scan.
+const DIRECT_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_HISTORY_LENGTH: usize = 64;
+const MAX_UNDO_STEPS: usize = 20;
+const MIN_CHANNEL: u8 = 1;
+const MAX_CHANNEL: u8 = 16;
+/// How long a polled state is trusted after the device has fallen back to `Unknown`.
+const LAST_KNOWN_STATE_TTL: Duration = Duration::from_secs(300);
+/// How long polled states that contradict a just sent command are ignored.
+const OPTIMISTIC_HOLD: Duration = Duration::from_secs(5);
+/// How often a group command is resent to devices that have not confirmed it.
+const PENDING_RETRY_INTERVAL: Duration = Duration::from_secs(3);
+/// How long a group command is retried before giving up.
+const PENDING_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the temperature of a device is read.
+const TEMPERATURE_READ_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct AppState {
+    pub scanner: Scanner,
+    pub device_entries: HashMap<BDAddr, DeviceEntry>,
+    pub ble_devices: HashMap<BDAddr, Device>,
+    /// Resolved power characteristic of each device.
+    pub power_characteristics: HashMap<BDAddr, Characteristic>,
+    pub error_state: Option<ErrorState>,
+    /// Whether a scan has been started successfully since launch.
+    pub has_scanned: bool,
+    /// Set when every read has been failing for a while, which usually means that the
+    /// Bluetooth adapter was turned off or removed.
+    pub adapter_lost: bool,
+    /// Scans that have failed to start in a row.
+    pub scan_failures: u32,
+    /// Attempts to recover the adapter since the last successful read.
+    pub recovery_attempts: u32,
+    /// The most recent read error since the last successful read.
+    pub last_poll_error: Option<String>,
+    /// Signals the command task to drop all queued commands and sleep every device.
+    pub panic_off: Arc<Notify>,
+    /// Previous states of the devices affected by recent power state changes, newest last.
+    pub undo_stack: Vec<Vec<(BDAddr, PowerStateCommand)>>,
+    /// The power state each device was last commanded to, kept across rescans.
+    pub desired_states: HashMap<BDAddr, PowerStateCommand>,
+    pub poll_stats: PollStats,
+    pub scan_task: Option<JoinHandle<()>>,
+    pub scan_started_at: Option<Instant>,
+    /// When looking for devices last finished: a full scan ended, or connecting to the known
+    /// devices found them all. Unset while a scan is running.
+    pub scan_finished_at: Option<Instant>,
+    /// Whether the current scan only connects to the known devices, which a full scan may follow.
+    pub direct_connecting: bool,
+    pub last_scan_duration: Option<Duration>,
+    /// Devices found by the current scan. Unreachable devices that are not found again are
+    /// removed when the scan ends.
+    pub rediscovered: HashSet<BDAddr>,
+    /// Device controlled by the keyboard shortcuts.
+    pub selected: Option<BDAddr>,
+    /// Devices checked for a bulk power change.
+    pub checked: HashSet<BDAddr>,
+    /// Device whose reboot is waiting for the user to confirm it.
+    pub reboot_confirmation: Option<BDAddr>,
+    /// Power change waiting for the user to confirm it because SteamVR is running.
+    pub power_change_confirmation: Option<PowerChange>,
+    /// Tag of a newer release, if the update check found one.
+    pub update_available: Option<String>,
+    /// When all devices will be put to sleep because SteamVR was closed.
+    pub steamvr_sleep_at: Option<Instant>,
+    /// When the sleep timer puts all devices to sleep, while it is running.
+    pub sleep_timer_at: Option<Instant>,
+    /// Whether SteamVR was running at the last check. Only checked when a SteamVR setting needs it.
+    pub steamvr_running: bool,
+    /// Whether SteamVR's own base station power management is enabled in its settings.
+    pub steamvr_power_management: bool,
+    /// When the headset stopped being used, while it is not. Only known with the `openvr` feature
+    /// while SteamVR is running.
+    pub hmd_idle_since: Option<Instant>,
+    /// The last automatic sleep or standby, while it can still be reverted.
+    pub auto_off: Option<AutoOff>,
+    /// When the launcher started waiting for the devices, while it is waiting.
+    pub launch_started_at: Option<Instant>,
+    pub config: Config,
+}
+
+impl AppState {
+    pub fn new(config: Config) -> Self {
+        Self {
+            scanner: Scanner::new(),
+            device_entries: HashMap::new(),
+            ble_devices: HashMap::new(),
+            power_characteristics: HashMap::new(),
+            error_state: None,
+            has_scanned: false,
+            adapter_lost: false,
+            scan_failures: 0,
+            recovery_attempts: 0,
+            last_poll_error: None,
+            panic_off: Arc::new(Notify::new()),
+            undo_stack: Vec::new(),
+            desired_states: HashMap::new(),
+            poll_stats: PollStats::new(),
+            scan_task: None,
+            scan_started_at: None,
+            scan_finished_at: None,
+            direct_connecting: false,
+            last_scan_duration: None,
+            rediscovered: HashSet::new(),
+            selected: config
+                .selected_device
+                .as_deref()
+                .and_then(|addr| BDAddr::from_str(addr).ok()),
+            checked: HashSet::new(),
+            reboot_confirmation: None,
+            power_change_confirmation: None,
+            update_available: None,
+            steamvr_sleep_at: None,
+            sleep_timer_at: None,
+            steamvr_running: false,
+            steamvr_power_management: false,
+            hmd_idle_since: None,
+            auto_off: None,
+            launch_started_at: None,
+            config,
+        }
+    }
+
+    /// Returns the configuration for the next scan. The devices found earlier are kept and updated
+    /// as they are found again, unless `clear` is set. The previous scan must have been stopped
+    /// already.
+    pub fn prepare_scan(&mut self, clear: bool) -> ScanConfig {
+        if clear {
+            self.device_entries.clear();
+            self.ble_devices.clear();
+            self.power_characteristics.clear();
+            self.checked.clear();
+        }
+
+        self.rediscovered.clear();
+        self.direct_connecting = false;
+
+        let scan_config = match self.config.scan_filter {
+            ScanFilter::Characteristic => {
+                let power_uuid = self.config.power_uuid;
+                ScanConfig::default()
+                    .filter_by_characteristics(move |uuids| uuids.contains(&power_uuid))
+            }
+            ScanFilter::Name => {
+                let prefix = self.config.scan_name_prefix.clone();
+                ScanConfig::default().filter_by_name(move |name| name.starts_with(&prefix))
+            }
+        };
+
+        if self.config.continuous_scan {
+            scan_config
+        } else {
+            scan_config.stop_after_timeout(SCAN_TIMEOUT)
+        }
+    }
+
+    /// Returns the addresses to connect to directly: the configured ones and the known devices.
+    pub fn direct_addresses(&self) -> Vec<BDAddr> {
+        let mut addrs: Vec<_> = self
+            .config
+            .device_addresses
+            .iter()
+            .chain(self.config.known_devices.keys())
+            .filter_map(|addr| BDAddr::from_str(addr).ok())
+            .collect();
+
+        addrs.sort();
+        addrs.dedup();
+        addrs
+    }
+
+    /// Creates a scan that only accepts `addrs` and stops as soon as all of them are found. The
+    /// devices are not connected to for checking their characteristics first, so this takes a
+    /// fraction of the time of a full scan.
+    pub fn prepare_direct_connect(&mut self, addrs: Vec<BDAddr>) -> ScanConfig {
+        self.rediscovered.clear();
+        self.direct_connecting = true;
+
+        let count = addrs.len();
+
+        ScanConfig::default()
+            .filter_by_address(move |addr| addrs.contains(&addr))
+            .stop_after_matches(count)
+            .stop_after_timeout(DIRECT_CONNECT_TIMEOUT)
+    }
+
+    /// Keeps `task` as the event stream task of the current scan. A task left by a scan that was
+    /// started concurrently is aborted rather than detached.
+    pub fn set_scan_task(&mut self, task: JoinHandle<()>) {
+        if let Some(previous) = self.scan_task.replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// Stops the scanner and the event stream task of the previous scan, if any.
+    pub async fn stop_scan(&mut self) {
+        if let Some(task) = self.scan_task.take() {
+            task.abort();
+        }
+
+        if self.scanner.is_active() {
+            if let Err(e) = self.scanner.stop().await {
+                log::warn!("Could not stop previous scan: {:?}", e);
+            }
+        }
+
+        self.record_scan_end();
+    }
+
+    /// Notes how long the last scan took once it has stopped.
+    pub fn record_scan_end(&mut self) {
+        if self.scanner.is_active() {
+            return;
+        }
+
+        if let Some(started_at) = self.scan_started_at.take() {
+            self.last_scan_duration = Some(started_at.elapsed());
+            self.remove_missing_devices();
+
+            // Whoever connected to the known devices decides whether a full scan follows
+            if !self.direct_connecting {
+                self.scan_finished_at = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Removes the devices that were neither found by the last scan nor are reachable otherwise.
+    fn remove_missing_devices(&mut self) {
+        let missing: Vec<_> = self
+            .device_entries
+            .iter()
+            .filter(|(addr, device)| !self.rediscovered.contains(addr) && !device.reachable)
+            .map(|(addr, _)| *addr)
+            .collect();
+
+        for addr in missing {
+            log::info!("{} was not found again", addr);
+
+            self.device_entries.remove(&addr);
+            self.ble_devices.remove(&addr);
+            self.power_characteristics.remove(&addr);
+            self.checked.remove(&addr);
+        }
+    }
+
+    /// Returns how long the current scan has been running, or how long the last one took.
+    pub fn scan_duration(&self) -> Option<Duration> {
+        self.scan_started_at
+            .map(|started_at| started_at.elapsed())
+            .or(self.last_scan_duration)
+    }
+
+    pub fn adapter_unavailable(&self) -> bool {
+        self.adapter_lost || matches!(self.error_state, Some(ErrorState::StartFailed(_)))
+    }
+
+    /// Returns the devices to read in the next poll cycle.
+    ///
+    /// While a scan is active only devices that have not been read yet are polled, unless
+    /// disabled in the settings, to reduce simultaneous BLE operations.
+    pub fn devices_to_poll(&self) -> Vec<(BDAddr, Device)> {
+        // A continuous scan never ends, so known devices would never be polled again
+        let throttle = self.config.throttle_polling_while_scanning
+            && !self.config.continuous_scan
+            && self.scanner.is_active();
+
+        self.ble_devices
+            .iter()
+            .filter(|(addr, _)| {
+                !throttle
+                    || self
+                        .device_entries
+                        .get(addr)
+                        .is_none_or(|d| d.last_known_state.is_none())
+            })
+            .map(|(addr, device)| (*addr, device.clone()))
+            .collect()
+    }
+
+    /// Marks a device as requiring pairing after a read was refused.
+    ///
+    /// Returns true if pairing should be attempted automatically, which is done once per session
+    /// for devices that have not been paired before.
+    pub fn record_pairing_required(&mut self, addr: BDAddr, error: &Error) -> bool {
+        let device = match self.device_entries.get_mut(&addr) {
+            Some(device) => device,
+            None => return false,
+        };
+
+        if !device.pairing_required {
+            log::warn!("{} refused access and may need pairing: {:?}", addr, error);
+            device.pairing_required = true;
+        }
+
+        let paired = self.config.paired_devices.contains(&addr.to_string());
+        let attempt = !paired && !device.pairing_attempted;
+        device.pairing_attempted = true;
+
+        attempt
+    }
+
+    /// Returns the devices among `addrs` whose last polled state differs from `state`.
+    pub fn devices_not_in(&self, addrs: &[BDAddr], state: PowerState) -> Vec<Device> {
+        addrs
+            .iter()
+            .filter(|addr| {
+                let polled_state = self
+                    .device_entries
+                    .get(addr)
+                    .and_then(|d| d.last_known_state)
+                    .map(|(state, _)| state);
+
+                polled_state != Some(state)
+            })
+            .filter_map(|addr| self.ble_devices.get(addr).cloned())
+            .collect()
+    }
+
+    /// Returns true if the last power change can be undone now. The devices it changed don't
+    /// accept the previous state again until their cooldown is over.
+    pub fn can_undo(&self) -> bool {
+        self.undo_stack.last().is_some_and(|entry| {
+            entry
+                .iter()
+                .all(|(addr, cmd)| !self.in_cooldown(*addr, *cmd))
+        })
+    }
+
+    /// Remembers the polled states of `addrs` so that a command about to be sent to them can be
+    /// undone.
+    pub fn push_undo(&mut self, addrs: &[BDAddr]) {
+        let entry: Vec<_> = addrs
+            .iter()
+            .filter_map(|addr| {
+                let (state, _) = self.device_entries.get(addr)?.last_known_state?;
+                Some((*addr, PowerStateCommand::for_state(state)?))
+            })
+            .collect();
+
+        if !entry.is_empty() {
+            if self.undo_stack.len() == MAX_UNDO_STEPS {
+                self.undo_stack.remove(0);
+            }
+
+            self.undo_stack.push(entry);
+        }
+    }
+
+    /// Returns the channels that are used by more than one device, with the devices using them.
+    pub fn channel_conflicts(&self) -> Vec<(u8, Vec<BDAddr>)> {
+        let mut channels: BTreeMap<u8, Vec<BDAddr>> = BTreeMap::new();
+
+        for (addr, device) in &self.device_entries {
+            if let Some(channel) = device.channel {
+                channels.entry(channel).or_default().push(*addr);
+            }
+        }
+
+        channels
+            .into_iter()
+            .filter(|(_, addrs)| addrs.len() > 1)
+            .collect()
+    }
+
+    /// Returns new channels for devices that share a channel, so that every device gets a
+    /// unique one. The first device on each channel keeps it.
+    pub fn unique_channel_assignments(&self) -> Vec<(BDAddr, u8)> {
+        let used: Vec<u8> = self
+            .device_entries
+            .values()
+            .filter_map(|d| d.channel)
+            .collect();
+        let mut free: Vec<u8> = (MIN_CHANNEL..=MAX_CHANNEL)
+            .rev()
+            .filter(|c| !used.contains(c))
+            .collect();
+
+        let mut assignments = Vec::new();
+
+        for (_, mut addrs) in self.channel_conflicts() {
+            addrs.sort_by_key(|addr| addr.to_string());
+
+            for addr in addrs.into_iter().skip(1) {
+                match free.pop() {
+                    Some(channel) => assignments.push((addr, channel)),
+                    None => return assignments,
+                }
+            }
+        }
+
+        assignments
+    }
+
+    /// Returns the members of `group` that group commands apply to.
+    pub fn group_members(&self, group: &str) -> Vec<BDAddr> {
+        self.device_entries
+            .iter()
+            .filter(|(_, d)| d.group.as_deref() == Some(group) && !d.manual_only)
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    /// Returns the devices that commands for all devices apply to.
+    pub fn auto_managed(&self) -> Vec<BDAddr> {
+        self.device_entries
+            .iter()
+            .filter(|(_, d)| !d.manual_only)
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    /// Includes or excludes a device from group and automatic power changes, and remembers it.
+    pub fn set_manual_only(&mut self, addr: BDAddr, manual_only: bool) {
+        if let Some(device) = self.device_entries.get_mut(&addr) {
+            device.manual_only = manual_only;
+        }
+
+        let key = addr.to_string();
+        self.config.manual_only_devices.retain(|a| *a != key);
+
+        if manual_only {
+            self.config.manual_only_devices.push(key);
+        }
+
+        self.config.save();
+    }
+
+    /// Pins or unpins a device to the top of the list, and remembers it.
+    pub fn set_pinned(&mut self, addr: BDAddr, pinned: bool) {
+        if let Some(device) = self.device_entries.get_mut(&addr) {
+            device.pinned = pinned;
+        }
+
+        let key = addr.to_string();
+        self.config.pinned_devices.retain(|a| *a != key);
+
+        if pinned {
+            self.config.pinned_devices.push(key);
+        }
+
+        self.config.save();
+    }
+
+    /// Updates a device with the results of a poll. `state` is `None` if the read failed.
+    ///
+    /// Returns a command to send if the device has just become reachable again and is not in
+    /// the state it was last commanded to.
+    pub fn record_poll(
+        &mut self,
+        addr: BDAddr,
+        rssi: Option<i16>,
+        state: Option<PowerState>,
+    ) -> Option<PowerStateCommand> {
+        let device = self.device_entries.get_mut(&addr)?;
+
+        device.rssi = rssi;
+
+        let state = match state {
+            Some(state) => state,
+            None => {
+                device.reachable = false;
+                self.error_counts(addr).read_failures += 1;
+                return None;
+            }
+        };
+
+        let reconnected = !device.reachable;
+
+        if reconnected && device.last_polled_at.is_some() {
+            self.error_counts(addr).reconnects += 1;
+        }
+
+        let device = self.device_entries.get_mut(&addr)?;
+        device.reachable = true;
+        device.pairing_required = false;
+
+        let now = Instant::now();
+
+        if let (Some(polled_at), Some((PowerState::On, _)), PowerState::On) =
+            (device.last_polled_at, device.last_known_state, state)
+        {
+            let on_time = now - polled_at;
+
+            device.session_on_time += on_time;
+            device.total_on_time += on_time;
+
+            *self
+                .config
+                .on_time_secs
+                .entry(addr.to_string())
+                .or_default() += on_time.as_secs_f64();
+        }
+
+        device.last_polled_at = Some(now);
+
+        if state != PowerState::Unknown {
+            device.set_polled_state(state);
+        }
+
+        if let Some(pending) = device.pending_command {
+            if [pending.cmd.target_state(), pending.cmd.expected_state()].contains(&state) {
+                device.pending_command = None;
+            }
+        }
+
+        if !reconnected
+            || state == PowerState::Unknown
+            || !self.config.restore_desired_state
+            || device.manual_only
+            || (self.config.defer_to_steamvr && self.steamvr_running)
+        {
+            return None;
+        }
+
+        let correction = self
+            .desired_states
+            .get(&addr)
+            .copied()
+            .filter(|cmd| ![cmd.target_state(), cmd.expected_state()].contains(&state))
+            .filter(|cmd| !self.in_cooldown(addr, *cmd))?;
+
+        self.record_command(&[addr], correction);
+
+        Some(correction)
+    }
+
+    /// Returns the devices in display order: named groups sorted by name followed by the
+    /// ungrouped devices, each with the pinned devices first and then sorted by address.
+    pub fn device_groups(&self) -> Vec<(Option<String>, Vec<BDAddr>)> {
+        let mut groups: BTreeMap<Option<String>, Vec<BDAddr>> = BTreeMap::new();
+
+        for (addr, device) in &self.device_entries {
+            groups.entry(device.group.clone()).or_default().push(*addr);
+        }
+
+        for addrs in groups.values_mut() {
+            addrs.sort_by_key(|addr| (!self.device_entries[addr].pinned, addr.to_string()));
+        }
+
+        let ungrouped = groups.remove(&None);
+
+        groups
+            .into_iter()
+            .chain(ungrouped.map(|addrs| (None, addrs)))
+            .collect()
+    }
+
+    /// Selects `addr` and remembers it across sessions.
+    pub fn select(&mut self, addr: Option<BDAddr>) {
+        if self.selected == addr {
+            return;
+        }
+
+        self.selected = addr;
+        self.config.selected_device = addr.map(|addr| addr.to_string());
+        self.config.save();
+    }
+
+    /// Moves the selection `offset` rows up or down the device list, or selects the first device
+    /// if none of the known devices is selected.
+    pub fn select_adjacent(&mut self, offset: isize) {
+        let addrs: Vec<_> = self
+            .device_groups()
+            .into_iter()
+            .flat_map(|(_, addrs)| addrs)
+            .collect();
+
+        let index = match self
+            .selected
+            .and_then(|s| addrs.iter().position(|a| *a == s))
+        {
+            Some(index) => (index as isize + offset).clamp(0, addrs.len() as isize - 1) as usize,
+            None => 0,
+        };
+
+        if let Some(addr) = addrs.get(index) {
+            self.select(Some(*addr));
+        }
+    }
+
+    /// Returns true if the temperature of the device should be read, i.e. a temperature
+    /// characteristic is configured and the last reading is old enough.
+    pub fn temperature_due(&self, addr: BDAddr) -> bool {
+        self.config.temperature_uuid.is_some()
+            && self.device_entries.get(&addr).is_some_and(|d| {
+                d.temperature
+                    .is_none_or(|(_, read_at)| read_at.elapsed() >= TEMPERATURE_READ_INTERVAL)
+            })
+    }
+
+    /// Stores a temperature reading. Returns a standby command if the device is on and has
+    /// reached the overheat temperature, and automatic standby is enabled.
+    pub fn record_temperature(
+        &mut self,
+        addr: BDAddr,
+        temperature: f32,
+    ) -> Option<PowerStateCommand> {
+        let cmd = PowerStateCommand::Standby;
+        let device = self.device_entries.get_mut(&addr)?;
+
+        device.temperature = Some((temperature, Instant::now()));
+
+        if !self.config.overheat_standby
+            || temperature < self.config.overheat_temperature
+            || ![PowerState::On, PowerState::Starting].contains(&device.power_state)
+            || device.manual_only
+            || self.in_cooldown(addr, cmd)
+        {
+            return None;
+        }
+
+        log::warn!(
+            "{} is at {:.1} °C, putting it in standby to let it cool down",
+            addr,
+            temperature
+        );
+
+        self.desired_states.insert(addr, cmd);
+        self.record_command(&[addr], cmd);
+
+        Some(cmd)
+    }
+
+    /// The persisted error counters of the device.
+    pub fn error_counts(&mut self, addr: BDAddr) -> &mut ErrorCounts {
+        self.config
+            .error_counts
+            .entry(addr.to_string())
+            .or_default()
+    }
+
+    /// Returns true if `cmd` would change the state of the device too soon after the last change.
+    pub fn in_cooldown(&self, addr: BDAddr, cmd: PowerStateCommand) -> bool {
+        let cooldown = self.config.power_cooldown();
+
+        self.device_entries
+            .get(&addr)
+            .is_some_and(|d| d.cooldown_remaining(cmd, cooldown).is_some())
+    }
+
+    /// Returns true if `cmd` can be sent to the device now, considering both the state it is in
+    /// and the cooldown.
+    pub fn can_send(&self, addr: BDAddr, cmd: PowerStateCommand) -> bool {
+        self.device_entries
+            .get(&addr)
+            .is_some_and(|d| d.can_send(cmd))
+            && !self.in_cooldown(addr, cmd)
+    }
+
+    /// Shows the state `addrs` are expected to be in after `cmd`, except for the devices in
+    /// cooldown that the command will not be sent to.
+    pub fn set_expected_states(&mut self, addrs: &[BDAddr], cmd: PowerStateCommand) {
+        for addr in addrs {
+            if self.in_cooldown(*addr, cmd) {
+                continue;
+            }
+
+            if let Some(device) = self.device_entries.get_mut(addr) {
+                device.set_optimistic_state(cmd);
+            }
+        }
+    }
+
+    /// Records an automatic sleep or standby of the devices that are not manual only, so that the
+    /// ones that were on can be woken again if activity resumes within the grace period.
+    pub fn record_auto_off(&mut self) {
+        let addrs = self
+            .auto_managed()
+            .into_iter()
+            .filter(|addr| {
+                self.device_entries
+                    .get(addr)
+                    .is_some_and(|d| matches!(d.power_state, PowerState::On | PowerState::Starting))
+            })
+            .collect();
+
+        self.auto_off = Some(AutoOff {
+            at: Instant::now(),
+            addrs,
+        });
+    }
+
+    /// Returns the devices to wake because activity resumed within the grace period of the last
+    /// automatic sleep or standby. The automatic change can only be reverted once.
+    pub fn take_auto_off_revert(&mut self) -> Vec<BDAddr> {
+        let grace = self.config.auto_off_grace();
+
+        match self.auto_off.take() {
+            Some(auto_off) if auto_off.at.elapsed() < grace => auto_off.addrs,
+            _ => Vec::new(),
+        }
+    }
+
+    /// Starts the cooldown of `addrs` after sending them `cmd`.
+    pub fn record_command(&mut self, addrs: &[BDAddr], cmd: PowerStateCommand) {
+        let now = Instant::now();
+
+        for addr in addrs {
+            if let Some(device) = self.device_entries.get_mut(addr) {
+                device.last_command = Some((cmd, now));
+            }
+        }
+    }
+
+    /// Marks `addrs` as waiting for a poll to confirm that `cmd` has taken effect.
+    pub fn track_pending(&mut self, addrs: &[BDAddr], cmd: PowerStateCommand) {
+        let now = Instant::now();
+
+        for addr in addrs {
+            if let Some(device) = self.device_entries.get_mut(addr) {
+                device.pending_command = Some(PendingCommand {
+                    cmd,
+                    started_at: now,
+                    sent_at: now,
+                    attempts: 1,
+                });
+            }
+        }
+    }
+
+    /// Returns the unconfirmed commands that are due to be resent, and gives up on the ones that
+    /// have not been confirmed in time.
+    pub fn pending_retries(&mut self) -> Vec<(BDAddr, Device, PowerStateCommand)> {
+        let mut retries = Vec::new();
+
+        for (addr, device) in self.device_entries.iter_mut() {
+            let pending = match device.pending_command.as_mut() {
+                Some(pending) => pending,
+                None => continue,
+            };
+
+            if pending.started_at.elapsed() >= PENDING_TIMEOUT {
+                log::warn!(
+                    "{} did not confirm {:?} after {} attempts",
+                    addr,
+                    pending.cmd,
+                    pending.attempts
+                );
+                device.pending_command = None;
+            } else if pending.sent_at.elapsed() >= PENDING_RETRY_INTERVAL {
+                if let Some(ble_device) = self.ble_devices.get(addr) {
+                    pending.sent_at = Instant::now();
+                    pending.attempts += 1;
+                    retries.push((*addr, ble_device.clone(), pending.cmd));
+                }
+            }
+        }
+
+        retries
+    }
+
+    pub async fn insert_device(&mut self, device_addr: BDAddr, device: Device) {
+        let name = device.local_name().await;
+
+        if let Some(name) = &name {
+            self.config
+                .known_devices
+                .insert(device_addr.to_string(), name.clone());
+        }
+
+        // Some adapters report the same base station under more than one address. The name is
+        // unique per unit, so a known name means the device has moved to a new address.
+        let previous_addr = name.as_ref().and_then(|name| {
+            self.device_entries
+                .iter()
+                .find(|(addr, d)| **addr != device_addr && d.name.as_ref() == Some(name))
+                .map(|(addr, _)| *addr)
+        });
+
+        self.rediscovered.insert(device_addr);
+
+        // Found again by a rescan. The existing connection keeps working, so only the name is
+        // updated.
+        if let Some(entry) = self.device_entries.get_mut(&device_addr) {
+            if name.is_some() {
+                entry.name = name;
+            }
+
+            self.ble_devices.entry(device_addr).or_insert(device);
+            return;
+        }
+
+        let entry = match previous_addr.and_then(|from| self.move_device(from, device_addr)) {
+            Some(entry) => entry,
+            None => self.new_entry(device_addr, name),
+        };
+
+        self.device_entries.insert(device_addr, entry);
+        self.ble_devices.insert(device_addr, device);
+        self.power_characteristics.remove(&device_addr);
+    }
+
+    /// Removes the device at `from`, carrying its settings over to `to`, and returns its entry.
+    fn move_device(&mut self, from: BDAddr, to: BDAddr) -> Option<DeviceEntry> {
+        let entry = self.device_entries.remove(&from)?;
+
+        log::info!("{} reappeared as {}", from, to);
+
+        self.ble_devices.remove(&from);
+        self.power_characteristics.remove(&from);
+
+        if let Some(cmd) = self.desired_states.remove(&from) {
+            self.desired_states.insert(to, cmd);
+        }
+
+        if self.selected == Some(from) {
+            self.select(Some(to));
+        }
+
+        if self.checked.remove(&from) {
+            self.checked.insert(to);
+        }
+
+        let (from, to) = (from.to_string(), to.to_string());
+
+        for settings in [&mut self.config.aliases, &mut self.config.groups] {
+            if let Some(value) = settings.remove(&from) {
+                settings.entry(to.clone()).or_insert(value);
+            }
+        }
+
+        if let Some(secs) = self.config.on_time_secs.remove(&from) {
+            *self.config.on_time_secs.entry(to.clone()).or_default() += secs;
+        }
+
+        if let Some(counts) = self.config.error_counts.remove(&from) {
+            let moved = self.config.error_counts.entry(to.clone()).or_default();
+            moved.read_failures += counts.read_failures;
+            moved.write_failures += counts.write_failures;
+            moved.reconnects += counts.reconnects;
+        }
+
+        self.config.known_devices.remove(&from);
+
+        for addrs in [
+            &mut self.config.manual_only_devices,
+            &mut self.config.pinned_devices,
+        ] {
+            for addr in addrs.iter_mut() {
+                if *addr == from {
+                    *addr = to.clone();
+                }
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Lists the previously discovered devices without connecting to them, so that there is
+    /// something to show before the first scan.
+    pub fn show_known_devices(&mut self) {
+        let known: Vec<_> = self
+            .config
+            .known_devices
+            .iter()
+            .filter_map(|(addr, name)| Some((BDAddr::from_str(addr).ok()?, name.clone())))
+            .collect();
+
+        for (addr, name) in known {
+            let entry = self.new_entry(addr, Some(name));
+            self.device_entries.insert(addr, entry);
+        }
+    }
+
+    pub fn new_entry(&self, addr: BDAddr, name: Option<String>) -> DeviceEntry {
+        let key = addr.to_string();
+
+        DeviceEntry {
+            alias: self.config.aliases.get(&key).cloned(),
+            group: self.config.groups.get(&key).cloned(),
+            total_on_time: Duration::from_secs_f64(
+                self.config
+                    .on_time_secs
+                    .get(&key)
+                    .copied()
+                    .unwrap_or_default(),
+            ),
+            name,
+            manual_only: self.config.manual_only_devices.contains(&key),
+            pinned: self.config.pinned_devices.contains(&key),
+            ..Default::default()
+        }
+    }
+}
+
+/// An automatic sleep or standby, e.g. because the headset was idle.
+#[derive(Clone, Debug)]
+pub struct AutoOff {
+    pub at: Instant,
+    /// The devices that were changed.
+    pub addrs: Vec<BDAddr>,
+}
+
+/// A power change requested from the UI.
+#[derive(Clone, Debug)]
+pub enum PowerChange {
+    Device(BDAddr, PowerStateCommand),
+    All(PowerStateCommand),
+    Checked(PowerStateCommand),
+    Group(String, PowerStateCommand),
+}
+
+impl PowerChange {
+    pub fn command(&self) -> PowerStateCommand {
+        match *self {
+            PowerChange::Device(_, cmd)
+            | PowerChange::All(cmd)
+            | PowerChange::Checked(cmd)
+            | PowerChange::Group(_, cmd) => cmd,
+        }
+    }
+}
+
+pub enum ErrorState {
+    StartFailed(String),
+}
+
+#[derive(Default)]
+pub struct DeviceEntry {
+    pub alias: Option<String>,
+    pub name: Option<String>,
+    pub group: Option<String>,
+    pub rssi: Option<i16>,
+    pub channel: Option<u8>,
+    /// Whether the last poll of the device succeeded.
+    pub reachable: bool,
+    pub last_polled_at: Option<Instant>,
+    /// Whether the device has refused reads in a way that suggests it needs to be paired.
+    pub pairing_required: bool,
+    /// Whether pairing has been attempted during this session.
+    pub pairing_attempted: bool,
+    /// Time spent in the `On` state during this session.
+    pub session_on_time: Duration,
+    /// Time spent in the `On` state across all sessions.
+    pub total_on_time: Duration,
+    pub power_state: PowerState,
+    pub last_known_state: Option<(PowerState, Instant)>,
+    /// Recent polled state transitions, oldest first.
+    pub history: VecDeque<(Instant, PowerState)>,
+    /// Group command that the device has not yet been seen to follow.
+    pub pending_command: Option<PendingCommand>,
+    /// Command whose expected state is shown instead of older polled states for a short while.
+    pub optimistic_hold: Option<(PowerStateCommand, Instant)>,
+    /// Leaves the device out of group commands and automatic power changes. It can still be
+    /// controlled on its own.
+    pub manual_only: bool,
+    /// Shows the device before the others in its group.
+    pub pinned: bool,
+    /// The last power command sent to the device and when, for enforcing the cooldown.
+    pub last_command: Option<(PowerStateCommand, Instant)>,
+    /// Last read temperature in °C and when it was read.
+    pub temperature: Option<(f32, Instant)>,
+    /// When a poll last showed the device moving from one known state to another.
+    pub state_changed_at: Option<Instant>,
+    /// What SteamVR knows about the device, if it has seen it.
+    pub steamvr: Option<StationInfo>,
+    /// What a running SteamVR reports about the device, while it sees it.
+    pub tracking: Option<TrackingInfo>,
+}
+
+/// A command sent as part of a group operation, retried until a poll confirms it.
+#[derive(Copy, Clone, Debug)]
+pub struct PendingCommand {
+    pub cmd: PowerStateCommand,
+    pub started_at: Instant,
+    pub sent_at: Instant,
+    pub attempts: u32,
+}
+
+impl DeviceEntry {
+    pub fn set_polled_state(&mut self, state: PowerState) {
+        if let Some((cmd, held_at)) = self.optimistic_hold {
+            if state == cmd.target_state() || held_at.elapsed() >= OPTIMISTIC_HOLD {
+                self.optimistic_hold = None;
+            }
+        }
+
+        // Reads taken before the device reacted to the command would make the state flicker back
+        let held = matches!(self.optimistic_hold, Some((cmd, _)) if state != cmd.expected_state());
+
+        if !held {
+            if self.power_state != state
+                && self.power_state != PowerState::Unknown
+                && state != PowerState::Unknown
+            {
+                self.state_changed_at = Some(Instant::now());
+            }
+
+            self.power_state = state;
+        }
+
+        if state != PowerState::Unknown {
+            self.last_known_state = Some((state, Instant::now()));
+        }
+
+        if self.history.back().map(|(_, s)| *s) != Some(state) {
+            if self.history.len() == MAX_HISTORY_LENGTH {
+                self.history.pop_front();
+            }
+
+            self.history.push_back((Instant::now(), state));
+        }
+    }
+
+    /// Shows the state the device is expected to be in after `cmd` until a poll confirms it or the
+    /// hold expires.
+    pub fn set_optimistic_state(&mut self, cmd: PowerStateCommand) {
+        if self.power_state != cmd.target_state() {
+            self.power_state = cmd.expected_state();
+            self.optimistic_hold = Some((cmd, Instant::now()));
+        }
+    }
+
+    /// Returns true if `cmd` is an allowed transition from the current state. A device that is
+    /// temporarily unknown can still be turned on if it was recently seen asleep or in standby.
+    pub fn can_send(&self, cmd: PowerStateCommand) -> bool {
+        match (self.power_state, self.last_known_state) {
+            (PowerState::Unknown, Some((state, seen_at))) if cmd == PowerStateCommand::On => {
+                seen_at.elapsed() < LAST_KNOWN_STATE_TTL
+                    && allowed_transitions(state).contains(&cmd)
+            }
+            (state, _) => allowed_transitions(state).contains(&cmd),
+        }
+    }
+
+    /// Returns true if the backend should write `cmd` to the device: it is an allowed transition
+    /// from the last polled state, or the device is already in or on its way to the new state.
+    /// Unlike `can_send` this ignores the shown state, which is already set to the expected result
+    /// of the command when it is sent from the UI.
+    pub fn accepts(&self, cmd: PowerStateCommand) -> bool {
+        match self.last_known_state {
+            Some((state, _)) => {
+                state == cmd.target_state()
+                    || state == cmd.expected_state()
+                    || allowed_transitions(state).contains(&cmd)
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if a poll since `since` has read `state` from the device. Unlike the shown
+    /// state, this is never an optimistic guess.
+    pub fn has_reported(&self, state: PowerState, since: Instant) -> bool {
+        matches!(self.last_known_state, Some((s, seen_at)) if s == state && seen_at >= since)
+    }
+
+    /// Returns true once the device has been on for at least `warm_up`.
+    pub fn is_ready(&self, warm_up: Duration) -> bool {
+        self.power_state == PowerState::On
+            && matches!(self.history.back(), Some((on_at, PowerState::On)) if on_at.elapsed() >= warm_up)
+    }
+
+    /// Returns true if the device is on but not ready yet.
+    pub fn is_warming_up(&self, warm_up: Duration) -> bool {
+        self.power_state == PowerState::On && !self.is_ready(warm_up)
+    }
+
+    /// The serial number SteamVR knows the device by, or the hex part of its advertised name.
+    pub fn serial(&self) -> Option<&str> {
+        if let Some(station) = &self.steamvr {
+            return Some(&station.serial);
+        }
+
+        self.name
+            .as_deref()?
+            .rsplit_once('-')
+            .map(|(_, serial)| serial)
+    }
+
+    /// The channel read from the device, or the one SteamVR has recorded if it hasn't been read.
+    pub fn known_channel(&self) -> Option<u8> {
+        self.channel
+            .or_else(|| self.steamvr.as_ref().and_then(|s| s.channel))
+    }
+
+    /// Returns the command that flips the device between on and sleep. A device that is
+    /// temporarily unknown is flipped based on its last known state.
+    pub fn toggle_command(&self) -> Option<PowerStateCommand> {
+        let state = match (self.power_state, self.last_known_state) {
+            (PowerState::Unknown, Some((state, _))) => state,
+            (state, _) => state,
+        };
+
+        PowerStateCommand::toggle(state)
+    }
+
+    /// Returns how long `cmd` has to wait if it would change the state again before `cooldown`
+    /// has passed since the last command.
+    pub fn cooldown_remaining(
+        &self,
+        cmd: PowerStateCommand,
+        cooldown: Duration,
+    ) -> Option<Duration> {
+        let (last, sent_at) = self.last_command?;
+
+        if last.target_state() == cmd.target_state() {
+            return None;
+        }
+
+        cooldown
+            .checked_sub(sent_at.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// Timing and read counters for the state poll loop.
+pub struct PollStats {
+    pub started_at: Instant,
+    pub cycles: u64,
+    pub total_cycle_time: Duration,
+    pub last_cycle_time: Duration,
+    pub reads_ok: u64,
+    pub reads_failed: u64,
+}
+
+impl PollStats {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            cycles: 0,
+            total_cycle_time: Duration::ZERO,
+            last_cycle_time: Duration::ZERO,
+            reads_ok: 0,
+            reads_failed: 0,
+        }
+    }
+
+    pub fn record_cycle(&mut self, duration: Duration, reads_ok: u64, reads_failed: u64) {
+        self.cycles += 1;
+        self.total_cycle_time += duration;
+        self.last_cycle_time = duration;
+        self.reads_ok += reads_ok;
+        self.reads_failed += reads_failed;
+    }
+
+    pub fn average_cycle_time(&self) -> Duration {
+        if self.cycles == 0 {
+            Duration::ZERO
+        } else {
+            self.total_cycle_time / self.cycles as u32
+        }
+    }
+
+    pub fn reads_per_second(&self) -> f64 {
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+
+        if elapsed > 0.0 {
+            (self.reads_ok + self.reads_failed) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        let total = self.reads_ok + self.reads_failed;
+
+        if total == 0 {
+            0.0
+        } else {
+            self.reads_failed as f64 / total as f64
+        }
+    }
+}
+
+impl Default for PollStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Why a power state change could not be made.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CommandError {
+    UnknownDevice,
+    /// The device changed state too recently.
+    Cooldown,
+    /// The command is not an allowed transition from the state of the device.
+    NotAllowed,
+    WriteFailed,
+    /// The backend stopped before the command was carried out.
+    BackendStopped,
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CommandError::UnknownDevice => "Unknown device",
+            CommandError::Cooldown => "Device changed state too recently",
+            CommandError::NotAllowed => "Device can't change to that state from its current state",
+            CommandError::WriteFailed => "Could not send the command to the device",
+            CommandError::BackendStopped => "Backend stopped",
+        })
+    }
+}
+
+impl CommandError {
+    /// Identifies the error in IPC responses.
+    pub fn code(self) -> &'static str {
+        match self {
+            CommandError::UnknownDevice => "not_found",
+            CommandError::Cooldown => "cooldown",
+            CommandError::NotAllowed => "not_allowed",
+            CommandError::WriteFailed => "write_failed",
+            CommandError::BackendStopped => "backend_stopped",
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Receives the outcome of a command once it has been written to the device.
+pub type CommandReply = oneshot::Sender<Result<(), CommandError>>;
+
+#[derive(Debug)]
+pub enum Command {
+    StartScan,
+    StopScan,
+    /// Changes the power state of a single device, optionally reporting the outcome.
+    ChangePowerState(BDAddr, PowerStateCommand, Option<CommandReply>),
+    ChangeAllPowerStates(PowerStateCommand),
+    ChangeGroupPowerState(String, PowerStateCommand),
+    /// Changes the power state of the given devices only.
+    ChangePowerStates(Vec<BDAddr>, PowerStateCommand),
+    /// Like `ChangePowerStates`, but also for devices that have just changed state, as when the
+    /// computer suspends. The reply is sent once the commands have been written.
+    SendPowerStates(Vec<BDAddr>, PowerStateCommand, oneshot::Sender<()>),
+    Pair(BDAddr),
+    AssignUniqueChannels,
+    Reboot(BDAddr),
+    /// Reconnects to every discovered device without scanning again.
+    ReconnectAll,
+    /// Forgets the discovered devices and scans for them from scratch.
+    ClearAndScan,
+    /// Starts the sleep timer, replacing a running one, or cancels it.
+    SetSleepTimer(Option<Duration>),
+    /// Puts the devices into the configured exit state, if there is one, and stops the backend.
+    /// The reply is sent once the backend is about to stop.
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Sleeps every device right away. This is intentionally unguarded: it never asks for
+/// confirmation and takes priority over any queued commands.
+pub fn panic_off(app_state: &mut AppState) {
+    for device in app_state.device_entries.values_mut() {
+        device.set_optimistic_state(PowerStateCommand::Sleep);
+    }
+
+    app_state.panic_off.notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::pending;
+
+    use tokio::time::timeout;
+
+    use super::*;
+
+    /// Spawns a task that runs until it is aborted, returning a receiver that fails once the task
+    /// has been dropped.
+    fn spawn_event_task() -> (JoinHandle<()>, oneshot::Receiver<()>) {
+        let (alive_tx, alive_rx) = oneshot::channel::<()>();
+
+        let task = tokio::task::spawn(async move {
+            let _alive = alive_tx;
+            pending::<()>().await
+        });
+
+        (task, alive_rx)
+    }
+
+    fn app_state_with(states: &[(&str, PowerState)]) -> AppState {
+        let mut app_state = AppState::new(Config::default());
+
+        for (addr, power_state) in states {
+            app_state.device_entries.insert(
+                BDAddr::from_str(addr).unwrap(),
+                DeviceEntry {
+                    power_state: *power_state,
+                    ..Default::default()
+                },
+            );
+        }
+
+        app_state
+    }
+
+    #[test]
+    fn auto_off_is_reverted_within_grace() {
+        let mut app_state = app_state_with(&[
+            ("AA:BB:CC:DD:EE:01", PowerState::On),
+            ("AA:BB:CC:DD:EE:02", PowerState::Sleep),
+            ("AA:BB:CC:DD:EE:03", PowerState::Standby),
+        ]);
+
+        app_state.record_auto_off();
+
+        assert_eq!(
+            app_state.take_auto_off_revert(),
+            vec![BDAddr::from_str("AA:BB:CC:DD:EE:01").unwrap()]
+        );
+        assert!(app_state.take_auto_off_revert().is_empty());
+    }
+
+    #[test]
+    fn auto_off_is_kept_after_grace() {
+        let mut app_state = app_state_with(&[("AA:BB:CC:DD:EE:01", PowerState::On)]);
+        app_state.config.auto_off_grace_secs = 0;
+
+        app_state.record_auto_off();
+
+        assert!(app_state.take_auto_off_revert().is_empty());
+    }
+
+    #[test]
+    fn only_a_full_scan_finishes_on_its_own() {
+        let mut app_state = app_state_with(&[]);
+
+        app_state.prepare_direct_connect(vec![]);
+        app_state.scan_started_at = Some(Instant::now());
+        app_state.record_scan_end();
+        assert!(app_state.scan_finished_at.is_none());
+
+        app_state.prepare_scan(false);
+        app_state.scan_started_at = Some(Instant::now());
+        app_state.record_scan_end();
+        assert!(app_state.scan_finished_at.is_some());
+    }
+
+    #[test]
+    fn unique_channel_assignments_move_all_but_the_first() {
+        let mut app_state = app_state_with(&[
+            ("AA:BB:CC:DD:EE:01", PowerState::On),
+            ("AA:BB:CC:DD:EE:02", PowerState::On),
+            ("AA:BB:CC:DD:EE:03", PowerState::On),
+            ("AA:BB:CC:DD:EE:04", PowerState::On),
+        ]);
+
+        for (addr, channel) in [("01", 1), ("02", 1), ("03", 1), ("04", 2)] {
+            let addr = BDAddr::from_str(&format!("AA:BB:CC:DD:EE:{}", addr)).unwrap();
+            app_state.device_entries.get_mut(&addr).unwrap().channel = Some(channel);
+        }
+
+        assert_eq!(
+            app_state.unique_channel_assignments(),
+            vec![
+                (BDAddr::from_str("AA:BB:CC:DD:EE:02").unwrap(), 3),
+                (BDAddr::from_str("AA:BB:CC:DD:EE:03").unwrap(), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn unique_channel_assignments_stop_when_out_of_channels() {
+        let addrs: Vec<String> = (1..=17)
+            .map(|i| format!("AA:BB:CC:DD:EE:{:02X}", i))
+            .collect();
+        let states: Vec<_> = addrs
+            .iter()
+            .map(|addr| (addr.as_str(), PowerState::On))
+            .collect();
+        let mut app_state = app_state_with(&states);
+
+        for device in app_state.device_entries.values_mut() {
+            device.channel = Some(1);
+        }
+
+        assert_eq!(app_state.unique_channel_assignments().len(), 15);
+    }
+
+    #[tokio::test]
+    async fn repeated_scans_keep_one_event_task() {
+        let mut app_state = AppState::new(Config::default());
+
+        let (task, first) = spawn_event_task();
+        app_state.set_scan_task(task);
+
+        let (task, mut second) = spawn_event_task();
+        app_state.set_scan_task(task);
+
+        let first = timeout(Duration::from_secs(1), first).await;
+        assert!(
+            matches!(first, Ok(Err(_))),
+            "the first event task was not aborted"
+        );
+        assert!(
+            matches!(second.try_recv(), Err(oneshot::error::TryRecvError::Empty)),
+            "the second event task is not running"
+        );
+    }
+}
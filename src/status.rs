@@ -0,0 +1,79 @@
+//! Machine readable device status, written to a file and/or stdout whenever it changes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use bleasy::BDAddr;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::state::AppState;
+
+/// Returns one JSON record per device, sorted by address.
+pub fn device_records(app_state: &AppState) -> Vec<Value> {
+    let mut addrs: Vec<_> = app_state.device_entries.keys().collect();
+    addrs.sort_by_key(|addr| addr.to_string());
+
+    addrs
+        .into_iter()
+        .filter_map(|addr| device_record(app_state, addr))
+        .collect()
+}
+
+/// Returns the JSON record of a single device, if it is known.
+pub fn device_record(app_state: &AppState, addr: &BDAddr) -> Option<Value> {
+    let device = app_state.device_entries.get(addr)?;
+
+    Some(json!({
+        "address": addr.to_string(),
+        "alias": device.alias,
+        "name": device.name,
+        "state": device.power_state.to_string(),
+        "ready": device.is_ready(app_state.config.warm_up()),
+        "rssi": device.rssi,
+    }))
+}
+
+/// Returns the records that are new or have changed since `last_records`. This is what every
+/// stream of status records writes, so that they all follow the same format.
+pub fn changed_records<'a>(
+    records: &'a [Value],
+    last_records: &'a [Value],
+) -> impl Iterator<Item = &'a Value> {
+    records.iter().filter(move |r| !last_records.contains(*r))
+}
+
+/// Periodically checks the device records and outputs them when they have changed.
+///
+/// The whole list is written to `file`, while `stdout` only receives the records that changed.
+pub async fn run(
+    app_state: Arc<Mutex<AppState>>,
+    file: Option<String>,
+    stdout: bool,
+    interval: Duration,
+) {
+    let mut last_records = Vec::new();
+
+    loop {
+        let records = device_records(&*app_state.lock().await);
+
+        if records != last_records {
+            if stdout {
+                for record in changed_records(&records, &last_records) {
+                    println!("{}", record);
+                }
+            }
+
+            if let Some(path) = &file {
+                if let Err(e) = std::fs::write(path, Value::Array(records.clone()).to_string()) {
+                    log::error!("Could not write status file {}: {}", path, e);
+                }
+            }
+
+            last_records = records;
+        }
+
+        sleep(interval).await;
+    }
+}
@@ -0,0 +1,219 @@
+//! Control over stdin and stdout, for apps that run this one as a child process and for tools that
+//! can pipe commands to a process.
+//!
+//! Each line read from stdin is either a JSON object with a `command` and its arguments, and an
+//! optional `id` that is copied to the response, or an IPC command in plain text. Device records
+//! are written as events whenever they change.
+
+use std::io::{self, BufRead};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use steamvr_lighthouse_control::power::PowerStateCommand;
+use steamvr_lighthouse_control::state::{AppState, Command};
+use steamvr_lighthouse_control::{ipc, status, LighthouseController};
+use tokio::sync::Mutex;
+
+const EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Executes the commands read from stdin until it is closed or a `quit` command is read.
+pub fn run(controller: &LighthouseController) -> io::Result<()> {
+    let app_state = controller.state();
+    let cmd_tx = controller.command_sender();
+    let runtime = controller.runtime();
+
+    if !app_state.blocking_lock().config.scan_on_startup {
+        cmd_tx.blocking_send(Command::StartScan).ok();
+    }
+
+    {
+        let app_state = app_state.clone();
+        std::thread::spawn(move || write_events(app_state));
+    }
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // Plain text lines are IPC commands, as typed in a terminal or sent by simple tools
+        if !line.starts_with('{') {
+            if matches!(line, "quit" | "exit") {
+                break;
+            }
+
+            let result = runtime.block_on(ipc::handle_request(
+                &plain_command(line),
+                &app_state,
+                &cmd_tx,
+            ));
+
+            println!("{}", response(&Value::Null, result));
+            continue;
+        }
+
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                println!("{}", response(&Value::Null, error(e)));
+                continue;
+            }
+        };
+
+        if request["command"] == "quit" {
+            break;
+        }
+
+        let result = match to_ipc_command(&request) {
+            Ok(command) => runtime.block_on(ipc::handle_request(&command, &app_state, &cmd_tx)),
+            Err(e) => error(e),
+        };
+
+        println!("{}", response(&request["id"], result));
+    }
+
+    Ok(())
+}
+
+/// Converts a JSON command to the IPC command syntax.
+fn to_ipc_command(request: &Value) -> Result<String, String> {
+    let arg = |key: &str| {
+        request[key]
+            .as_str()
+            .ok_or_else(|| format!("Missing {}", key))
+    };
+
+    let command = arg("command")?;
+
+    Ok(match command {
+        "power" => format!(
+            "power {} {}{}",
+            arg("target")?,
+            arg("state")?,
+            if request["wait"] == true { " wait" } else { "" }
+        ),
+        "scan" if request["clear"] == true => "scan clear".to_string(),
+        "list" | "scan" | "reconnect" | "panic" | "launch" => command.to_string(),
+        _ => return Err(format!("Unknown command: {}", command)),
+    })
+}
+
+/// Rewrites the `<state> all` and `<state> <address>` forms of a plain text line to the IPC
+/// command syntax.
+fn plain_command(line: &str) -> String {
+    let args: Vec<&str> = line.split_whitespace().collect();
+
+    match args.as_slice() {
+        [state, "all"] if PowerStateCommand::from_str(state).is_ok() => format!("all {}", state),
+        [state, addr] if PowerStateCommand::from_str(state).is_ok() => {
+            format!("set {} {}", addr, state)
+        }
+        _ => line.to_string(),
+    }
+}
+
+fn response(id: &Value, mut result: Value) -> Value {
+    result["type"] = json!("response");
+
+    if !id.is_null() {
+        result["id"] = id.clone();
+    }
+
+    result
+}
+
+fn error(message: impl ToString) -> Value {
+    json!({ "ok": false, "error": message.to_string() })
+}
+
+/// Writes a `device` event with the record of every device that was found or has changed.
+fn write_events(app_state: Arc<Mutex<AppState>>) {
+    let mut last_records = Vec::new();
+
+    loop {
+        let records = status::device_records(&app_state.blocking_lock());
+
+        for record in status::changed_records(&records, &last_records) {
+            let mut event = record.clone();
+            event["type"] = json!("device");
+            println!("{}", event);
+        }
+
+        last_records = records;
+
+        std::thread::sleep(EVENT_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_command_rewrites_the_shorthands() {
+        assert_eq!(plain_command("on all"), "all on");
+        assert_eq!(plain_command("Sleep all"), "all Sleep");
+        assert_eq!(
+            plain_command("standby AA:BB:CC:DD:EE:FF"),
+            "set AA:BB:CC:DD:EE:FF standby"
+        );
+    }
+
+    #[test]
+    fn plain_command_passes_other_commands_through() {
+        assert_eq!(plain_command("list"), "list");
+        assert_eq!(plain_command("all on"), "all on");
+        assert_eq!(
+            plain_command("set AA:BB:CC:DD:EE:FF on"),
+            "set AA:BB:CC:DD:EE:FF on"
+        );
+    }
+
+    #[test]
+    fn to_ipc_command_converts_json_commands() {
+        let command = |request| to_ipc_command(&request);
+
+        assert_eq!(
+            command(json!({ "command": "list" })),
+            Ok("list".to_string())
+        );
+        assert_eq!(
+            command(json!({ "command": "scan", "clear": true })),
+            Ok("scan clear".to_string())
+        );
+        assert_eq!(
+            command(json!({ "command": "power", "target": "all", "state": "on" })),
+            Ok("power all on".to_string())
+        );
+        assert_eq!(
+            command(json!({
+                "command": "power",
+                "target": "AA:BB:CC:DD:EE:FF",
+                "state": "sleep",
+                "wait": true,
+            })),
+            Ok("power AA:BB:CC:DD:EE:FF sleep wait".to_string())
+        );
+    }
+
+    #[test]
+    fn to_ipc_command_rejects_invalid_commands() {
+        assert_eq!(
+            to_ipc_command(&json!({ "command": "power", "target": "all" })),
+            Err("Missing state".to_string())
+        );
+        assert_eq!(
+            to_ipc_command(&json!({ "command": "format" })),
+            Err("Unknown command: format".to_string())
+        );
+        assert_eq!(
+            to_ipc_command(&json!({ "id": 1 })),
+            Err("Missing command".to_string())
+        );
+    }
+}
@@ -0,0 +1,741 @@
+//! Integration with SteamVR: following whether it is running, reading base station names and
+//! details from its configuration files and registering the app to be started with it.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bleasy::BDAddr;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::sleep;
+
+use crate::config::{Config, SteamVrInterlock};
+use crate::power::PowerStateCommand;
+use crate::state::{AppState, Command};
+
+const PROCESS_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+/// Names of the processes that run while SteamVR is open, without the `.exe` extension.
+const PROCESS_NAMES: &[&str] = &["vrserver", "vrmonitor"];
+/// Identifies the app in SteamVR's app configuration.
+const APP_KEY: &str = "urholaukkarinen.steamvr-lighthouse-control";
+/// File under the Steam config directory listing the registered app manifests.
+const APP_CONFIG_FILE: &str = "appconfig.json";
+
+/// Files under the Steam config directory that may name base stations.
+const FILES: &[&str] = &[LIGHTHOUSE_DB_FILE, "steamvr.vrsettings"];
+/// SteamVR's record of the base stations it has seen and where they are.
+const LIGHTHOUSE_DB_FILE: &str = "lighthouse/lighthousedb.json";
+/// How often the lighthouse database is read again while the app is running.
+const STATION_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Setting in `steamvr.vrsettings` that lets SteamVR power the base stations on and off itself.
+const POWER_MANAGEMENT_KEY: &str = "basestationPowerManagement";
+const CHANNEL_KEYS: &[&str] = &["channel", "mode"];
+const POSE_KEYS: &[&str] = &["pose", "position"];
+const SERIAL_KEYS: &[&str] = &[
+    "serialNumber",
+    "serial_number",
+    "base_serial_number",
+    "serial",
+];
+const NAME_KEYS: &[&str] = &["friendly_name", "friendlyName", "displayName", "name"];
+
+/// What SteamVR's lighthouse database says about a base station.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StationInfo {
+    pub serial: String,
+    pub channel: Option<u8>,
+    /// Position in meters in SteamVR's tracking space.
+    pub position: Option<[f64; 3]>,
+}
+
+/// What a running SteamVR reports about a base station through OpenVR. Only available with the
+/// `openvr` feature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackingInfo {
+    /// Whether SteamVR has a valid pose for the station, i.e. it contributes to tracking.
+    pub tracking: bool,
+    /// OpenVR's tracking result for the station, e.g. `Running_OK`.
+    pub result: String,
+    /// When SteamVR last had a valid pose for the station.
+    pub pose_updated_at: Option<Instant>,
+}
+
+/// Outcome of an import.
+#[derive(Clone, Debug, Default)]
+pub struct ImportSummary {
+    /// Devices that were given an alias.
+    pub named: Vec<(BDAddr, String)>,
+    /// Names in SteamVR that did not match any discovered device.
+    pub unmatched: Vec<String>,
+}
+
+/// Watches for SteamVR starting and exiting. Depending on the settings, all devices are turned on
+/// when it starts and put to sleep a while after it exits, unless it is started again before that,
+/// and whether it is running is recorded for the interlock and for deferring to SteamVR. The
+/// SteamVR details of the devices and whether SteamVR manages their power itself are kept up to
+/// date as well.
+///
+/// SteamVR that is already running when the watch starts only counts as started if it launched
+/// the app, as set by `launched_by_steamvr`.
+pub async fn watch(
+    app_state: Arc<Mutex<AppState>>,
+    cmd_tx: Sender<Command>,
+    launched_by_steamvr: bool,
+) {
+    let mut was_running: Option<bool> = None;
+    let mut stations = Vec::new();
+    let mut stations_read_at: Option<Instant> = None;
+
+    loop {
+        sleep(PROCESS_CHECK_INTERVAL).await;
+
+        if stations_read_at.is_none_or(|at| at.elapsed() >= STATION_REFRESH_INTERVAL) {
+            stations_read_at = Some(Instant::now());
+
+            let config_dir = app_state.lock().await.config.steamvr_config_dir.clone();
+
+            let result = tokio::task::spawn_blocking(move || {
+                let config_dir = config_dir.as_deref();
+                (
+                    read_stations(config_dir),
+                    power_management_enabled(config_dir),
+                )
+            })
+            .await;
+
+            if let Ok((found, power_management)) = result {
+                stations = found;
+                app_state.lock().await.steamvr_power_management = power_management;
+            }
+        }
+
+        // Devices found since the last read are matched as well
+        apply_stations(&mut *app_state.lock().await, &stations);
+
+        let (power_on, sleep_delay, needs_running) = {
+            let config = &app_state.lock().await.config;
+
+            (
+                // SteamVR turns the devices on by itself when the app defers to it
+                config.power_on_with_steamvr && !config.defer_to_steamvr,
+                config
+                    .sleep_after_steamvr
+                    .then(|| config.steamvr_sleep_delay()),
+                config.steamvr_interlock != SteamVrInterlock::Off || config.defer_to_steamvr,
+            )
+        };
+
+        if !power_on && sleep_delay.is_none() && !needs_running {
+            was_running = None;
+
+            let mut app_state = app_state.lock().await;
+            app_state.steamvr_sleep_at = None;
+            app_state.steamvr_running = false;
+            continue;
+        }
+
+        let running = tokio::task::spawn_blocking(is_running)
+            .await
+            .unwrap_or(false);
+
+        app_state.lock().await.steamvr_running = running;
+
+        // Not running when the watch starts is no exit
+        let started = match was_running {
+            None => running && launched_by_steamvr,
+            Some(was_running) => !was_running && running,
+        };
+        let exited = was_running == Some(true) && !running;
+        was_running = Some(running);
+
+        let revert = if started {
+            app_state.lock().await.take_auto_off_revert()
+        } else {
+            Vec::new()
+        };
+
+        if !revert.is_empty() {
+            log::info!("SteamVR started again, waking the devices put to sleep right away");
+            let (reply_tx, _) = oneshot::channel();
+            cmd_tx
+                .send(Command::SendPowerStates(
+                    revert,
+                    PowerStateCommand::On,
+                    reply_tx,
+                ))
+                .await
+                .ok();
+        }
+
+        if started && power_on {
+            log::info!("SteamVR started, turning all devices on");
+            cmd_tx
+                .send(Command::ChangeAllPowerStates(PowerStateCommand::On))
+                .await
+                .ok();
+        }
+
+        let sleep_now = {
+            let mut app_state = app_state.lock().await;
+
+            match sleep_delay {
+                Some(delay) if exited => {
+                    log::info!(
+                        "SteamVR exited, putting all devices to sleep in {} s",
+                        delay.as_secs()
+                    );
+                    app_state.steamvr_sleep_at = Some(Instant::now() + delay);
+                    false
+                }
+                Some(_) if !running => match app_state.steamvr_sleep_at {
+                    Some(at) if Instant::now() >= at => {
+                        app_state.steamvr_sleep_at = None;
+                        app_state.record_auto_off();
+                        true
+                    }
+                    _ => false,
+                },
+                _ => {
+                    if app_state.steamvr_sleep_at.take().is_some() && running {
+                        log::info!("SteamVR started again, devices are kept on");
+                    }
+                    false
+                }
+            }
+        };
+
+        if sleep_now {
+            log::info!(
+                "SteamVR has been closed for the grace period, putting all devices to sleep"
+            );
+            cmd_tx
+                .send(Command::ChangeAllPowerStates(PowerStateCommand::Sleep))
+                .await
+                .ok();
+        }
+    }
+}
+
+/// Returns true if a SteamVR process is running.
+#[cfg(target_os = "linux")]
+pub fn is_running() -> bool {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+
+    entries.flatten().any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm"))
+            .is_ok_and(|comm| PROCESS_NAMES.contains(&comm.trim()))
+    })
+}
+
+/// Returns true if a SteamVR process is running.
+#[cfg(windows)]
+pub fn is_running() -> bool {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = std::process::Command::new("tasklist")
+        .args(["/FO", "CSV", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+
+    output.is_ok_and(|output| {
+        String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+            let image = line.split(',').next().unwrap_or_default().trim_matches('"');
+            let name = image.to_ascii_lowercase();
+
+            PROCESS_NAMES.contains(&name.trim_end_matches(".exe"))
+        })
+    })
+}
+
+/// Returns true if a SteamVR process is running.
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn is_running() -> bool {
+    let output = std::process::Command::new("ps")
+        .args(["-A", "-o", "comm="])
+        .output();
+
+    output.is_ok_and(|output| {
+        String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+            let name = line.trim().rsplit('/').next().unwrap_or_default();
+            PROCESS_NAMES.contains(&name)
+        })
+    })
+}
+
+/// Sets the aliases of the discovered devices that SteamVR has a name for. Devices that already
+/// have an alias are left as they are.
+///
+/// Base stations are matched by their serial number, which is the hex part of the advertised
+/// name, e.g. `LHB-1234ABCD`.
+pub fn import_names(app_state: &mut AppState) -> Result<ImportSummary, String> {
+    let names = read_names(app_state.config.steamvr_config_dir.as_deref())?;
+    let mut summary = ImportSummary::default();
+
+    for (serial, name) in names {
+        let found = app_state.device_entries.iter_mut().find(|(_, d)| {
+            d.name
+                .as_deref()
+                .is_some_and(|n| n.to_ascii_uppercase().ends_with(&serial))
+        });
+
+        let (addr, device) = match found {
+            Some((addr, device)) => (*addr, device),
+            None => {
+                summary.unmatched.push(name);
+                continue;
+            }
+        };
+
+        // SteamVR falls back to the advertised name, which is no better than no alias
+        if device.alias.is_some() || device.name.as_deref() == Some(name.as_str()) {
+            continue;
+        }
+
+        device.alias = Some(name.clone());
+        app_state
+            .config
+            .aliases
+            .insert(addr.to_string(), name.clone());
+        summary.named.push((addr, name));
+    }
+
+    summary.unmatched.sort();
+    summary.unmatched.dedup();
+
+    if !summary.named.is_empty() {
+        app_state.config.save();
+    }
+
+    Ok(summary)
+}
+
+/// Attaches the station info to the discovered devices with the same serial number.
+fn apply_stations(app_state: &mut AppState, stations: &[StationInfo]) {
+    for device in app_state.device_entries.values_mut() {
+        let name = device
+            .name
+            .as_deref()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        device.steamvr = stations
+            .iter()
+            .find(|station| name.ends_with(&station.serial))
+            .cloned();
+    }
+}
+
+/// Returns true if SteamVR's own base station power management is enabled, in which case it
+/// may change the power states behind the app's back.
+fn power_management_enabled(config_dir: Option<&str>) -> bool {
+    let dirs = match config_dir {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => default_config_dirs(),
+    };
+
+    dirs.iter()
+        .filter_map(|dir| std::fs::read_to_string(dir.join("steamvr.vrsettings")).ok())
+        .filter_map(|contents| serde_json::from_str::<Value>(&contents).ok())
+        .any(|settings| {
+            settings
+                .as_object()
+                .into_iter()
+                .flat_map(|sections| sections.values())
+                .filter_map(|section| section.get(POWER_MANAGEMENT_KEY))
+                .any(|value| match value {
+                    Value::Bool(enabled) => *enabled,
+                    Value::Number(mode) => mode.as_u64() != Some(0),
+                    _ => false,
+                })
+        })
+}
+
+/// Reads the base stations in SteamVR's lighthouse database. Missing files are not an error, as
+/// SteamVR may not be installed.
+fn read_stations(config_dir: Option<&str>) -> Vec<StationInfo> {
+    let dirs = match config_dir {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => default_config_dirs(),
+    };
+
+    let mut stations = Vec::new();
+
+    for path in dirs.iter().map(|dir| dir.join(LIGHTHOUSE_DB_FILE)) {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        match serde_json::from_str::<Value>(&contents) {
+            Ok(value) => collect_stations(&value, &mut stations),
+            Err(e) => log::debug!("Could not parse {}: {}", path.display(), e),
+        }
+    }
+
+    stations
+}
+
+/// Collects every object that has a serial number, at any depth. The database lists a station in
+/// several places, so the details found for the same serial are merged.
+fn collect_stations(value: &Value, stations: &mut Vec<StationInfo>) {
+    match value {
+        Value::Object(map) => {
+            let serial = SERIAL_KEYS
+                .iter()
+                .find_map(|key| map.get(*key))
+                .and_then(serial_string);
+
+            if let Some(serial) = serial {
+                let channel = CHANNEL_KEYS
+                    .iter()
+                    .find_map(|key| map.get(*key)?.as_u64())
+                    .filter(|channel| (1..=16).contains(channel))
+                    .map(|channel| channel as u8);
+                let position = POSE_KEYS
+                    .iter()
+                    .find_map(|key| map.get(*key)?.as_array())
+                    .and_then(|pose| {
+                        let mut coords = pose.iter().map(Value::as_f64);
+                        Some([coords.next()??, coords.next()??, coords.next()??])
+                    });
+
+                let index = match stations.iter().position(|s| s.serial == serial) {
+                    Some(index) => index,
+                    None => {
+                        stations.push(StationInfo {
+                            serial,
+                            ..Default::default()
+                        });
+                        stations.len() - 1
+                    }
+                };
+
+                let station = &mut stations[index];
+                station.channel = station.channel.or(channel);
+                station.position = station.position.or(position);
+            }
+
+            for value in map.values() {
+                collect_stations(value, stations);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_stations(value, stations);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reads the serial numbers and names of the base stations known to SteamVR.
+fn read_names(config_dir: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let dirs = match config_dir {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => default_config_dirs(),
+    };
+
+    let mut found = false;
+    let mut names = Vec::new();
+
+    for path in dirs
+        .iter()
+        .flat_map(|dir| FILES.iter().map(move |f| dir.join(f)))
+    {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        found = true;
+
+        match serde_json::from_str::<Value>(&contents) {
+            Ok(value) => collect_names(&value, &mut names),
+            Err(e) => log::warn!("Could not parse {}: {}", path.display(), e),
+        }
+    }
+
+    if found {
+        Ok(names)
+    } else {
+        Err("SteamVR configuration not found".to_string())
+    }
+}
+
+/// Collects every object that has both a serial number and a name, at any depth.
+fn collect_names(value: &Value, names: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            let serial = SERIAL_KEYS
+                .iter()
+                .find_map(|key| map.get(*key))
+                .and_then(serial_string);
+            let name = NAME_KEYS
+                .iter()
+                .find_map(|key| map.get(*key)?.as_str())
+                .map(str::trim)
+                .filter(|name| !name.is_empty());
+
+            if let (Some(serial), Some(name)) = (serial, name) {
+                names.push((serial, name.to_string()));
+            }
+
+            for value in map.values() {
+                collect_names(value, names);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_names(value, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Normalizes a serial number to the upper case hex form used in the advertised names.
+fn serial_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(number) => number.as_u64().map(|serial| format!("{:08X}", serial)),
+        Value::String(serial) => Some(serial.trim_start_matches("LHB-").to_ascii_uppercase())
+            .filter(|serial| !serial.is_empty()),
+        _ => None,
+    }
+}
+
+/// Writes the app manifest and registers it with SteamVR, so that SteamVR lists the app and starts
+/// it automatically. SteamVR rewrites its configuration while running, so this should be done
+/// while it is closed.
+pub fn register(config_dir: Option<&str>) -> Result<(), String> {
+    let steam_dir = steam_config_dir(config_dir)?;
+    let manifest = manifest_path().ok_or("No config directory")?;
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe = exe.to_string_lossy();
+
+    let contents = json!({
+        "source": "builtin",
+        "applications": [{
+            "app_key": APP_KEY,
+            "launch_type": "binary",
+            "binary_path_windows": exe,
+            "binary_path_linux": exe,
+            "binary_path_osx": exe,
+            "arguments": "--steamvr",
+            "is_dashboard_overlay": true,
+            "strings": {
+                "en_us": {
+                    "name": "SteamVR Lighthouse Control",
+                    "description": "Controls the power state of the base stations"
+                }
+            }
+        }]
+    });
+
+    if let Some(dir) = manifest.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    write_json(&manifest, &contents)?;
+
+    let mut app_config = read_json(&steam_dir.join(APP_CONFIG_FILE))?;
+    let manifest = manifest.to_string_lossy();
+    let paths = manifest_paths(&mut app_config)?;
+
+    if !paths
+        .iter()
+        .any(|path| path.as_str() == Some(manifest.as_ref()))
+    {
+        paths.push(Value::from(manifest.as_ref()));
+    }
+
+    write_json(&steam_dir.join(APP_CONFIG_FILE), &app_config)?;
+
+    let app_config_dir = steam_dir.join("vrappconfig");
+    std::fs::create_dir_all(&app_config_dir).map_err(|e| e.to_string())?;
+
+    let autolaunch_path = app_config_dir.join(format!("{}.vrappconfig", APP_KEY));
+    let mut autolaunch = read_json(&autolaunch_path)?;
+
+    match autolaunch.as_object_mut() {
+        Some(map) => map.insert("autolaunch".to_string(), Value::Bool(true)),
+        None => {
+            return Err(format!(
+                "Unexpected contents in {}",
+                autolaunch_path.display()
+            ))
+        }
+    };
+
+    write_json(&autolaunch_path, &autolaunch)
+}
+
+/// Removes the app manifest from SteamVR's configuration and deletes it.
+pub fn unregister(config_dir: Option<&str>) -> Result<(), String> {
+    let steam_dir = steam_config_dir(config_dir)?;
+    let manifest = manifest_path().ok_or("No config directory")?;
+
+    let mut app_config = read_json(&steam_dir.join(APP_CONFIG_FILE))?;
+    let manifest_str = manifest.to_string_lossy();
+    manifest_paths(&mut app_config)?.retain(|path| path.as_str() != Some(manifest_str.as_ref()));
+    write_json(&steam_dir.join(APP_CONFIG_FILE), &app_config)?;
+
+    if manifest.exists() {
+        std::fs::remove_file(&manifest).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Returns true if the app manifest is registered with SteamVR.
+pub fn is_registered(config_dir: Option<&str>) -> bool {
+    let (steam_dir, manifest) = match (steam_config_dir(config_dir), manifest_path()) {
+        (Ok(steam_dir), Some(manifest)) => (steam_dir, manifest),
+        _ => return false,
+    };
+
+    let manifest = manifest.to_string_lossy();
+
+    read_json(&steam_dir.join(APP_CONFIG_FILE))
+        .ok()
+        .and_then(|app_config| {
+            app_config["manifest_paths"].as_array().map(|paths| {
+                paths
+                    .iter()
+                    .any(|path| path.as_str() == Some(manifest.as_ref()))
+            })
+        })
+        .unwrap_or(false)
+}
+
+fn manifest_path() -> Option<PathBuf> {
+    Config::path()?
+        .parent()
+        .map(|dir| dir.join(format!("{}.vrmanifest", APP_KEY)))
+}
+
+fn manifest_paths(app_config: &mut Value) -> Result<&mut Vec<Value>, String> {
+    let map = app_config
+        .as_object_mut()
+        .ok_or_else(|| format!("Unexpected contents in {}", APP_CONFIG_FILE))?;
+
+    map.entry("manifest_paths")
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or_else(|| format!("Unexpected manifest_paths in {}", APP_CONFIG_FILE))
+}
+
+/// Reads a JSON file, or an empty object if it does not exist yet.
+fn read_json(path: &Path) -> Result<Value, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Could not parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(json!({})),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+
+    std::fs::write(path, contents).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// The configured Steam config directory, or the first default one that exists.
+fn steam_config_dir(config_dir: Option<&str>) -> Result<PathBuf, String> {
+    match config_dir {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => default_config_dirs()
+            .into_iter()
+            .find(|dir| dir.is_dir())
+            .ok_or_else(|| "Steam config directory not found".to_string()),
+    }
+}
+
+#[cfg(windows)]
+fn default_config_dirs() -> Vec<PathBuf> {
+    vec![PathBuf::from(r"C:\Program Files (x86)\Steam\config")]
+}
+
+#[cfg(not(windows))]
+fn default_config_dirs() -> Vec<PathBuf> {
+    let home = match dirs::home_dir() {
+        Some(home) => home,
+        None => return Vec::new(),
+    };
+
+    [
+        ".steam/steam/config",
+        ".local/share/Steam/config",
+        "Library/Application Support/Steam/config",
+    ]
+    .iter()
+    .map(|dir| home.join(dir))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serial_string_normalizes_serials() {
+        assert_eq!(
+            serial_string(&json!(0x1234ABCD)),
+            Some("1234ABCD".to_string())
+        );
+        assert_eq!(serial_string(&json!(0xABCD)), Some("0000ABCD".to_string()));
+        assert_eq!(
+            serial_string(&json!("LHB-1234abcd")),
+            Some("1234ABCD".to_string())
+        );
+        assert_eq!(
+            serial_string(&json!("1234abcd")),
+            Some("1234ABCD".to_string())
+        );
+    }
+
+    #[test]
+    fn serial_string_rejects_empty_and_other_values() {
+        assert_eq!(serial_string(&json!("")), None);
+        assert_eq!(serial_string(&json!("LHB-")), None);
+        assert_eq!(serial_string(&json!(true)), None);
+        assert_eq!(serial_string(&json!(-1)), None);
+    }
+
+    #[test]
+    fn collect_stations_merges_details_by_serial() {
+        let database = json!({
+            "base_stations": [
+                { "serialNumber": 0x1234ABCD, "channel": 3 },
+                { "nested": { "serial_number": "LHB-1234ABCD", "pose": [1.0, 2.0, 3.0] } },
+                { "serialNumber": "LHB-0000FFFF", "channel": 17 },
+            ]
+        });
+
+        let mut stations = Vec::new();
+        collect_stations(&database, &mut stations);
+
+        assert_eq!(
+            stations,
+            vec![
+                StationInfo {
+                    serial: "1234ABCD".to_string(),
+                    channel: Some(3),
+                    position: Some([1.0, 2.0, 3.0]),
+                },
+                StationInfo {
+                    serial: "0000FFFF".to_string(),
+                    channel: None,
+                    position: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Puts the base stations to sleep when the computer suspends or shuts down, so that they are not
+//! left spinning overnight, and optionally wakes them up again on resume.
+//!
+//! bleasy has no access to the power events, so they are read from the command line tools of the
+//! operating system instead: `gdbus` for systemd-logind on Linux and PowerShell for WMI on
+//! Windows. On Linux a delay lock is also taken with `systemd-inhibit` so that there is time to
+//! write the commands before the computer goes down. Shutdown is only detected on Linux, as
+//! Windows only announces it to windows with a message loop.
+
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bleasy::BDAddr;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdout};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::{sleep, timeout};
+
+use crate::config::ResumeAction;
+use crate::power::{PowerState, PowerStateCommand};
+use crate::state::{AppState, Command};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// How long the writes may take. systemd-logind waits for five seconds by default before it
+/// ignores the delay lock.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(4);
+/// Gives the Bluetooth adapter time to come back after resuming.
+const RESUME_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+enum PowerEvent {
+    Suspend,
+    Resume,
+    Shutdown,
+}
+
+/// Reads the power events of the operating system.
+struct Monitor {
+    events: Lines<BufReader<ChildStdout>>,
+    _process: Child,
+    /// Holds off suspend and shutdown until it is dropped.
+    inhibitor: Option<Child>,
+}
+
+impl Monitor {
+    fn start() -> std::io::Result<Self> {
+        let mut process = monitor_command()?
+            .stdout(Stdio::piped())
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdout = process
+            .stdout
+            .take()
+            .ok_or(std::io::ErrorKind::BrokenPipe)?;
+
+        Ok(Self {
+            events: BufReader::new(stdout).lines(),
+            _process: process,
+            inhibitor: inhibit(),
+        })
+    }
+
+    /// Lets suspend and shutdown go ahead.
+    fn release(&mut self) {
+        drop(self.inhibitor.take());
+    }
+
+    /// Holds off the next suspend or shutdown again.
+    fn delay(&mut self) {
+        if self.inhibitor.is_none() {
+            self.inhibitor = inhibit();
+        }
+    }
+
+    /// Returns the next event, or `None` once the monitor has exited.
+    async fn next_event(&mut self) -> Option<PowerEvent> {
+        loop {
+            match self.events.next_line().await {
+                Ok(Some(line)) => {
+                    if let Some(event) = parse_event(&line) {
+                        return Some(event);
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Watches for suspend and shutdown while `sleep_on_suspend` is enabled.
+pub async fn watch(app_state: Arc<Mutex<AppState>>, cmd_tx: Sender<Command>) {
+    let mut monitor: Option<Monitor> = None;
+    // Set when the monitor could not be used, so that it is not retried until the setting is
+    // turned off and on again
+    let mut unavailable = false;
+
+    loop {
+        let enabled = app_state.lock().await.config.sleep_on_suspend;
+
+        if !enabled {
+            monitor = None;
+            unavailable = false;
+        } else if monitor.is_none() && !unavailable {
+            match Monitor::start() {
+                Ok(started) => monitor = Some(started),
+                Err(e) => {
+                    log::error!("Could not watch for suspend and shutdown: {}", e);
+                    unavailable = true;
+                }
+            }
+        }
+
+        let event = match &mut monitor {
+            Some(monitor) => tokio::select! {
+                event = monitor.next_event() => event,
+                _ = sleep(CHECK_INTERVAL) => continue,
+            },
+            None => {
+                sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+        };
+
+        match event {
+            Some(PowerEvent::Suspend | PowerEvent::Shutdown) => {
+                log::info!("The computer is going down, putting the base stations to sleep");
+
+                sleep_devices(&app_state, &cmd_tx).await;
+
+                if let Some(monitor) = &mut monitor {
+                    monitor.release();
+                }
+            }
+            Some(PowerEvent::Resume) => {
+                if let Some(monitor) = &mut monitor {
+                    monitor.delay();
+                }
+
+                wake_devices(&app_state, &cmd_tx).await;
+            }
+            None => {
+                log::error!("Stopped receiving suspend and shutdown events");
+                monitor = None;
+                unavailable = true;
+            }
+        }
+    }
+}
+
+/// Puts every device that is not manual only to sleep, waiting for the writes for at most
+/// `WRITE_TIMEOUT`. The states before that are saved for `wake_devices`.
+async fn sleep_devices(app_state: &Mutex<AppState>, cmd_tx: &Sender<Command>) {
+    let addrs: Vec<_> = {
+        let mut app_state = app_state.lock().await;
+
+        let states = app_state
+            .auto_managed()
+            .into_iter()
+            .filter_map(|addr| {
+                let state = app_state.device_entries.get(&addr)?.power_state;
+                Some((addr.to_string(), PowerStateCommand::for_state(state)?))
+            })
+            .collect();
+
+        app_state.config.pre_suspend_states = states;
+        app_state.config.save();
+
+        app_state
+            .auto_managed()
+            .into_iter()
+            .filter(|addr| {
+                app_state
+                    .device_entries
+                    .get(addr)
+                    .is_some_and(|d| d.power_state != PowerState::Sleep)
+            })
+            .collect()
+    };
+
+    // The computer is about to go down, so this is sent even to devices that have just changed
+    // state, like the exit state
+    let writes = async {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        let cmd = Command::SendPowerStates(addrs, PowerStateCommand::Sleep, reply_tx);
+
+        if cmd_tx.send(cmd).await.is_ok() {
+            reply_rx.await.ok();
+        }
+    };
+
+    if timeout(WRITE_TIMEOUT, writes).await.is_err() {
+        log::warn!("Not all base stations could be put to sleep in time");
+    }
+}
+
+/// Turns the devices back on, or back into the states saved by `sleep_devices`, depending on
+/// `resume_action`.
+async fn wake_devices(app_state: &Mutex<AppState>, cmd_tx: &Sender<Command>) {
+    let (action, states) = {
+        let mut app_state = app_state.lock().await;
+        let states = std::mem::take(&mut app_state.config.pre_suspend_states);
+        app_state.config.save();
+
+        (app_state.config.resume_action, states)
+    };
+
+    if action == ResumeAction::Nothing {
+        return;
+    }
+
+    sleep(RESUME_DELAY).await;
+
+    log::info!("The computer has resumed, waking the base stations");
+
+    // The connections made before the suspend are usually gone
+    cmd_tx.send(Command::ReconnectAll).await.ok();
+
+    if action == ResumeAction::TurnOn {
+        cmd_tx
+            .send(Command::ChangeAllPowerStates(PowerStateCommand::On))
+            .await
+            .ok();
+        return;
+    }
+
+    for cmd in [PowerStateCommand::On, PowerStateCommand::Standby] {
+        let addrs: Vec<_> = states
+            .iter()
+            .filter(|(_, state)| **state == cmd)
+            .filter_map(|(addr, _)| BDAddr::from_str(addr).ok())
+            .collect();
+
+        if !addrs.is_empty() {
+            cmd_tx
+                .send(Command::ChangePowerStates(addrs, cmd))
+                .await
+                .ok();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn monitor_command() -> std::io::Result<tokio::process::Command> {
+    let mut command = tokio::process::Command::new("gdbus");
+
+    command.args([
+        "monitor",
+        "--system",
+        "--dest",
+        "org.freedesktop.login1",
+        "--object-path",
+        "/org/freedesktop/login1",
+    ]);
+
+    Ok(command)
+}
+
+/// Parses a signal printed by `gdbus monitor`, such as
+/// `/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)`.
+#[cfg(target_os = "linux")]
+fn parse_event(line: &str) -> Option<PowerEvent> {
+    if line.contains("PrepareForSleep (true") {
+        Some(PowerEvent::Suspend)
+    } else if line.contains("PrepareForSleep (false") {
+        Some(PowerEvent::Resume)
+    } else if line.contains("PrepareForShutdown (true") {
+        Some(PowerEvent::Shutdown)
+    } else {
+        None
+    }
+}
+
+/// Takes a delay lock that lasts until the returned process is dropped.
+#[cfg(target_os = "linux")]
+fn inhibit() -> Option<Child> {
+    let result = tokio::process::Command::new("systemd-inhibit")
+        .args([
+            "--what=sleep:shutdown",
+            "--mode=delay",
+            "--who=SteamVR Lighthouse Control",
+            "--why=Putting the base stations to sleep",
+            // Exits once stdin is closed, which happens when the process is dropped
+            "cat",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+
+    match result {
+        Ok(child) => Some(child),
+        Err(e) => {
+            log::warn!("Could not delay suspend and shutdown: {}", e);
+            None
+        }
+    }
+}
+
+/// Prints the event type of every Win32_PowerManagementEvent: 4 when suspending and 7 when
+/// resuming.
+#[cfg(windows)]
+const WMI_SCRIPT: &str = r#"
+Register-WmiEvent -Class Win32_PowerManagementEvent -SourceIdentifier power | Out-Null
+while ($true) {
+    $e = Wait-Event -SourceIdentifier power
+    [Console]::WriteLine($e.SourceEventArgs.NewEvent.EventType)
+    Remove-Event -EventIdentifier $e.EventIdentifier
+}
+"#;
+
+#[cfg(windows)]
+fn monitor_command() -> std::io::Result<tokio::process::Command> {
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = tokio::process::Command::new("powershell");
+
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", WMI_SCRIPT])
+        .creation_flags(CREATE_NO_WINDOW);
+
+    Ok(command)
+}
+
+#[cfg(windows)]
+fn parse_event(line: &str) -> Option<PowerEvent> {
+    match line.trim() {
+        "4" => Some(PowerEvent::Suspend),
+        "7" => Some(PowerEvent::Resume),
+        _ => None,
+    }
+}
+
+/// Windows only gives a moment to react to suspend and can't be delayed without a window.
+#[cfg(windows)]
+fn inhibit() -> Option<Child> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn monitor_command() -> std::io::Result<tokio::process::Command> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "not supported on this platform",
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn parse_event(_line: &str) -> Option<PowerEvent> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn inhibit() -> Option<Child> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_event_reads_logind_signals() {
+        let signal = |name: &str, value: &str| {
+            format!(
+                "/org/freedesktop/login1: org.freedesktop.login1.Manager.{} ({},)",
+                name, value
+            )
+        };
+
+        assert_eq!(
+            parse_event(&signal("PrepareForSleep", "true")),
+            Some(PowerEvent::Suspend)
+        );
+        assert_eq!(
+            parse_event(&signal("PrepareForSleep", "false")),
+            Some(PowerEvent::Resume)
+        );
+        assert_eq!(
+            parse_event(&signal("PrepareForShutdown", "true")),
+            Some(PowerEvent::Shutdown)
+        );
+        assert_eq!(parse_event(&signal("PrepareForShutdown", "false")), None);
+        assert_eq!(parse_event("Monitoring signals from all objects"), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn parse_event_reads_wmi_event_types() {
+        assert_eq!(parse_event("4"), Some(PowerEvent::Suspend));
+        assert_eq!(parse_event(" 7\r"), Some(PowerEvent::Resume));
+        assert_eq!(parse_event("10"), None);
+    }
+}
@@ -0,0 +1,289 @@
+//! Terminal interface for headless machines, e.g. when connected over SSH.
+
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use steamvr_lighthouse_control::config::SteamVrInterlock;
+use steamvr_lighthouse_control::power::{PowerState, PowerStateCommand};
+use steamvr_lighthouse_control::state::{panic_off, AppState, Command, ErrorState, PowerChange};
+use steamvr_lighthouse_control::{logging, LighthouseController};
+
+use crate::i18n::tf;
+use crate::ui::{
+    apply_power_change, power_change_target, request_power_change, undo, DEFAULT_TITLE,
+};
+
+/// How often the screen is redrawn when there is no input.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+const LOG_PANEL_HEIGHT: u16 = 8;
+const HELP: &str = "↑/↓ select  1 on  2 standby  3 sleep  t toggle  \
+                    A/S/D all on/standby/sleep  x panic off  u undo  r scan  R clear and scan  c reconnect  q quit";
+
+/// Runs the terminal interface until the user quits.
+pub fn run(controller: &LighthouseController) -> io::Result<()> {
+    // Log lines would be printed over the interface, so they are only shown in the log panel
+    logging::set_echo(false);
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let result = run_loop(&mut terminal, controller);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    logging::set_echo(true);
+
+    result
+}
+
+fn run_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    controller: &LighthouseController,
+) -> io::Result<()> {
+    let app_state = controller.state();
+    let cmd_tx = controller.command_sender();
+
+    loop {
+        {
+            let app_state = app_state.blocking_lock();
+            terminal.draw(|f| draw(f, &app_state))?;
+        }
+
+        if !event::poll(REFRESH_INTERVAL)? {
+            continue;
+        }
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        let mut app_state = app_state.blocking_lock();
+
+        // Turning devices off while SteamVR is running waits for y, any other key cancels it
+        if let Some(change) = app_state.power_change_confirmation.take() {
+            let blocked = app_state.config.steamvr_interlock == SteamVrInterlock::Block;
+
+            if key.code == KeyCode::Char('y') && !blocked {
+                apply_power_change(&cmd_tx, &mut app_state, change);
+            }
+
+            continue;
+        }
+
+        let power_command = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => {
+                app_state.select_adjacent(-1);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app_state.select_adjacent(1);
+                None
+            }
+            KeyCode::Char('1') => Some(PowerStateCommand::On),
+            KeyCode::Char('2') => Some(PowerStateCommand::Standby),
+            KeyCode::Char('3') => Some(PowerStateCommand::Sleep),
+            KeyCode::Char('t') => app_state
+                .selected
+                .and_then(|addr| app_state.device_entries.get(&addr))
+                .and_then(|d| d.toggle_command()),
+            KeyCode::Char('A') => {
+                let change = PowerChange::All(PowerStateCommand::On);
+                request_power_change(&cmd_tx, &mut app_state, change);
+                None
+            }
+            KeyCode::Char('S') => {
+                let change = PowerChange::All(PowerStateCommand::Standby);
+                request_power_change(&cmd_tx, &mut app_state, change);
+                None
+            }
+            KeyCode::Char('D') => {
+                let change = PowerChange::All(PowerStateCommand::Sleep);
+                request_power_change(&cmd_tx, &mut app_state, change);
+                None
+            }
+            KeyCode::Char('x') => {
+                panic_off(&mut app_state);
+                None
+            }
+            KeyCode::Char('u') => {
+                undo(&cmd_tx, &mut app_state);
+                None
+            }
+            KeyCode::Char('R') => {
+                cmd_tx.blocking_send(Command::ClearAndScan).ok();
+                None
+            }
+            KeyCode::Char('c') => {
+                cmd_tx.blocking_send(Command::ReconnectAll).ok();
+                None
+            }
+            KeyCode::Char('r') => {
+                let cmd = if app_state.config.continuous_scan && app_state.scanner.is_active() {
+                    Command::StopScan
+                } else {
+                    Command::StartScan
+                };
+
+                cmd_tx.blocking_send(cmd).ok();
+                None
+            }
+            _ => None,
+        };
+
+        if let (Some(cmd), Some(addr)) = (power_command, app_state.selected) {
+            if app_state.can_send(addr, cmd) {
+                request_power_change(&cmd_tx, &mut app_state, PowerChange::Device(addr, cmd));
+            }
+        }
+    }
+}
+
+fn draw<B: Backend>(f: &mut Frame<B>, app_state: &AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(LOG_PANEL_HEIGHT),
+        ])
+        .split(f.size());
+
+    f.render_widget(
+        Paragraph::new(format!("{}\n{}", status_line(app_state), HELP))
+            .block(Block::default().title(DEFAULT_TITLE)),
+        chunks[0],
+    );
+
+    let addrs: Vec<_> = app_state
+        .device_groups()
+        .into_iter()
+        .flat_map(|(_, addrs)| addrs)
+        .collect();
+
+    let items: Vec<_> = addrs
+        .iter()
+        .filter_map(|addr| {
+            let device = app_state.device_entries.get(addr)?;
+
+            let name = device
+                .alias
+                .as_deref()
+                .or(device.name.as_deref())
+                .unwrap_or("?");
+
+            let pending = if device.pending_command.is_some() {
+                "  …"
+            } else {
+                ""
+            };
+
+            let line = format!(
+                "{:<20} {:<12} {:<9} {:>8} {:>5}{}",
+                name,
+                device.group.as_deref().unwrap_or(""),
+                if device.is_warming_up(app_state.config.warm_up()) {
+                    "Warming up".to_string()
+                } else {
+                    device.power_state.to_string()
+                },
+                device
+                    .rssi
+                    .map(|rssi| format!("{} dBm", rssi))
+                    .unwrap_or_default(),
+                device
+                    .known_channel()
+                    .map(|ch| format!("ch {}", ch))
+                    .unwrap_or_default(),
+                pending
+            );
+
+            Some(ListItem::new(line).style(Style::default().fg(state_color(device.power_state))))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(
+        app_state
+            .selected
+            .and_then(|selected| addrs.iter().position(|addr| *addr == selected)),
+    );
+
+    f.render_stateful_widget(
+        List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Devices"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> "),
+        chunks[1],
+        &mut list_state,
+    );
+
+    let lines = logging::recent_lines();
+    let visible = (LOG_PANEL_HEIGHT as usize).saturating_sub(2);
+    let log = lines[lines.len().saturating_sub(visible)..].join("\n");
+
+    f.render_widget(
+        Paragraph::new(log).block(Block::default().borders(Borders::ALL).title("Log")),
+        chunks[2],
+    );
+}
+
+fn status_line(app_state: &AppState) -> String {
+    if let Some(change) = &app_state.power_change_confirmation {
+        let target = power_change_target(app_state, change);
+
+        return if app_state.config.steamvr_interlock == SteamVrInterlock::Block {
+            format!("{} (press any key)", tf("power_off_blocked", &[&target]))
+        } else {
+            format!("{} (y/n)", tf("power_off_warning", &[&target]))
+        };
+    }
+
+    match &app_state.error_state {
+        Some(ErrorState::StartFailed(e)) => format!(
+            "Scan failed (attempt {}): {}. Is bluetooth enabled? Retrying",
+            app_state.scan_failures, e
+        ),
+        None if app_state.adapter_lost => match &app_state.last_poll_error {
+            Some(e) => format!(
+                "Bluetooth adapter unavailable (attempt {}): {}",
+                app_state.recovery_attempts, e
+            ),
+            None => "Bluetooth adapter unavailable, reconnecting".to_string(),
+        },
+        None if app_state.scanner.is_active() && app_state.config.continuous_scan => {
+            "Scanning continuously, press r to stop".to_string()
+        }
+        None if app_state.scanner.is_active() => "Scanning for base stations".to_string(),
+        None if !app_state.has_scanned => "Press r to scan for base stations".to_string(),
+        None if app_state.device_entries.is_empty() => {
+            "No base stations found. Are they powered and in range?".to_string()
+        }
+        None => format!("Found {} devices", app_state.device_entries.len()),
+    }
+}
+
+fn state_color(state: PowerState) -> Color {
+    match state {
+        PowerState::On => Color::Green,
+        PowerState::Standby => Color::Yellow,
+        PowerState::Sleep => Color::Gray,
+        PowerState::Starting => Color::Blue,
+        PowerState::Unknown => Color::DarkGray,
+    }
+}
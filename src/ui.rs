@@ -0,0 +1,2115 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bleasy::BDAddr;
+use eframe::egui;
+use egui::output::OutputEvent;
+use egui::{Color32, Layout, Rect, Response, Sense, Stroke, Ui, Widget, WidgetInfo, WidgetType};
+use steamvr_lighthouse_control::config::{
+    Config, ExitPowerState, ResumeAction, ScanFilter, Schedule, SteamVrInterlock,
+};
+use steamvr_lighthouse_control::power::{PowerState, PowerStateCommand};
+use steamvr_lighthouse_control::state::{
+    panic_off, AppState, Command, DeviceEntry, ErrorState, PowerChange,
+};
+use steamvr_lighthouse_control::steamvr;
+use steamvr_lighthouse_control::update::RELEASES_PAGE_URL;
+use steamvr_lighthouse_control::VERSION;
+use steamvr_lighthouse_control::{launcher, logging};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::MutexGuard;
+
+use crate::i18n::{set_language, state_name, t, tf, LANGUAGES};
+
+pub const DEFAULT_TITLE: &str = "SteamVR Lighthouse Control";
+const PANIC_OFF_SHORTCUT: &str = "Ctrl+Shift+S";
+const STATE_INDICATOR_SIZE: f32 = 12.0;
+/// How long the name of a device is highlighted after its state has changed.
+const CHANGE_FLASH_DURATION: Duration = Duration::from_secs(2);
+/// Time span covered by the state history strip.
+const HISTORY_WINDOW: Duration = Duration::from_secs(600);
+
+pub fn ui_device_list(ui: &mut Ui, cmd_tx: &Sender<Command>, app_state: &mut MutexGuard<AppState>) {
+    let mut groups = app_state.device_groups();
+
+    let app_state = &mut **app_state;
+    let mut changed_groups = Vec::new();
+
+    if groups.iter().all(|(group, _)| group.is_none()) {
+        let addrs = groups.pop().map(|(_, addrs)| addrs).unwrap_or_default();
+
+        ui_device_grid(ui, cmd_tx, "grid", &addrs, app_state, &mut changed_groups);
+    } else {
+        for (group, addrs) in groups {
+            let title = group.clone().unwrap_or_else(|| t("ungrouped").to_string());
+
+            egui::CollapsingHeader::new(&title)
+                .default_open(true)
+                .show(ui, |ui| {
+                    if let Some(group) = &group {
+                        ui.horizontal(|ui| {
+                            for (label, cmd) in [
+                                ("on", PowerStateCommand::On),
+                                ("standby", PowerStateCommand::Standby),
+                                ("sleep", PowerStateCommand::Sleep),
+                            ] {
+                                if ui.small_button(t(label)).clicked() {
+                                    request_power_change(
+                                        cmd_tx,
+                                        app_state,
+                                        PowerChange::Group(group.clone(), cmd),
+                                    );
+                                }
+                            }
+                        });
+                    }
+
+                    ui_device_grid(ui, cmd_tx, &title, &addrs, app_state, &mut changed_groups);
+                });
+        }
+    }
+
+    if !changed_groups.is_empty() {
+        for addr in changed_groups {
+            let group = app_state
+                .device_entries
+                .get(&addr)
+                .and_then(|d| d.group.clone());
+
+            match group {
+                Some(group) => app_state.config.groups.insert(addr.to_string(), group),
+                None => app_state.config.groups.remove(&addr.to_string()),
+            };
+        }
+
+        app_state.config.save();
+    }
+}
+
+/// Shows the devices in `addrs`, and collects the addresses of devices whose group was edited.
+fn ui_device_grid(
+    ui: &mut Ui,
+    cmd_tx: &Sender<Command>,
+    id: &str,
+    addrs: &[BDAddr],
+    app_state: &mut AppState,
+    changed_groups: &mut Vec<BDAddr>,
+) {
+    egui::Grid::new(id)
+        .num_columns(4)
+        .striped(true)
+        .spacing([15.0, 4.0])
+        .show(ui, |ui| {
+            for addr in addrs {
+                let selected = app_state.selected == Some(*addr);
+                let checked = app_state.checked.contains(addr);
+
+                let response = match app_state.device_entries.get_mut(addr) {
+                    Some(device) => ui_device_entry(
+                        ui,
+                        cmd_tx,
+                        addr,
+                        device,
+                        selected,
+                        checked,
+                        &app_state.config,
+                    ),
+                    None => continue,
+                };
+
+                match response.checked_changed {
+                    Some(true) => {
+                        app_state.checked.insert(*addr);
+                    }
+                    Some(false) => {
+                        app_state.checked.remove(addr);
+                    }
+                    None => {}
+                }
+
+                if response.reboot_clicked {
+                    app_state.reboot_confirmation = Some(*addr);
+                }
+
+                if response.select_clicked {
+                    app_state.select(Some(*addr));
+                }
+
+                if response.group_changed {
+                    changed_groups.push(*addr);
+                }
+
+                if let Some(manual_only) = response.manual_only_changed {
+                    app_state.set_manual_only(*addr, manual_only);
+                }
+
+                if let Some(pinned) = response.pinned_changed {
+                    app_state.set_pinned(*addr, pinned);
+                }
+
+                if let Some(cmd) = response.power_command {
+                    request_power_change(cmd_tx, app_state, PowerChange::Device(*addr, cmd));
+                }
+            }
+        });
+}
+
+/// User interactions with a device row that need to be handled by the caller.
+struct EntryResponse {
+    select_clicked: bool,
+    checked_changed: Option<bool>,
+    group_changed: bool,
+    manual_only_changed: Option<bool>,
+    pinned_changed: Option<bool>,
+    reboot_clicked: bool,
+    power_command: Option<PowerStateCommand>,
+}
+
+/// Shows a single device row.
+fn ui_device_entry(
+    ui: &mut Ui,
+    cmd_tx: &Sender<Command>,
+    addr: &BDAddr,
+    device: &mut DeviceEntry,
+    selected: bool,
+    mut checked: bool,
+    config: &Config,
+) -> EntryResponse {
+    let power_state = device.power_state;
+    let mut select_clicked = false;
+    let mut checked_changed = None;
+    let mut group_changed = false;
+    let mut manual_only_changed = None;
+    let mut pinned_changed = None;
+    let mut reboot_clicked = false;
+    let mut power_command = None;
+
+    let name = device
+        .alias
+        .clone()
+        .or_else(|| device.name.clone())
+        .unwrap_or_else(|| "?".to_string());
+
+    announce_state_change(ui, addr, &name, power_state);
+
+    ui.horizontal(|ui| {
+        if describe(
+            ui.checkbox(&mut checked, ""),
+            WidgetType::Checkbox,
+            tf("include_in_bulk", &[&name]),
+        )
+        .changed()
+        {
+            checked_changed = Some(checked);
+        }
+
+        let (star, description) = if device.pinned {
+            ("★", tf("unpin", &[&name]))
+        } else {
+            ("☆", tf("pin", &[&name]))
+        };
+
+        if describe(
+            ui.selectable_label(device.pinned, star),
+            WidgetType::SelectableLabel,
+            &description,
+        )
+        .on_hover_text(description)
+        .clicked()
+        {
+            pinned_changed = Some(!device.pinned);
+        }
+
+        ui.label(t("name"));
+
+        let mut label = egui::RichText::new(name.as_str());
+
+        if let Some(changed_at) = device.state_changed_at {
+            let progress = changed_at.elapsed().as_secs_f32() / CHANGE_FLASH_DURATION.as_secs_f32();
+
+            // Fades out from a half transparent tint of the new state's color
+            if progress < 1.0 {
+                let tint = state_color(power_state).linear_multiply(0.5 * (1.0 - progress));
+                label = label.background_color(tint);
+                ui.ctx().request_repaint();
+            }
+        }
+
+        let response = ui
+            .selectable_label(selected, label)
+            .on_hover_text(t("name_hint"));
+
+        select_clicked = response.clicked();
+
+        response.context_menu(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(t("group"));
+
+                let mut group = device.group.clone().unwrap_or_default();
+
+                if ui.text_edit_singleline(&mut group).changed() {
+                    device.group = Some(group).filter(|g| !g.trim().is_empty());
+                    group_changed = true;
+                }
+            });
+
+            let mut manual_only = device.manual_only;
+
+            if ui
+                .checkbox(&mut manual_only, t("manual_only"))
+                .on_hover_text(t("manual_only_hint"))
+                .changed()
+            {
+                manual_only_changed = Some(manual_only);
+            }
+
+            if config.reboot_command().is_some() {
+                ui.separator();
+                ui.weak(t("advanced"));
+
+                if ui
+                    .button(egui::RichText::new(t("reboot_menu")).color(Color32::RED))
+                    .clicked()
+                {
+                    reboot_clicked = true;
+                    ui.close_menu();
+                }
+            }
+        });
+
+        if device.manual_only {
+            ui.weak(t("manual_only_badge"))
+                .on_hover_text(t("manual_only_badge_hint"));
+        }
+
+        let steamvr_channel = device.steamvr.as_ref().and_then(|s| s.channel);
+
+        match (device.channel, steamvr_channel) {
+            (Some(channel), Some(steamvr_channel)) if channel != steamvr_channel => {
+                ui.colored_label(
+                    Color32::from_rgb(230, 160, 30),
+                    tf("channel_badge", &[&channel]),
+                )
+                .on_hover_text(tf("channel_mismatch", &[&channel, &steamvr_channel]));
+            }
+            (None, Some(channel)) => {
+                ui.weak(tf("channel_badge", &[&channel]))
+                    .on_hover_text(tf("steamvr_channel_hint", &[&channel]));
+            }
+            (Some(channel), _) => {
+                ui.weak(tf("channel_badge", &[&channel]))
+                    .on_hover_text(tf("channel_hint", &[&channel]));
+            }
+            (None, None) => {}
+        }
+
+        if let Some(station) = &device.steamvr {
+            let mut hint = tf("serial_hint", &[&station.serial]);
+
+            if let Some(channel) = station.channel {
+                hint += "\n";
+                hint += &tf("steamvr_channel_hint", &[&channel]);
+            }
+
+            if let Some([x, y, z]) = station.position {
+                hint += "\n";
+                hint += &tf("position_hint", &[&format!("{:.2}, {:.2}, {:.2}", x, y, z)]);
+            }
+
+            ui.weak(t("steamvr_badge")).on_hover_text(hint);
+        }
+
+        match &device.tracking {
+            Some(tracking) if tracking.tracking => {
+                ui.colored_label(Color32::from_rgb(80, 180, 80), t("tracking_badge"))
+                    .on_hover_text(t("tracking_badge_hint"));
+            }
+            Some(_) if device.power_state == PowerState::On => {
+                ui.colored_label(Color32::from_rgb(230, 160, 30), t("not_tracking_badge"))
+                    .on_hover_text(t("not_tracking_badge_hint"));
+            }
+            _ => {}
+        }
+
+        if device.pairing_required
+            && describe(
+                ui.small_button(t("pair")),
+                WidgetType::Button,
+                tf("pair_device", &[&name]),
+            )
+            .on_hover_text(t("pair_hint"))
+            .clicked()
+        {
+            cmd_tx.blocking_send(Command::Pair(*addr)).ok();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("state"));
+
+        if device.is_warming_up(config.warm_up()) {
+            egui::Spinner::new().size(STATE_INDICATOR_SIZE).ui(ui);
+            ui.colored_label(state_color(power_state), t("warming_up"))
+                .on_hover_text(t("warming_up_hint"));
+        } else {
+            ui_state_indicator(ui, power_state);
+            ui.colored_label(state_color(power_state), state_name(power_state));
+        }
+
+        ui_state_history(ui, &device.history);
+
+        if let Some((temperature, _)) = device.temperature {
+            let color = if temperature >= config.overheat_temperature {
+                Color32::RED
+            } else if temperature >= config.temperature_warning {
+                Color32::from_rgb(230, 160, 30)
+            } else {
+                ui.visuals().text_color()
+            };
+
+            ui.colored_label(color, format!("{:.0} °C", temperature))
+                .on_hover_text(tf("temperature_hint", &[&format!("{:.1}", temperature)]));
+        }
+
+        if let Some(pending) = device.pending_command {
+            ui.weak("⏳").on_hover_text(tf(
+                "pending_hint",
+                &[&state_name(pending.cmd.target_state()), &pending.attempts],
+            ));
+        }
+    });
+
+    ui.label(format!(
+        "⏱ {:.1} h",
+        device.total_on_time.as_secs_f64() / 3600.0
+    ))
+    .on_hover_text(tf(
+        "on_time_hint",
+        &[
+            &format_duration(device.total_on_time),
+            &format_duration(device.session_on_time),
+        ],
+    ));
+
+    let cooldown = config.power_cooldown();
+    let cooldown_remaining = |cmd| device.cooldown_remaining(cmd, cooldown);
+    let enabled = |cmd| device.can_send(cmd) && cooldown_remaining(cmd).is_none();
+    let disabled_hint = |cmd| disabled_reason(device, cmd, cooldown);
+
+    ui.allocate_ui(ui.available_size(), |ui| {
+        ui.with_layout(Layout::right_to_left(), |ui| {
+            ui.horizontal(|ui| {
+                if let Some(cmd) = device.toggle_command() {
+                    let description = match cmd {
+                        PowerStateCommand::On => tf("turn_on", &[&name]),
+                        _ => tf("put_to_sleep", &[&name]),
+                    };
+
+                    if with_disabled_hint(
+                        describe(
+                            ui.add_enabled(enabled(cmd), egui::Button::new("⏻")),
+                            WidgetType::Button,
+                            description,
+                        ),
+                        disabled_hint(cmd),
+                    )
+                    .on_hover_text(t("toggle_hint"))
+                    .clicked()
+                    {
+                        power_command = Some(cmd);
+                    }
+                }
+
+                if with_disabled_hint(
+                    describe(
+                        ui.add_enabled(
+                            enabled(PowerStateCommand::Standby),
+                            power_button(t("stand_by"), PowerStateCommand::Standby, power_state),
+                        ),
+                        WidgetType::Button,
+                        tf("put_in_standby", &[&name]),
+                    ),
+                    disabled_hint(PowerStateCommand::Standby),
+                )
+                .clicked()
+                {
+                    power_command = Some(PowerStateCommand::Standby);
+                }
+
+                if with_disabled_hint(
+                    describe(
+                        ui.add_enabled(
+                            enabled(PowerStateCommand::Sleep),
+                            power_button(t("sleep"), PowerStateCommand::Sleep, power_state),
+                        ),
+                        WidgetType::Button,
+                        tf("put_to_sleep", &[&name]),
+                    ),
+                    disabled_hint(PowerStateCommand::Sleep),
+                )
+                .clicked()
+                {
+                    power_command = Some(PowerStateCommand::Sleep);
+                }
+
+                if with_disabled_hint(
+                    describe(
+                        ui.add_enabled(
+                            enabled(PowerStateCommand::On),
+                            power_button(t("on"), PowerStateCommand::On, power_state),
+                        ),
+                        WidgetType::Button,
+                        tf("turn_on", &[&name]),
+                    ),
+                    disabled_hint(PowerStateCommand::On),
+                )
+                .clicked()
+                {
+                    power_command = Some(PowerStateCommand::On);
+                }
+            });
+        });
+    });
+
+    ui.end_row();
+
+    EntryResponse {
+        select_clicked,
+        checked_changed,
+        group_changed,
+        manual_only_changed,
+        pinned_changed,
+        reboot_clicked,
+        power_command,
+    }
+}
+
+/// Draws a dot in the color of `state`, or a spinner while the device is starting.
+fn ui_state_indicator(ui: &mut Ui, state: PowerState) {
+    if state == PowerState::Starting {
+        egui::Spinner::new().size(STATE_INDICATOR_SIZE).ui(ui);
+        return;
+    }
+
+    let (rect, _) = ui.allocate_exact_size(egui::Vec2::splat(STATE_INDICATOR_SIZE), Sense::hover());
+    let radius = STATE_INDICATOR_SIZE / 2.0 - 1.0;
+    let color = state_color(state);
+
+    if state == PowerState::Unknown {
+        ui.painter()
+            .circle_stroke(rect.center(), radius, Stroke::new(1.5, color));
+    } else {
+        ui.painter().circle_filled(rect.center(), radius, color);
+    }
+}
+
+/// A power button in the color of the state it leads to. The button of the state the device is
+/// currently in is filled.
+fn power_button(label: &str, cmd: PowerStateCommand, current: PowerState) -> egui::Button {
+    let target = cmd.target_state();
+    let color = state_color(target);
+    let button = egui::Button::new(egui::RichText::new(label).color(color));
+
+    if [target, cmd.expected_state()].contains(&current) {
+        button
+            .fill(color.linear_multiply(0.25))
+            .stroke(Stroke::new(1.0, color))
+    } else {
+        button
+    }
+}
+
+/// Explains why the power button for `cmd` is disabled, or returns `None` if it is enabled.
+fn disabled_reason(
+    device: &DeviceEntry,
+    cmd: PowerStateCommand,
+    cooldown: Duration,
+) -> Option<String> {
+    if let Some(remaining) = device.cooldown_remaining(cmd, cooldown) {
+        return Some(tf("cooldown_remaining", &[&(remaining.as_secs() + 1)]));
+    }
+
+    if device.can_send(cmd) {
+        return None;
+    }
+
+    let key = match device.power_state {
+        PowerState::On => "already_on",
+        PowerState::Starting => "already_starting",
+        PowerState::Standby => "already_standby",
+        PowerState::Sleep => "already_asleep",
+        PowerState::Unknown => "state_unknown_hint",
+    };
+
+    Some(t(key).to_string())
+}
+
+/// Shows `reason` when hovering over a disabled power button.
+fn with_disabled_hint(response: Response, reason: Option<String>) -> Response {
+    match reason {
+        Some(reason) => response.on_disabled_hover_text(reason),
+        None => response,
+    }
+}
+
+/// Replaces the short text a screen reader would read for a widget with `description`.
+fn describe(response: Response, typ: WidgetType, description: impl ToString) -> Response {
+    let description = description.to_string();
+    response.widget_info(|| WidgetInfo::labeled(typ, &description));
+    response
+}
+
+/// Lets a screen reader announce the device's new state whenever it changes.
+fn announce_state_change(ui: &Ui, addr: &BDAddr, name: &str, state: PowerState) {
+    let id = egui::Id::new(("announced_state", addr));
+    let previous = ui.ctx().data().get_temp::<PowerState>(id);
+    ui.ctx().data().insert_temp(id, state);
+
+    if previous.is_some_and(|previous| previous != state) {
+        ui.output()
+            .events
+            .push(OutputEvent::ValueChanged(WidgetInfo::labeled(
+                WidgetType::Label,
+                tf("state_changed", &[&name, &state_name(state)]),
+            )));
+    }
+}
+
+fn state_color(state: PowerState) -> Color32 {
+    match state {
+        PowerState::On => Color32::from_rgb(60, 180, 75),
+        PowerState::Standby => Color32::from_rgb(230, 160, 30),
+        PowerState::Sleep => Color32::from_gray(110),
+        PowerState::Starting => Color32::from_rgb(70, 130, 220),
+        PowerState::Unknown => Color32::from_gray(60),
+    }
+}
+
+/// Draws a strip of the device's state over the last `HISTORY_WINDOW`, newest on the right.
+fn ui_state_history(ui: &mut Ui, history: &VecDeque<(Instant, PowerState)>) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(60.0, 10.0), Sense::hover());
+
+    let now = Instant::now();
+    let window = HISTORY_WINDOW.as_secs_f32();
+    let x_at = |time: Instant| {
+        let age = now.saturating_duration_since(time).as_secs_f32();
+        rect.right() - (age / window).min(1.0) * rect.width()
+    };
+
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0.0, Color32::from_gray(30));
+
+    for (i, (start, state)) in history.iter().enumerate() {
+        let end = history.get(i + 1).map_or(now, |(time, _)| *time);
+        let segment = Rect::from_x_y_ranges(x_at(*start)..=x_at(end), rect.y_range());
+
+        painter.rect_filled(segment, 0.0, state_color(*state));
+    }
+
+    if let Some(pos) = response.hover_pos() {
+        let age = (rect.right() - pos.x) / rect.width() * window;
+        let hovered_time = now.checked_sub(Duration::from_secs_f32(age.max(0.0)));
+
+        let segment =
+            hovered_time.and_then(|time| history.iter().rev().find(|(start, _)| *start <= time));
+
+        if let Some((start, state)) = segment {
+            response.on_hover_text(tf(
+                "state_since",
+                &[&state_name(*state), &format_duration(now - *start)],
+            ));
+        }
+    }
+}
+
+/// Warns about devices that share an RF channel, which causes tracking problems.
+pub fn ui_channel_conflicts(ui: &mut Ui, cmd_tx: &Sender<Command>, app_state: &mut AppState) {
+    let conflicts = app_state.channel_conflicts();
+
+    if conflicts.is_empty() {
+        return;
+    }
+
+    for (channel, addrs) in conflicts {
+        let names: Vec<_> = addrs
+            .iter()
+            .map(|addr| {
+                app_state
+                    .device_entries
+                    .get(addr)
+                    .and_then(|d| d.alias.clone().or_else(|| d.name.clone()))
+                    .unwrap_or_else(|| addr.to_string())
+            })
+            .collect();
+
+        ui.colored_label(
+            Color32::from_rgb(230, 160, 30),
+            tf("channel_conflict", &[&channel, &names.join(", ")]),
+        );
+    }
+
+    if ui
+        .button(t("assign_channels"))
+        .on_hover_text(t("assign_channels_hint"))
+        .clicked()
+    {
+        cmd_tx.blocking_send(Command::AssignUniqueChannels).ok();
+    }
+}
+
+/// Warns when SteamVR's own base station power management is enabled, as it changes the power
+/// states too and the two end up fighting.
+pub fn ui_steamvr_conflict(ui: &mut Ui, app_state: &mut AppState) {
+    let id = egui::Id::new("steamvr_conflict_dismissed");
+
+    if !app_state.steamvr_power_management || ui.ctx().data().get_temp::<bool>(id).is_some() {
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.colored_label(
+            Color32::from_rgb(230, 160, 30),
+            t("steamvr_power_management"),
+        )
+        .on_hover_text(t("steamvr_power_management_help"));
+
+        if ui
+            .checkbox(
+                &mut app_state.config.defer_to_steamvr,
+                t("defer_to_steamvr"),
+            )
+            .on_hover_text(t("defer_to_steamvr_hint"))
+            .changed()
+        {
+            app_state.config.save();
+        }
+
+        if ui.small_button(t("dismiss")).clicked() {
+            ui.ctx().data().insert_temp(id, true);
+        }
+    });
+}
+
+pub fn ui_header(
+    ui: &mut Ui,
+    cmd_tx: &Sender<Command>,
+    app_state: &mut MutexGuard<AppState>,
+    show_diagnostics: &mut bool,
+    show_settings: &mut bool,
+) -> bool {
+    let mut toggle_compact = false;
+
+    ui.horizontal(|ui| {
+        match &app_state.error_state {
+            Some(ErrorState::StartFailed(e)) => {
+                egui::Spinner::default().ui(ui);
+                ui.label(tf("scan_failed_attempt", &[&app_state.scan_failures, e]))
+                    .on_hover_text(t("scan_failed"));
+            }
+            None => {
+                if app_state.adapter_lost {
+                    egui::Spinner::default().ui(ui);
+
+                    match &app_state.last_poll_error {
+                        Some(e) if app_state.recovery_attempts > 0 => ui.label(tf(
+                            "adapter_unavailable_attempt",
+                            &[&app_state.recovery_attempts, e],
+                        )),
+                        Some(e) => ui.label(tf("adapter_unavailable_error", &[e])),
+                        None => ui.label(t("adapter_unavailable")),
+                    };
+                } else if app_state.scanner.is_active() {
+                    egui::Spinner::default().ui(ui);
+
+                    if app_state.config.continuous_scan {
+                        ui.label(t("scanning_continuously"))
+                            .on_hover_text(t("scanning_continuously_hint"));
+                    } else {
+                        ui.label(t("scanning"));
+                    }
+                } else if !app_state.has_scanned {
+                    ui.label(t("press_scan"));
+                } else if app_state.device_entries.is_empty() {
+                    ui.label(t("no_devices"))
+                        .on_hover_text(t("no_devices_hint"));
+                } else {
+                    ui.label(tf("found_devices", &[&app_state.device_entries.len()]));
+                }
+            }
+        }
+
+        ui.allocate_ui(ui.available_size(), |ui| {
+            ui.with_layout(Layout::right_to_left(), |ui| {
+                if app_state.config.continuous_scan && app_state.scanner.is_active() {
+                    if describe(ui.button("⏹"), WidgetType::Button, t("stop_scanning"))
+                        .on_hover_text(t("stop_scanning"))
+                        .clicked()
+                    {
+                        cmd_tx.blocking_send(Command::StopScan).ok();
+                    }
+                } else {
+                    let response = describe(
+                        ui.add_enabled(!app_state.scanner.is_active(), egui::Button::new("🔃")),
+                        WidgetType::Button,
+                        t("scan"),
+                    )
+                    .on_hover_text(t("scan_hint"));
+
+                    if response.clicked() {
+                        cmd_tx.blocking_send(Command::StartScan).ok();
+                    }
+
+                    response.context_menu(|ui| {
+                        if ui.button(t("clear_and_scan")).clicked() {
+                            cmd_tx.blocking_send(Command::ClearAndScan).ok();
+                            ui.close_menu();
+                        }
+                    });
+                }
+
+                if describe(
+                    ui.add_enabled(!app_state.ble_devices.is_empty(), egui::Button::new("🔌")),
+                    WidgetType::Button,
+                    t("reconnect_all"),
+                )
+                .on_hover_text(t("reconnect_all_hint"))
+                .clicked()
+                {
+                    cmd_tx.blocking_send(Command::ReconnectAll).ok();
+                }
+
+                if describe(
+                    ui.selectable_label(*show_diagnostics, "📊"),
+                    WidgetType::SelectableLabel,
+                    t("poll_diagnostics"),
+                )
+                .on_hover_text(t("poll_diagnostics"))
+                .clicked()
+                {
+                    *show_diagnostics = !*show_diagnostics;
+                }
+
+                if describe(
+                    ui.selectable_label(*show_settings, "⚙"),
+                    WidgetType::SelectableLabel,
+                    t("settings"),
+                )
+                .on_hover_text(t("settings"))
+                .clicked()
+                {
+                    *show_settings = !*show_settings;
+                }
+
+                if describe(
+                    ui.add_enabled(app_state.can_undo(), egui::Button::new("↶")),
+                    WidgetType::Button,
+                    t("undo"),
+                )
+                .on_hover_text(t("undo_hint"))
+                .on_disabled_hover_text(if app_state.undo_stack.is_empty() {
+                    t("undo_hint")
+                } else {
+                    t("undo_cooldown_hint")
+                })
+                .clicked()
+                {
+                    undo(cmd_tx, app_state);
+                }
+
+                if describe(
+                    ui.add_enabled(
+                        app_state.launch_started_at.is_none(),
+                        egui::Button::new("🚀"),
+                    ),
+                    WidgetType::Button,
+                    t("launch"),
+                )
+                .on_hover_text(t("launch_hint"))
+                .clicked()
+                {
+                    launcher::request(app_state);
+                }
+
+                if let Some(started_at) = app_state.launch_started_at {
+                    ui.label(tf("launching", &[&started_at.elapsed().as_secs()]));
+                    egui::Spinner::default().ui(ui);
+                }
+
+                if let Some(at) = app_state.steamvr_sleep_at {
+                    let remaining = at.saturating_duration_since(Instant::now()).as_secs();
+
+                    ui.label(format!("💤 {}:{:02}", remaining / 60, remaining % 60))
+                        .on_hover_text(t("steamvr_sleep_countdown"));
+                }
+
+                match app_state.sleep_timer_at {
+                    Some(at) => {
+                        let remaining = at.saturating_duration_since(Instant::now()).as_secs();
+
+                        ui.label(format!("⏲ {}:{:02}", remaining / 60, remaining % 60))
+                            .on_hover_text(t("sleep_timer_countdown"));
+
+                        if describe(
+                            ui.small_button("✖"),
+                            WidgetType::Button,
+                            t("cancel_sleep_timer"),
+                        )
+                        .on_hover_text(t("cancel_sleep_timer"))
+                        .clicked()
+                        {
+                            cmd_tx.blocking_send(Command::SetSleepTimer(None)).ok();
+                        }
+                    }
+                    None => {
+                        if describe(ui.button("⏲"), WidgetType::Button, t("sleep_timer"))
+                            .on_hover_text(tf(
+                                "sleep_timer_hint",
+                                &[&app_state.config.sleep_timer_mins],
+                            ))
+                            .clicked()
+                        {
+                            let duration = app_state.config.sleep_timer();
+                            cmd_tx
+                                .blocking_send(Command::SetSleepTimer(Some(duration)))
+                                .ok();
+                        }
+                    }
+                }
+
+                if let Some(tag) = &app_state.update_available {
+                    ui.hyperlink_to(format!("⬆ {}", tag), RELEASES_PAGE_URL)
+                        .on_hover_text(t("update_available"));
+                }
+
+                toggle_compact = describe(ui.button("🗕"), WidgetType::Button, t("compact_mode"))
+                    .on_hover_text(t("compact_mode"))
+                    .clicked();
+            });
+        });
+    });
+
+    toggle_compact
+}
+
+/// Minimal control bar shown in compact mode. Returns true if compact mode should be toggled.
+pub fn ui_compact_bar(
+    ui: &mut Ui,
+    cmd_tx: &Sender<Command>,
+    app_state: &mut MutexGuard<AppState>,
+) -> bool {
+    let mut toggle_compact = false;
+
+    ui.horizontal(|ui| {
+        let count = |state: PowerState| {
+            app_state
+                .device_entries
+                .values()
+                .filter(|d| d.power_state == state)
+                .count()
+        };
+
+        ui.label(tf(
+            "compact_summary",
+            &[
+                &(count(PowerState::On) + count(PowerState::Starting)),
+                &count(PowerState::Standby),
+                &count(PowerState::Sleep),
+            ],
+        ));
+
+        ui.allocate_ui(ui.available_size(), |ui| {
+            ui.with_layout(Layout::right_to_left(), |ui| {
+                toggle_compact = describe(ui.button("🗖"), WidgetType::Button, t("full_mode"))
+                    .on_hover_text(t("full_mode"))
+                    .clicked();
+
+                if describe(
+                    ui.checkbox(&mut app_state.config.always_on_top, "📌"),
+                    WidgetType::Checkbox,
+                    t("always_on_top"),
+                )
+                .on_hover_text(t("always_on_top_hint"))
+                .changed()
+                {
+                    app_state.config.save();
+                }
+
+                ui_all_power_buttons(ui, cmd_tx, app_state);
+            });
+        });
+    });
+
+    toggle_compact
+}
+
+/// Asks for confirmation before rebooting the device chosen from its context menu.
+pub fn ui_reboot_confirmation(
+    ctx: &egui::Context,
+    cmd_tx: &Sender<Command>,
+    app_state: &mut MutexGuard<AppState>,
+) {
+    let addr = match app_state.reboot_confirmation {
+        Some(addr) => addr,
+        None => return,
+    };
+
+    let name = app_state
+        .device_entries
+        .get(&addr)
+        .and_then(|d| d.alias.clone().or_else(|| d.name.clone()))
+        .unwrap_or_else(|| addr.to_string());
+
+    egui::Window::new(t("reboot_title"))
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.label(tf("reboot_warning", &[&name]));
+
+            ui.horizontal(|ui| {
+                if ui
+                    .button(egui::RichText::new(t("reboot")).color(Color32::RED))
+                    .clicked()
+                {
+                    cmd_tx.blocking_send(Command::Reboot(addr)).ok();
+                    app_state.reboot_confirmation = None;
+                }
+
+                if ui.button(t("cancel")).clicked() {
+                    app_state.reboot_confirmation = None;
+                }
+            });
+        });
+}
+
+/// Asks for confirmation before turning devices off while SteamVR is running, or explains why
+/// they were not turned off if the interlock blocks it.
+pub fn ui_power_change_confirmation(
+    ctx: &egui::Context,
+    cmd_tx: &Sender<Command>,
+    app_state: &mut MutexGuard<AppState>,
+) {
+    let change = match app_state.power_change_confirmation.clone() {
+        Some(change) => change,
+        None => return,
+    };
+
+    let blocked = app_state.config.steamvr_interlock == SteamVrInterlock::Block;
+    let target = power_change_target(app_state, &change);
+
+    egui::Window::new(t("steamvr_running"))
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            if blocked {
+                ui.label(tf("power_off_blocked", &[&target]));
+
+                if ui.button(t("close")).clicked() {
+                    app_state.power_change_confirmation = None;
+                }
+
+                return;
+            }
+
+            ui.label(tf("power_off_warning", &[&target]));
+
+            ui.horizontal(|ui| {
+                let label = match change.command() {
+                    PowerStateCommand::Standby => t("standby"),
+                    _ => t("sleep"),
+                };
+
+                if ui
+                    .button(egui::RichText::new(label).color(Color32::RED))
+                    .clicked()
+                {
+                    app_state.power_change_confirmation = None;
+                    apply_power_change(cmd_tx, app_state, change.clone());
+                }
+
+                if ui.button(t("cancel")).clicked() {
+                    app_state.power_change_confirmation = None;
+                }
+            });
+        });
+}
+
+/// Keyboard control of the selected device: the up and down arrows move the selection, 1, 2
+/// and 3 turn it on, put it in standby or put it to sleep, and T flips it between on and sleep.
+pub fn handle_selection_keys(
+    ctx: &egui::Context,
+    cmd_tx: &Sender<Command>,
+    app_state: &mut MutexGuard<AppState>,
+) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    let (offset, cmd, toggle) = {
+        let input = ctx.input();
+
+        let offset = if input.key_pressed(egui::Key::ArrowUp) {
+            -1
+        } else if input.key_pressed(egui::Key::ArrowDown) {
+            1
+        } else {
+            0
+        };
+
+        let cmd = if input.key_pressed(egui::Key::Num1) {
+            Some(PowerStateCommand::On)
+        } else if input.key_pressed(egui::Key::Num2) {
+            Some(PowerStateCommand::Standby)
+        } else if input.key_pressed(egui::Key::Num3) {
+            Some(PowerStateCommand::Sleep)
+        } else {
+            None
+        };
+
+        (offset, cmd, input.key_pressed(egui::Key::T))
+    };
+
+    if offset != 0 {
+        app_state.select_adjacent(offset);
+    }
+
+    let addr = match app_state.selected {
+        Some(addr) => addr,
+        None => return,
+    };
+
+    let toggle_command = || {
+        app_state
+            .device_entries
+            .get(&addr)
+            .and_then(|d| d.toggle_command())
+    };
+
+    let cmd = match cmd.or_else(|| toggle.then(toggle_command).flatten()) {
+        Some(cmd) => cmd,
+        None => return,
+    };
+
+    if app_state.can_send(addr, cmd) {
+        request_power_change(cmd_tx, app_state, PowerChange::Device(addr, cmd));
+    }
+}
+
+/// Buttons for changing the power state of every device at once.
+pub fn ui_all_power_buttons(
+    ui: &mut Ui,
+    cmd_tx: &Sender<Command>,
+    app_state: &mut MutexGuard<AppState>,
+) {
+    for (label, cmd) in [
+        ("on_all", PowerStateCommand::On),
+        ("standby_all", PowerStateCommand::Standby),
+        ("sleep_all", PowerStateCommand::Sleep),
+    ] {
+        if ui
+            .add_enabled(
+                !app_state.device_entries.is_empty(),
+                egui::Button::new(t(label)),
+            )
+            .clicked()
+        {
+            request_power_change(cmd_tx, app_state, PowerChange::All(cmd));
+        }
+    }
+
+    let pending: Vec<_> = app_state
+        .device_entries
+        .values()
+        .filter(|d| d.pending_command.is_some())
+        .map(|d| d.alias.as_deref().or(d.name.as_deref()).unwrap_or("?"))
+        .collect();
+
+    if !pending.is_empty() {
+        ui.weak(tf("pending_count", &[&pending.len()]))
+            .on_hover_text(tf("pending_list", &[&pending.join("\n")]));
+    }
+
+    if ui
+        .add(
+            egui::Button::new(egui::RichText::new(t("panic_off")).color(Color32::WHITE))
+                .fill(Color32::from_rgb(180, 30, 30)),
+        )
+        .on_hover_text(tf("panic_off_hint", &[&PANIC_OFF_SHORTCUT]))
+        .clicked()
+    {
+        panic_off(app_state);
+    }
+}
+
+/// Toolbar for changing the power state of the checked devices. Only shown when some are checked.
+pub fn ui_bulk_actions(ui: &mut Ui, cmd_tx: &Sender<Command>, app_state: &mut AppState) {
+    if app_state.checked.is_empty() {
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(tf("checked_count", &[&app_state.checked.len()]));
+
+        for (label, cmd) in [
+            ("on", PowerStateCommand::On),
+            ("standby", PowerStateCommand::Standby),
+            ("sleep", PowerStateCommand::Sleep),
+        ] {
+            if ui.button(t(label)).clicked() {
+                request_power_change(cmd_tx, app_state, PowerChange::Checked(cmd));
+            }
+        }
+
+        if ui.button(t("clear")).clicked() {
+            app_state.checked.clear();
+        }
+    });
+}
+
+/// Describes the devices affected by `change`, for the confirmation.
+pub fn power_change_target(app_state: &AppState, change: &PowerChange) -> String {
+    match change {
+        PowerChange::Device(addr, _) => app_state
+            .device_entries
+            .get(addr)
+            .and_then(|d| d.alias.clone().or_else(|| d.name.clone()))
+            .unwrap_or_else(|| addr.to_string()),
+        PowerChange::All(_) => t("all_base_stations").to_string(),
+        PowerChange::Checked(_) => tf("checked_devices", &[&app_state.checked.len()]),
+        PowerChange::Group(group, _) => tf("group_target", &[group]),
+    }
+}
+
+/// Applies a power change, unless it would turn devices off while SteamVR is running. Then it has
+/// to be confirmed first or is refused, depending on the interlock setting.
+pub fn request_power_change(
+    cmd_tx: &Sender<Command>,
+    app_state: &mut AppState,
+    change: PowerChange,
+) {
+    if change.command() != PowerStateCommand::On
+        && app_state.steamvr_running
+        && app_state.config.steamvr_interlock != SteamVrInterlock::Off
+    {
+        app_state.power_change_confirmation = Some(change);
+        return;
+    }
+
+    apply_power_change(cmd_tx, app_state, change);
+}
+
+pub fn apply_power_change(cmd_tx: &Sender<Command>, app_state: &mut AppState, change: PowerChange) {
+    match change {
+        PowerChange::Device(addr, cmd) => send_power_state(cmd_tx, app_state, addr, cmd),
+        PowerChange::All(cmd) => send_all_power_states(cmd_tx, app_state, cmd),
+        PowerChange::Checked(cmd) => send_checked_power_states(cmd_tx, app_state, cmd),
+        PowerChange::Group(group, cmd) => send_group_power_states(cmd_tx, app_state, group, cmd),
+    }
+}
+
+/// Changes the power state of every member of `group`.
+fn send_group_power_states(
+    cmd_tx: &Sender<Command>,
+    app_state: &mut AppState,
+    group: String,
+    cmd: PowerStateCommand,
+) {
+    let members = app_state.group_members(&group);
+    app_state.push_undo(&members);
+
+    app_state.set_expected_states(&members, cmd);
+
+    cmd_tx
+        .blocking_send(Command::ChangeGroupPowerState(group, cmd))
+        .ok();
+}
+
+/// Changes the power state of the checked devices and clears the checks.
+fn send_checked_power_states(
+    cmd_tx: &Sender<Command>,
+    app_state: &mut AppState,
+    cmd: PowerStateCommand,
+) {
+    let addrs: Vec<_> = app_state.checked.drain().collect();
+    app_state.push_undo(&addrs);
+
+    app_state.set_expected_states(&addrs, cmd);
+
+    cmd_tx
+        .blocking_send(Command::ChangePowerStates(addrs, cmd))
+        .ok();
+}
+
+pub fn send_all_power_states(
+    cmd_tx: &Sender<Command>,
+    app_state: &mut AppState,
+    cmd: PowerStateCommand,
+) {
+    let addrs = app_state.auto_managed();
+    app_state.push_undo(&addrs);
+
+    app_state.set_expected_states(&addrs, cmd);
+
+    cmd_tx
+        .blocking_send(Command::ChangeAllPowerStates(cmd))
+        .ok();
+}
+
+pub fn send_power_state(
+    cmd_tx: &Sender<Command>,
+    app_state: &mut AppState,
+    addr: BDAddr,
+    cmd: PowerStateCommand,
+) {
+    app_state.push_undo(&[addr]);
+
+    app_state.set_expected_states(&[addr], cmd);
+
+    cmd_tx
+        .blocking_send(Command::ChangePowerState(addr, cmd, None))
+        .ok();
+}
+
+/// Returns the devices affected by the most recent power state change to their previous states.
+pub fn undo(cmd_tx: &Sender<Command>, app_state: &mut AppState) {
+    // Kept until it can be carried out, rather than lost to the cooldown
+    if !app_state.can_undo() {
+        return;
+    }
+
+    let entry = match app_state.undo_stack.pop() {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    for (addr, cmd) in entry {
+        app_state.set_expected_states(&[addr], cmd);
+
+        cmd_tx
+            .blocking_send(Command::ChangePowerState(addr, cmd, None))
+            .ok();
+    }
+}
+
+pub fn ui_diagnostics(ui: &mut Ui, app_state: &mut MutexGuard<AppState>) {
+    let stats = &app_state.poll_stats;
+
+    egui::Grid::new("diagnostics_grid")
+        .num_columns(2)
+        .spacing([15.0, 4.0])
+        .show(ui, |ui| {
+            ui.label(t("last_poll"));
+            ui.label(format!("{} ms", stats.last_cycle_time.as_millis()));
+            ui.end_row();
+
+            ui.label(t("average_poll"));
+            ui.label(format!("{} ms", stats.average_cycle_time().as_millis()));
+            ui.end_row();
+
+            ui.label(t("reads_per_second"));
+            ui.label(format!("{:.1}", stats.reads_per_second()));
+            ui.end_row();
+
+            ui.label(t("failure_rate"));
+            ui.label(format!("{:.1} %", stats.failure_rate() * 100.0));
+            ui.end_row();
+        });
+
+    ui.separator();
+
+    if ui
+        .button(t("copy_diagnostics"))
+        .on_hover_text(t("copy_diagnostics_hint"))
+        .clicked()
+    {
+        ui.output().copied_text = diagnostics_report(app_state);
+    }
+
+    ui_tracking_health(ui, app_state);
+
+    egui::CollapsingHeader::new(t("log")).show(ui, |ui| {
+        egui::ScrollArea::vertical()
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for line in logging::recent_lines().iter().rev() {
+                    ui.label(line);
+                }
+            });
+    });
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label(t("on_time"));
+
+        if ui.button(t("copy_csv")).clicked() {
+            ui.output().copied_text = on_time_csv(app_state);
+        }
+
+        if ui.button(t("reset")).clicked() {
+            app_state.config.on_time_secs.clear();
+            app_state.config.save();
+
+            for device in app_state.device_entries.values_mut() {
+                device.session_on_time = Duration::ZERO;
+                device.total_on_time = Duration::ZERO;
+            }
+        }
+    });
+
+    ui.separator();
+    ui_error_counts(ui, &mut app_state.config);
+}
+
+/// OpenVR's view of each base station, for telling apart a station that is off from one that is on
+/// but not tracking well. Only shown while SteamVR reports on the stations.
+fn ui_tracking_health(ui: &mut Ui, app_state: &AppState) {
+    let mut devices: Vec<_> = app_state
+        .device_entries
+        .values()
+        .filter_map(|d| {
+            Some((
+                d.alias.as_deref().or(d.name.as_deref())?,
+                d.tracking.as_ref()?,
+            ))
+        })
+        .collect();
+
+    if devices.is_empty() {
+        return;
+    }
+
+    devices.sort_by_key(|(name, _)| *name);
+
+    egui::CollapsingHeader::new(t("tracking_health")).show(ui, |ui| {
+        egui::Grid::new("tracking_health_grid")
+            .num_columns(3)
+            .spacing([15.0, 4.0])
+            .show(ui, |ui| {
+                ui.strong(t("device"));
+                ui.strong(t("tracking_result"));
+                ui.strong(t("last_pose"));
+                ui.end_row();
+
+                for (name, tracking) in devices {
+                    ui.label(name);
+                    ui.label(&tracking.result);
+                    ui.label(match tracking.pose_updated_at {
+                        Some(at) => tf("seconds_ago", &[&at.elapsed().as_secs()]),
+                        None => t("never").to_string(),
+                    });
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+/// Table of the errors seen with each device across sessions, sortable by clicking a column.
+fn ui_error_counts(ui: &mut Ui, config: &mut Config) {
+    let sort_id = egui::Id::new("error_counts_sort");
+    let mut sort_column = ui.ctx().data().get_temp::<usize>(sort_id).unwrap_or(3);
+
+    ui.horizontal(|ui| {
+        ui.label(t("error_counts"));
+
+        if ui.button(t("reset")).clicked() {
+            config.error_counts.clear();
+            config.save();
+        }
+    });
+
+    if config.error_counts.is_empty() {
+        ui.weak(t("no_errors"));
+        return;
+    }
+
+    let mut rows: Vec<_> = config
+        .error_counts
+        .iter()
+        .map(|(addr, counts)| {
+            let name = config
+                .aliases
+                .get(addr)
+                .or_else(|| config.known_devices.get(addr))
+                .unwrap_or(addr);
+
+            (name.as_str(), *counts)
+        })
+        .collect();
+
+    match sort_column {
+        0 => rows.sort_by_key(|(name, _)| *name),
+        1 => rows.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.read_failures)),
+        2 => rows.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.write_failures)),
+        _ => rows.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.reconnects)),
+    }
+
+    egui::Grid::new("error_counts_grid")
+        .num_columns(4)
+        .striped(true)
+        .spacing([15.0, 4.0])
+        .show(ui, |ui| {
+            for (column, key) in ["device", "read_failures", "write_failures", "reconnects"]
+                .into_iter()
+                .enumerate()
+            {
+                if ui.selectable_label(sort_column == column, t(key)).clicked() {
+                    sort_column = column;
+                }
+            }
+            ui.end_row();
+
+            for (name, counts) in rows {
+                ui.label(name);
+                ui.label(counts.read_failures.to_string());
+                ui.label(counts.write_failures.to_string());
+                ui.label(counts.reconnects.to_string());
+                ui.end_row();
+            }
+        });
+
+    ui.ctx().data().insert_temp(sort_id, sort_column);
+}
+
+/// Collects app, system, and device information for bug reports.
+fn diagnostics_report(app_state: &AppState) -> String {
+    let mut report = format!(
+        "SteamVR Lighthouse Control {}\nOS: {} ({})\n",
+        VERSION,
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+
+    let scan_state = match &app_state.error_state {
+        Some(ErrorState::StartFailed(e)) => format!("start failed ({}), adapter unavailable?", e),
+        None if app_state.scanner.is_active() => "scanning".to_string(),
+        None if app_state.has_scanned => "completed".to_string(),
+        None => "not started".to_string(),
+    };
+
+    report += &format!("Last scan: {}\n", scan_state);
+
+    let stats = &app_state.poll_stats;
+    report += &format!(
+        "Polling: avg {} ms, {:.1} reads/s, {:.1} % failed\n",
+        stats.average_cycle_time().as_millis(),
+        stats.reads_per_second(),
+        stats.failure_rate() * 100.0
+    );
+
+    report += &format!("\nDevices ({}):\n", app_state.device_entries.len());
+
+    for (addr, device) in &app_state.device_entries {
+        report += &format!(
+            "{} name={:?} alias={:?} state={} rssi={:?}\n",
+            addr, device.name, device.alias, device.power_state, device.rssi
+        );
+    }
+
+    report += "\nRecent log:\n";
+
+    for line in logging::recent_lines() {
+        report += &line;
+        report += "\n";
+    }
+
+    report
+}
+
+fn on_time_csv(app_state: &AppState) -> String {
+    let mut csv = String::from("address,name,total_hours,session_hours\n");
+
+    for (addr, device) in &app_state.device_entries {
+        csv += &format!(
+            "{},{},{:.2},{:.2}\n",
+            addr,
+            device
+                .alias
+                .as_ref()
+                .or(device.name.as_ref())
+                .map_or("", |n| n),
+            device.total_on_time.as_secs_f64() / 3600.0,
+            device.session_on_time.as_secs_f64() / 3600.0
+        );
+    }
+
+    csv
+}
+
+/// Returns true if any setting was changed.
+pub fn ui_settings(ui: &mut Ui, config: &mut Config) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label(t("language"));
+
+        let selected = LANGUAGES
+            .iter()
+            .find(|(code, _, _)| *code == config.language)
+            .map_or(config.language.as_str(), |(_, name, _)| name);
+
+        egui::ComboBox::from_id_source("language")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (code, name, _) in LANGUAGES {
+                    if ui
+                        .selectable_value(&mut config.language, code.to_string(), *name)
+                        .changed()
+                    {
+                        set_language(code);
+                        changed = true;
+                    }
+                }
+            });
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("window_title"));
+
+        changed |= ui
+            .add(egui::TextEdit::singleline(&mut config.window_title).hint_text(DEFAULT_TITLE))
+            .on_hover_text(t("window_title_hint"))
+            .changed();
+    });
+
+    changed |= ui
+        .checkbox(
+            &mut config.restore_desired_state,
+            t("restore_desired_state"),
+        )
+        .changed();
+
+    changed |= ui
+        .checkbox(
+            &mut config.panic_off_shortcut,
+            tf("panic_off_shortcut", &[&PANIC_OFF_SHORTCUT]),
+        )
+        .changed();
+
+    changed |= ui
+        .checkbox(
+            &mut config.throttle_polling_while_scanning,
+            t("throttle_polling"),
+        )
+        .changed();
+
+    ui.horizontal(|ui| {
+        ui.label(t("steamvr_interlock"))
+            .on_hover_text(t("steamvr_interlock_hint"));
+
+        for (interlock, label) in [
+            (SteamVrInterlock::Off, "interlock_off"),
+            (SteamVrInterlock::Confirm, "interlock_confirm"),
+            (SteamVrInterlock::Block, "interlock_block"),
+        ] {
+            changed |= ui
+                .radio_value(&mut config.steamvr_interlock, interlock, t(label))
+                .changed();
+        }
+    });
+
+    changed |= ui
+        .checkbox(&mut config.sleep_on_suspend, t("sleep_on_suspend"))
+        .on_hover_text(t("sleep_on_suspend_hint"))
+        .changed();
+
+    ui.add_enabled_ui(config.sleep_on_suspend, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(t("resume_action"));
+
+            for (action, label) in [
+                (ResumeAction::Nothing, "resume_nothing"),
+                (ResumeAction::TurnOn, "resume_turn_on"),
+                (ResumeAction::Restore, "resume_restore"),
+            ] {
+                changed |= ui
+                    .radio_value(&mut config.resume_action, action, t(label))
+                    .changed();
+            }
+        });
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("exit_power_state"));
+
+        for (state, label) in [
+            (ExitPowerState::Unchanged, "exit_unchanged"),
+            (ExitPowerState::Standby, "standby"),
+            (ExitPowerState::Sleep, "sleep"),
+        ] {
+            changed |= ui
+                .radio_value(&mut config.exit_power_state, state, t(label))
+                .changed();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("recognize_by"));
+
+        changed |= ui
+            .radio_value(
+                &mut config.scan_filter,
+                ScanFilter::Characteristic,
+                t("by_characteristic"),
+            )
+            .on_hover_text(t("by_characteristic_hint"))
+            .changed();
+
+        changed |= ui
+            .radio_value(&mut config.scan_filter, ScanFilter::Name, t("by_name"))
+            .on_hover_text(tf("by_name_hint", &[&config.scan_name_prefix]))
+            .changed();
+    });
+
+    changed |= ui
+        .checkbox(&mut config.screen_reader, t("screen_reader"))
+        .on_hover_text(t("screen_reader_hint"))
+        .changed();
+
+    changed |= ui
+        .checkbox(&mut config.acknowledged_writes, t("acknowledged_writes"))
+        .on_hover_text(t("acknowledged_writes_hint"))
+        .changed();
+
+    ui.horizontal(|ui| {
+        ui.label(t("power_cooldown"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.power_cooldown_secs)
+                    .clamp_range(0..=300)
+                    .suffix(" s"),
+            )
+            .on_hover_text(t("power_cooldown_hint"))
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("warm_up"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.warm_up_secs)
+                    .clamp_range(0..=120)
+                    .suffix(" s"),
+            )
+            .on_hover_text(t("warm_up_hint"))
+            .changed();
+    });
+
+    if config.temperature_uuid.is_some() {
+        ui.horizontal(|ui| {
+            ui.label(t("temperature_warning"));
+
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut config.temperature_warning)
+                        .clamp_range(0.0..=100.0)
+                        .suffix(" °C"),
+                )
+                .changed();
+        });
+
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut config.overheat_standby, t("overheat_standby"))
+                .on_hover_text(t("overheat_standby_hint"))
+                .changed();
+
+            changed |= ui
+                .add_enabled(
+                    config.overheat_standby,
+                    egui::DragValue::new(&mut config.overheat_temperature)
+                        .clamp_range(0.0..=100.0)
+                        .suffix(" °C"),
+                )
+                .changed();
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(t("device_timeout"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.operation_timeout_ms)
+                    .clamp_range(500..=60_000)
+                    .speed(100)
+                    .suffix(" ms"),
+            )
+            .on_hover_text(t("device_timeout_hint"))
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("poll_jitter"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.poll_jitter_percent)
+                    .clamp_range(0..=100)
+                    .suffix(" %"),
+            )
+            .on_hover_text(t("poll_jitter_hint"))
+            .changed();
+    });
+
+    changed |= ui
+        .checkbox(
+            &mut config.power_on_with_steamvr,
+            t("power_on_with_steamvr"),
+        )
+        .on_hover_text(t("power_on_with_steamvr_hint"))
+        .changed();
+
+    #[cfg(feature = "openvr")]
+    ui.indent("wake_on_hmd_proximity", |ui| {
+        ui.add_enabled_ui(config.power_on_with_steamvr, |ui| {
+            changed |= ui
+                .checkbox(
+                    &mut config.wake_on_hmd_proximity,
+                    t("wake_on_hmd_proximity"),
+                )
+                .on_hover_text(t("wake_on_hmd_proximity_hint"))
+                .changed();
+        });
+    });
+
+    ui.horizontal(|ui| {
+        changed |= ui
+            .checkbox(&mut config.sleep_after_steamvr, t("sleep_after_steamvr"))
+            .on_hover_text(t("sleep_after_steamvr_hint"))
+            .changed();
+
+        changed |= ui
+            .add_enabled(
+                config.sleep_after_steamvr,
+                egui::DragValue::new(&mut config.steamvr_sleep_delay_mins)
+                    .clamp_range(0..=240)
+                    .suffix(" min"),
+            )
+            .on_hover_text(t("sleep_after_steamvr_hint"))
+            .changed();
+    });
+
+    #[cfg(feature = "openvr")]
+    ui.horizontal(|ui| {
+        changed |= ui
+            .checkbox(&mut config.hmd_idle_standby, t("hmd_idle_standby"))
+            .on_hover_text(t("hmd_idle_standby_hint"))
+            .changed();
+
+        changed |= ui
+            .add_enabled(
+                config.hmd_idle_standby || config.hmd_proximity(),
+                egui::DragValue::new(&mut config.hmd_idle_mins)
+                    .clamp_range(1..=240)
+                    .suffix(" min"),
+            )
+            .on_hover_text(t("hmd_idle_standby_hint"))
+            .changed();
+    });
+
+    #[cfg(feature = "openvr")]
+    {
+        changed |= ui
+            .checkbox(&mut config.standby_with_hmd, t("standby_with_hmd"))
+            .on_hover_text(t("standby_with_hmd_hint"))
+            .changed();
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(t("auto_off_grace"))
+            .on_hover_text(t("auto_off_grace_hint"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.auto_off_grace_secs)
+                    .clamp_range(0..=600)
+                    .suffix(" s"),
+            )
+            .on_hover_text(t("auto_off_grace_hint"))
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("sleep_timer_duration"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.sleep_timer_mins)
+                    .clamp_range(1..=480)
+                    .suffix(" min"),
+            )
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("launch_command"));
+
+        let mut command = config.launch_command.clone().unwrap_or_default();
+
+        if ui
+            .add(egui::TextEdit::singleline(&mut command).hint_text("SteamVR"))
+            .on_hover_text(t("launch_command_hint"))
+            .changed()
+        {
+            config.launch_command = Some(command).filter(|c| !c.trim().is_empty());
+            changed = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t("launch_timeout"));
+
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.launch_timeout_secs)
+                    .clamp_range(5..=600)
+                    .suffix(" s"),
+            )
+            .on_hover_text(t("launch_timeout_hint"))
+            .changed();
+    });
+
+    changed |= ui
+        .checkbox(&mut config.continuous_scan, t("continuous_scan"))
+        .on_hover_text(t("continuous_scan_hint"))
+        .changed();
+
+    changed |= ui
+        .checkbox(&mut config.scan_on_startup, t("scan_on_startup"))
+        .on_hover_text(t("scan_on_startup_hint"))
+        .changed();
+
+    changed |= ui
+        .checkbox(&mut config.direct_connect, t("direct_connect"))
+        .on_hover_text(t("direct_connect_hint"))
+        .changed();
+
+    changed |= ui
+        .checkbox(&mut config.check_for_updates, t("check_for_updates"))
+        .on_hover_text(t("check_for_updates_hint"))
+        .changed();
+
+    ui.separator();
+    ui.weak(tf("version", &[&VERSION]));
+
+    changed
+}
+
+/// Button for filling in aliases from SteamVR, with the outcome of the last import below it.
+pub fn ui_steamvr_import(ui: &mut Ui, app_state: &mut AppState) {
+    let id = egui::Id::new("steamvr_import_result");
+
+    if ui
+        .button(t("import_steamvr"))
+        .on_hover_text(t("import_steamvr_hint"))
+        .clicked()
+    {
+        let result = match steamvr::import_names(app_state) {
+            Ok(summary) => {
+                log::info!(
+                    "Imported {} names from SteamVR, unmatched: {:?}",
+                    summary.named.len(),
+                    summary.unmatched
+                );
+
+                let mut result = tf("imported_names", &[&summary.named.len()]);
+
+                if !summary.unmatched.is_empty() {
+                    result += "\n";
+                    result += &tf("unmatched_names", &[&summary.unmatched.join(", ")]);
+                }
+
+                result
+            }
+            Err(e) => {
+                log::warn!("Could not import names from SteamVR: {}", e);
+                e
+            }
+        };
+
+        ui.ctx().data().insert_temp(id, result);
+    }
+
+    let result = ui.ctx().data().get_temp::<String>(id);
+
+    if let Some(result) = result {
+        ui.weak(result);
+    }
+}
+
+/// Editor for the scheduled power changes. Returns true if any of them was changed.
+pub fn ui_schedules(ui: &mut Ui, config: &mut Config) -> bool {
+    let mut changed = false;
+
+    let mut groups: Vec<String> = config.groups.values().cloned().collect();
+    groups.sort();
+    groups.dedup();
+
+    egui::CollapsingHeader::new(t("schedules")).show(ui, |ui| {
+        let mut removed = None;
+
+        for (i, schedule) in config.schedules.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut schedule.enabled, "").changed();
+
+                let color = if schedule.minute_of_day().is_some() {
+                    ui.visuals().text_color()
+                } else {
+                    Color32::RED
+                };
+
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut schedule.time)
+                            .desired_width(40.0)
+                            .text_color(color),
+                    )
+                    .on_hover_text(t("schedule_time_hint"))
+                    .changed();
+
+                for (day, key) in [
+                    (1, "monday"),
+                    (2, "tuesday"),
+                    (3, "wednesday"),
+                    (4, "thursday"),
+                    (5, "friday"),
+                    (6, "saturday"),
+                    (7, "sunday"),
+                ] {
+                    let selected = schedule.days.contains(&day);
+
+                    if ui
+                        .selectable_label(selected, t(key))
+                        .on_hover_text(t("schedule_days_hint"))
+                        .clicked()
+                    {
+                        if selected {
+                            schedule.days.retain(|d| *d != day);
+                        } else {
+                            schedule.days.push(day);
+                            schedule.days.sort_unstable();
+                        }
+
+                        changed = true;
+                    }
+                }
+
+                let command_label = |cmd: PowerStateCommand| match cmd {
+                    PowerStateCommand::On => t("on"),
+                    PowerStateCommand::Standby => t("standby"),
+                    PowerStateCommand::Sleep => t("sleep"),
+                };
+
+                egui::ComboBox::from_id_source(("schedule_command", i))
+                    .selected_text(command_label(schedule.command))
+                    .show_ui(ui, |ui| {
+                        for cmd in [
+                            PowerStateCommand::On,
+                            PowerStateCommand::Standby,
+                            PowerStateCommand::Sleep,
+                        ] {
+                            changed |= ui
+                                .selectable_value(&mut schedule.command, cmd, command_label(cmd))
+                                .changed();
+                        }
+                    });
+
+                egui::ComboBox::from_id_source(("schedule_group", i))
+                    .selected_text(
+                        schedule
+                            .group
+                            .as_deref()
+                            .unwrap_or_else(|| t("all_base_stations")),
+                    )
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut schedule.group, None, t("all_base_stations"))
+                            .changed();
+
+                        for group in &groups {
+                            changed |= ui
+                                .selectable_value(&mut schedule.group, Some(group.clone()), group)
+                                .changed();
+                        }
+                    });
+
+                if ui.small_button("🗑").on_hover_text(t("remove")).clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+
+        if let Some(i) = removed {
+            config.schedules.remove(i);
+            changed = true;
+        }
+
+        if ui.button(t("add_schedule")).clicked() {
+            config.schedules.push(Schedule::default());
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Status of the SteamVR app registration with a button to change it.
+pub fn ui_steamvr_registration(ui: &mut Ui, config: &Config) {
+    let id = egui::Id::new("steamvr_registration");
+    let config_dir = config.steamvr_config_dir.as_deref();
+
+    // Checking reads SteamVR's configuration, so it is only done once and after a change
+    let (registered, error) = ui
+        .ctx()
+        .data()
+        .get_temp::<(bool, Option<String>)>(id)
+        .unwrap_or_else(|| (steamvr::is_registered(config_dir), None));
+
+    ui.ctx().data().insert_temp(id, (registered, error.clone()));
+
+    ui.horizontal(|ui| {
+        ui.label(if registered {
+            t("steamvr_registered")
+        } else {
+            t("steamvr_not_registered")
+        });
+
+        let label = if registered {
+            t("unregister_steamvr")
+        } else {
+            t("register_steamvr")
+        };
+
+        if ui
+            .button(label)
+            .on_hover_text(t("register_steamvr_hint"))
+            .clicked()
+        {
+            let result = if registered {
+                steamvr::unregister(config_dir)
+            } else {
+                steamvr::register(config_dir)
+            };
+
+            if let Err(e) = &result {
+                log::warn!("SteamVR registration failed: {}", e);
+            }
+
+            ui.ctx()
+                .data()
+                .insert_temp(id, (steamvr::is_registered(config_dir), result.err()));
+        }
+    });
+
+    if let Some(e) = error {
+        ui.weak(e);
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,66 @@
+//! Opt-in check for newer releases on GitHub.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::state::AppState;
+use crate::VERSION;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/urholaukkarinen/steamvr-lighthouse-control/releases/latest";
+pub const RELEASES_PAGE_URL: &str =
+    "https://github.com/urholaukkarinen/steamvr-lighthouse-control/releases";
+
+/// Looks up the latest release and records it in the app state if it is newer than this build.
+/// Failures are only logged.
+pub async fn check(app_state: Arc<Mutex<AppState>>) {
+    let tag = match tokio::task::spawn_blocking(latest_release_tag).await {
+        Ok(Ok(tag)) => tag,
+        Ok(Err(e)) => {
+            log::info!("Could not check for updates: {}", e);
+            return;
+        }
+        Err(e) => {
+            log::info!("Could not check for updates: {:?}", e);
+            return;
+        }
+    };
+
+    if is_newer(&tag, VERSION) {
+        log::info!("A newer version is available: {}", tag);
+        app_state.lock().await.update_available = Some(tag);
+    }
+}
+
+fn latest_release_tag() -> Result<String, String> {
+    let body = ureq::get(LATEST_RELEASE_URL)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+
+    let release: Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+
+    release["tag_name"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "Release has no tag".to_string())
+}
+
+/// Compares dotted version numbers, ignoring a leading `v` and any pre-release suffix.
+fn is_newer(tag: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+
+    parse(tag) > parse(current)
+}
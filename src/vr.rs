@@ -0,0 +1,215 @@
+//! Live information from a running SteamVR through OpenVR, which SteamVR's processes and config
+//! files don't tell: whether the headset is in use or in standby and which base stations are
+//! tracking. Only built with the `openvr` feature, as the
+//! OpenVR SDK has to be built along with it.
+
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use openvr::system::event::Event;
+use openvr::{
+    property, tracked_device_index, ApplicationType, System, TrackedDeviceClass,
+    TrackingUniverseOrigin, MAX_TRACKED_DEVICE_COUNT,
+};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::power::PowerStateCommand;
+use crate::state::{AppState, Command};
+use crate::steamvr::TrackingInfo;
+
+/// How often connecting is tried again while SteamVR is not running.
+const CONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Follows SteamVR on a thread of its own, as the OpenVR calls block. The thread exits once the
+/// backend has stopped.
+pub fn spawn(app_state: Arc<Mutex<AppState>>, cmd_tx: Sender<Command>) {
+    std::thread::spawn(move || {
+        while !cmd_tx.is_closed() {
+            // A background application doesn't start SteamVR, so this fails until it is running
+            // SAFETY: OpenVR is only used from this thread, and the context is shut down before
+            // the next one is created.
+            match unsafe { openvr::init(ApplicationType::Background) } {
+                Ok(context) => {
+                    log::info!("Connected to SteamVR");
+
+                    if let Ok(system) = context.system() {
+                        follow(&system, &app_state, &cmd_tx);
+                    }
+
+                    {
+                        let mut app_state = app_state.blocking_lock();
+                        app_state.hmd_idle_since = None;
+                        apply_tracking(&mut app_state, &[]);
+                    }
+
+                    unsafe { context.shutdown() };
+                }
+                Err(e) => log::trace!("Could not connect to SteamVR: {:?}", e),
+            }
+
+            sleep(CONNECT_INTERVAL);
+        }
+    });
+}
+
+/// Follows the headset and the base stations until SteamVR quits or the backend stops.
+fn follow(system: &System, app_state: &Mutex<AppState>, cmd_tx: &Sender<Command>) {
+    // Set while the devices are in standby because the headset was idle
+    let mut idle_standby = false;
+    // Set while the devices are in standby because the headset is
+    let mut hmd_standby = false;
+
+    while !cmd_tx.is_closed() {
+        while let Some((info, _)) =
+            system.poll_next_event_with_pose(TrackingUniverseOrigin::Standing)
+        {
+            let hmd = info.tracked_device_index == tracked_device_index::HMD;
+
+            match info.event {
+                Event::Quit(_) => {
+                    system.acknowledge_quit_exiting();
+                    log::info!("SteamVR is quitting");
+                    return;
+                }
+                Event::TrackedDeviceUserInteractionStarted if hmd => {
+                    let (revert, proximity) = {
+                        let mut app_state = app_state.blocking_lock();
+                        app_state.hmd_idle_since = None;
+                        (
+                            app_state.take_auto_off_revert(),
+                            app_state.config.hmd_proximity(),
+                        )
+                    };
+
+                    // Right after the standby the devices may still be in their cooldown, so the
+                    // ones it changed are woken regardless of it
+                    if !revert.is_empty() {
+                        idle_standby = false;
+
+                        log::info!("The headset is in use again, waking the devices right away");
+                        let (reply_tx, _) = oneshot::channel();
+                        cmd_tx
+                            .blocking_send(Command::SendPowerStates(
+                                revert,
+                                PowerStateCommand::On,
+                                reply_tx,
+                            ))
+                            .ok();
+                    } else if idle_standby || proximity {
+                        idle_standby = false;
+
+                        log::info!("The headset is in use again, turning all devices on");
+                        cmd_tx
+                            .blocking_send(Command::ChangeAllPowerStates(PowerStateCommand::On))
+                            .ok();
+                    }
+                }
+                Event::TrackedDeviceUserInteractionEnded if hmd => {
+                    app_state.blocking_lock().hmd_idle_since = Some(Instant::now());
+                }
+                Event::EnterStandbyMode if app_state.blocking_lock().config.standby_with_hmd => {
+                    hmd_standby = true;
+
+                    log::info!("The headset went to standby, putting all devices in standby");
+                    cmd_tx
+                        .blocking_send(Command::ChangeAllPowerStates(PowerStateCommand::Standby))
+                        .ok();
+                }
+                Event::LeaveStandbyMode if hmd_standby => {
+                    hmd_standby = false;
+
+                    log::info!("The headset woke up, turning all devices on");
+                    cmd_tx
+                        .blocking_send(Command::ChangeAllPowerStates(PowerStateCommand::On))
+                        .ok();
+                }
+                _ => {}
+            }
+        }
+
+        let stations = read_stations(system);
+
+        let idle = {
+            let mut app_state = app_state.blocking_lock();
+            apply_tracking(&mut app_state, &stations);
+
+            let delay = app_state.config.hmd_idle_delay();
+
+            let idle = (app_state.config.hmd_idle_standby || app_state.config.hmd_proximity())
+                && app_state
+                    .hmd_idle_since
+                    .is_some_and(|at| at.elapsed() >= delay);
+
+            if idle && !idle_standby {
+                app_state.record_auto_off();
+            }
+
+            idle
+        };
+
+        if idle && !idle_standby {
+            idle_standby = true;
+
+            log::info!("The headset has been idle, putting all devices in standby");
+            cmd_tx
+                .blocking_send(Command::ChangeAllPowerStates(PowerStateCommand::Standby))
+                .ok();
+        }
+
+        sleep(POLL_INTERVAL);
+    }
+}
+
+/// Returns the serial numbers of the base stations SteamVR knows of, with their tracking
+/// information.
+fn read_stations(system: &System) -> Vec<(String, TrackingInfo)> {
+    let poses = system.device_to_absolute_tracking_pose(TrackingUniverseOrigin::Standing, 0.0);
+
+    (0..MAX_TRACKED_DEVICE_COUNT)
+        .filter(|i| system.tracked_device_class(*i as u32) == TrackedDeviceClass::TrackingReference)
+        .filter_map(|i| {
+            // An empty serial would match every device by its name
+            let serial = system
+                .string_tracked_device_property(i as u32, property::SerialNumber_String)
+                .ok()
+                .map(|serial| serial.to_string_lossy().to_ascii_uppercase())
+                .filter(|serial| !serial.is_empty())?;
+            let pose = &poses[i];
+            let tracking = pose.device_is_connected() && pose.pose_is_valid();
+
+            Some((
+                serial,
+                TrackingInfo {
+                    tracking,
+                    result: format!("{:?}", pose.tracking_result()),
+                    pose_updated_at: tracking.then(Instant::now),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Records the tracking information on the devices, matched by serial number like the lighthouse
+/// database. SteamVR knows 2.0 base stations by their advertised name, e.g. `LHB-1234ABCD`.
+fn apply_tracking(app_state: &mut AppState, stations: &[(String, TrackingInfo)]) {
+    for device in app_state.device_entries.values_mut() {
+        let name = device
+            .name
+            .as_deref()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        let previous_pose = device.tracking.as_ref().and_then(|t| t.pose_updated_at);
+
+        device.tracking = stations
+            .iter()
+            .find(|(serial, _)| name.ends_with(serial.as_str()))
+            .map(|(_, info)| TrackingInfo {
+                pose_updated_at: info.pose_updated_at.or(previous_pose),
+                ..info.clone()
+            });
+    }
+}
@@ -0,0 +1,110 @@
+//! Outbound webhooks, posting a JSON payload to user configured URLs whenever a device changes
+//! state.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bleasy::BDAddr;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::power::PowerState;
+use crate::state::AppState;
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_ATTEMPTS: u32 = 5;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Watches the polled device states and notifies `urls` of every transition.
+pub async fn run(app_state: Arc<Mutex<AppState>>, urls: Vec<String>) {
+    let mut last_states: HashMap<BDAddr, (PowerState, bool)> = HashMap::new();
+
+    loop {
+        let payloads: Vec<_> = {
+            let app_state = app_state.lock().await;
+            let warm_up = app_state.config.warm_up();
+            let mut payloads = Vec::new();
+
+            for (addr, device) in &app_state.device_entries {
+                let state = match device.history.back() {
+                    Some((_, state)) => *state,
+                    None => continue,
+                };
+
+                // A device that has warmed up is reported again, now as ready
+                let ready = device.is_ready(warm_up);
+
+                // Devices seen for the first time have no transition to report
+                if let Some((old_state, was_ready)) = last_states.insert(*addr, (state, ready)) {
+                    if old_state != state || was_ready != ready {
+                        payloads.push(json!({
+                            "address": addr.to_string(),
+                            "name": device.alias.as_ref().or(device.name.as_ref()),
+                            "old_state": old_state.to_string(),
+                            "new_state": state.to_string(),
+                            "ready": ready,
+                            "timestamp": unix_time(),
+                        }));
+                    }
+                }
+            }
+
+            payloads
+        };
+
+        for payload in payloads {
+            for url in &urls {
+                tokio::task::spawn(deliver(url.clone(), payload.clone()));
+            }
+        }
+
+        sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Posts `payload` to `url`, retrying with an increasing delay if it fails.
+async fn deliver(url: String, payload: Value) {
+    let mut delay = FIRST_RETRY_DELAY;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = tokio::task::spawn_blocking({
+            let url = url.clone();
+            let payload = payload.clone();
+
+            move || {
+                ureq::post(&url)
+                    .set("Content-Type", "application/json")
+                    .send_string(&payload.to_string())
+                    .map(|_| ())
+                    // ureq's error holds the whole response, only its message is needed
+                    .map_err(|e| e.to_string())
+            }
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => log::warn!("Webhook {} failed (attempt {}): {}", url, attempt, e),
+            Err(e) => log::warn!("Webhook {} failed (attempt {}): {:?}", url, attempt, e),
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    log::error!(
+        "Giving up on webhook {} after {} attempts",
+        url,
+        MAX_ATTEMPTS
+    );
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}